@@ -0,0 +1,417 @@
+use std::{fmt::Display, io, str::FromStr, sync::Arc};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::types::{ProcessingPlan, Purchase};
+
+/// What should happen when a rule fires.
+///
+/// Written as `kind` or `kind(argument, ..)`, e.g. `packing_slip(royalty)`, with arguments
+/// quoted like `email(owner, "Re: a, b")` when they hold `,`, `(`, `)` or `"`.
+/// Any other text is kept as a custom, free-text action, quoted when it would read as another
+/// action.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum ProcessAction {
+	/// `packing_slip` or `packing_slip(department)` for a duplicate slip.
+	PackingSlip {
+		department: Option<Arc<str>>
+	},
+	/// `attach(item)`, adds an item to the packing slip.
+	AttachItem {
+		item: Arc<str>
+	},
+	/// `activate_membership`
+	ActivateMembership,
+	/// `upgrade_membership`
+	UpgradeMembership,
+	/// `email(recipient, subject)`
+	Email {
+		recipient: Arc<str>,
+		subject:   Arc<str>
+	},
+	/// `commission(recipient)`
+	Commission {
+		recipient: Arc<str>
+	},
+	Custom(Arc<str>)
+}
+impl ProcessAction {
+	pub const SYNTAX_HELP: &'static str = "packing_slip, packing_slip(department), \
+	                                              attach(item), activate_membership, \
+	                                              upgrade_membership, email(recipient, subject), \
+	                                              commission(recipient) or any other text, with \
+	                                              \"quotes\" around arguments holding commas or \
+	                                              parentheses";
+
+	/// Human readable sentence for processing output.
+	pub fn description(&self) -> String {
+		match self {
+			ProcessAction::PackingSlip { department: None } => {
+				"generate a packing slip for shipping".to_string()
+			},
+			ProcessAction::PackingSlip {
+				department: Some(department)
+			} => format!(
+				"create a duplicate packing slip for the {} department",
+				department
+			),
+			ProcessAction::AttachItem { item } => format!("add '{}' to the packing slip", item),
+			ProcessAction::ActivateMembership => "activate the membership".to_string(),
+			ProcessAction::UpgradeMembership => "apply the membership upgrade".to_string(),
+			ProcessAction::Email { recipient, subject } => {
+				format!("e-mail the {} about '{}'", recipient, subject)
+			},
+			ProcessAction::Commission { recipient } => {
+				format!("generate a commission payment to the {}", recipient)
+			},
+			ProcessAction::Custom(description) => description.to_string()
+		}
+	}
+}
+impl From<&str> for ProcessAction {
+	fn from(value: &str) -> Self {
+		value.parse().unwrap_or_else(|()| {
+			ProcessAction::Custom(match unquote(value.trim()) {
+				Some(text) => text.into(),
+				None => value.into()
+			})
+		})
+	}
+}
+impl FromStr for ProcessAction {
+	type Err = ();
+
+	/// Fails when the text is not one of the typed action kinds.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		let (kind, arguments) = match s.split_once('(') {
+			Some((kind, rest)) => (kind.trim(), read_arguments(rest).ok_or(())?),
+			None => (s, Vec::new())
+		};
+		let arguments = arguments.iter().map(String::as_str).collect::<Vec<_>>();
+		Ok(match (kind, arguments.as_slice()) {
+			("packing_slip", []) => ProcessAction::PackingSlip { department: None },
+			("packing_slip", [department]) => ProcessAction::PackingSlip {
+				department: Some((*department).into())
+			},
+			("attach", [item]) => ProcessAction::AttachItem {
+				item: (*item).into()
+			},
+			("activate_membership", []) => ProcessAction::ActivateMembership,
+			("upgrade_membership", []) => ProcessAction::UpgradeMembership,
+			("email", [recipient, subject]) => ProcessAction::Email {
+				recipient: (*recipient).into(),
+				subject:   (*subject).into()
+			},
+			("commission", [recipient]) => ProcessAction::Commission {
+				recipient: (*recipient).into()
+			},
+			_ => return Err(())
+		})
+	}
+}
+/// Reads the arguments after the opening parenthesis up to the closing one, which has to end
+/// the text. Unquoted arguments can't be empty.
+fn read_arguments(mut rest: &str) -> Option<Vec<String>> {
+	let mut arguments = Vec::new();
+	loop {
+		rest = rest.trim_start();
+		if rest.starts_with('"') {
+			let (argument, after) = read_quoted(rest)?;
+			arguments.push(argument);
+			rest = after.trim_start();
+		} else {
+			let end = rest.find([',', ')'])?;
+			let argument = rest[..end].trim();
+			if argument.is_empty() || argument.contains(['(', '"']) {
+				return None;
+			}
+			arguments.push(argument.to_string());
+			rest = &rest[end..];
+		}
+		match rest.chars().next()? {
+			',' => rest = &rest[1..],
+			')' => return rest[1..].trim().is_empty().then_some(arguments),
+			_ => return None
+		}
+	}
+}
+/// Reads the quoted text `s` starts with, returning it and what follows it.
+fn read_quoted(s: &str) -> Option<(String, &str)> {
+	let mut chars = s.strip_prefix('"')?.char_indices();
+	let mut text = String::new();
+	while let Some((index, c)) = chars.next() {
+		match c {
+			// past the opening and the closing quote
+			'"' => return Some((text, &s[index + 2..])),
+			'\\' => match chars.next()? {
+				(_, escaped @ ('"' | '\\')) => text.push(escaped),
+				_ => return None
+			},
+			c => text.push(c)
+		}
+	}
+	None
+}
+/// The text inside the quotes when `s` is nothing but quoted text.
+fn unquote(s: &str) -> Option<String> {
+	match read_quoted(s)? {
+		(text, "") => Some(text),
+		_ => None
+	}
+}
+fn write_quoted(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+	write!(f, "\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+fn write_argument(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+	let is_bare =
+		!text.is_empty() && text.trim() == text && !text.contains([',', '(', ')', '"', '\\']);
+	if is_bare {
+		write!(f, "{}", text)
+	} else {
+		write_quoted(f, text)
+	}
+}
+impl Display for ProcessAction {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ProcessAction::PackingSlip { department: None } => write!(f, "packing_slip"),
+			ProcessAction::PackingSlip {
+				department: Some(department)
+			} => {
+				write!(f, "packing_slip(")?;
+				write_argument(f, department)?;
+				write!(f, ")")
+			},
+			ProcessAction::AttachItem { item } => {
+				write!(f, "attach(")?;
+				write_argument(f, item)?;
+				write!(f, ")")
+			},
+			ProcessAction::ActivateMembership => write!(f, "activate_membership"),
+			ProcessAction::UpgradeMembership => write!(f, "upgrade_membership"),
+			ProcessAction::Email { recipient, subject } => {
+				write!(f, "email(")?;
+				write_argument(f, recipient)?;
+				write!(f, ", ")?;
+				write_argument(f, subject)?;
+				write!(f, ")")
+			},
+			ProcessAction::Commission { recipient } => {
+				write!(f, "commission(")?;
+				write_argument(f, recipient)?;
+				write!(f, ")")
+			},
+			ProcessAction::Custom(description) => {
+				let is_bare = !description.is_empty()
+					&& description.trim() == description.as_ref()
+					&& !description.starts_with('"')
+					&& description.parse::<ProcessAction>().is_err();
+				if is_bare {
+					write!(f, "{}", description)
+				} else {
+					write_quoted(f, description)
+				}
+			}
+		}
+	}
+}
+
+/// Older rule files store actions as plain text.
+pub fn deserialize_action<'de, D>(deserializer: D) -> Result<ProcessAction, D::Error>
+where
+	D: Deserializer<'de>
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum StoredAction {
+		Structured(ProcessAction),
+		Text(String)
+	}
+	Ok(match StoredAction::deserialize(deserializer)? {
+		StoredAction::Structured(action) => action,
+		StoredAction::Text(text) => ProcessAction::from(text.as_str())
+	})
+}
+
+/// What an action is carried out for.
+#[derive(Debug, Clone, Copy)]
+pub struct ActionContext<'a> {
+	/// `None` for order-wide actions.
+	pub purchase:        Option<&'a Purchase>,
+	pub quantity:        usize,
+	/// Equal for every run over the same order reference and purchase, so executors can skip
+	/// work that was already done.
+	pub idempotency_key: &'a str
+}
+impl Display for ActionContext<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.purchase {
+			Some(purchase) => write!(f, "'{}' (x{})", purchase.title, self.quantity),
+			None => write!(f, "the whole order")
+		}
+	}
+}
+
+pub trait ActionExecutor {
+	fn execute(&mut self, action: &ProcessAction, context: ActionContext) -> io::Result<()>;
+	/// Called once every step of a plan has been executed.
+	fn finish(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// 64-bit FNV-1a, which unlike the std hashers is stable between builds.
+pub fn stable_hash(parts: &[&str]) -> u64 {
+	let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+	for part in parts {
+		for byte in part.bytes().chain([0]) {
+			hash ^= u64::from(byte);
+			hash = hash.wrapping_mul(0x0100_0000_01b3);
+		}
+	}
+	hash
+}
+/// Lines of the same order only share a key when they are the same purchase, down to its ID.
+pub fn idempotency_key(reference: &str, purchase: Option<&Purchase>) -> String {
+	let id = purchase
+		.map(|purchase| purchase.id.to_string())
+		.unwrap_or_default();
+	let mut parts = vec![reference];
+	if let Some(purchase) = purchase {
+		parts.push(&id);
+		parts.push(&purchase.title);
+		parts.extend(
+			purchase
+				.identifiers
+				.0
+				.iter()
+				.map(|identifier| identifier.0.as_ref())
+		);
+	}
+	format!("{:016x}", stable_hash(&parts))
+}
+
+/// Records what would have been done, without any side effects.
+#[derive(Debug, Default)]
+pub struct DryRunExecutor {
+	pub log: Vec<String>
+}
+impl ActionExecutor for DryRunExecutor {
+	fn execute(&mut self, action: &ProcessAction, context: ActionContext) -> io::Result<()> {
+		self.log
+			.push(format!("would {} for {}", action.description(), context));
+		Ok(())
+	}
+}
+
+impl ProcessingPlan {
+	/// Runs every step in processing order, purchases first and then the whole order.
+	/// `reference` identifies the order, running the same plan twice with the same reference
+	/// gives every step the same idempotency key.
+	pub fn execute(&self, executor: &mut impl ActionExecutor, reference: &str) -> io::Result<()> {
+		for line in &self.lines {
+			let key = idempotency_key(reference, Some(&line.purchase));
+			let context = ActionContext {
+				purchase:        Some(&line.purchase),
+				quantity:        line.quantity,
+				idempotency_key: &key
+			};
+			for step in &line.steps {
+				executor.execute(step, context)?;
+			}
+		}
+		let key = idempotency_key(reference, None);
+		let context = ActionContext {
+			purchase:        None,
+			quantity:        1,
+			idempotency_key: &key
+		};
+		for step in &self.order_steps {
+			executor.execute(step, context)?;
+		}
+		executor.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_actions() {
+		for action in [
+			ProcessAction::PackingSlip { department: None },
+			ProcessAction::PackingSlip {
+				department: Some("royalty".into())
+			},
+			ProcessAction::AttachItem {
+				item: "First Aid video".into()
+			},
+			ProcessAction::ActivateMembership,
+			ProcessAction::Email {
+				recipient: "owner".into(),
+				subject:   "membership activated".into()
+			},
+			ProcessAction::Custom("give me the keyboard or muffin".into())
+		] {
+			assert_eq!(ProcessAction::from(action.to_string().as_str()), action);
+		}
+		assert_eq!(
+			ProcessAction::from("attach()"),
+			ProcessAction::Custom("attach()".into())
+		);
+		assert_eq!(
+			ProcessAction::from("email(owner)"),
+			ProcessAction::Custom("email(owner)".into())
+		);
+		assert_eq!(
+			ProcessAction::from(r#"email(owner, "Re: a, b")"#),
+			ProcessAction::Email {
+				recipient: "owner".into(),
+				subject:   "Re: a, b".into()
+			}
+		);
+	}
+
+	#[test]
+	fn round_trips() {
+		let texts = [
+			"royalty",
+			"First Aid video",
+			"",
+			" padded ",
+			"Re: a, b",
+			"(draft)",
+			r#"say "hi""#,
+			r"back\slash",
+			"\"quoted\"",
+			"packing_slip",
+			"attach(x)",
+			"email(owner, Re: a, b)",
+			"give me the keyboard or muffin"
+		];
+		for text in texts {
+			let text: Arc<str> = text.into();
+			for action in [
+				ProcessAction::PackingSlip {
+					department: Some(text.clone())
+				},
+				ProcessAction::AttachItem { item: text.clone() },
+				ProcessAction::Email {
+					recipient: text.clone(),
+					subject:   text.clone()
+				},
+				ProcessAction::Email {
+					recipient: "owner".into(),
+					subject:   text.clone()
+				},
+				ProcessAction::Commission {
+					recipient: text.clone()
+				},
+				ProcessAction::Custom(text.clone())
+			] {
+				let printed = action.to_string();
+				assert_eq!(ProcessAction::from(printed.as_str()), action, "{}", printed);
+			}
+		}
+	}
+}
@@ -0,0 +1,402 @@
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use super::actions::{stable_hash, ActionContext, ActionExecutor, ProcessAction};
+
+/// Writes every packing slip of a purchase once all of its steps are known, so attached items
+/// end up on the slip regardless of the order the rules fired in.
+#[derive(Debug)]
+pub struct PackingSlipWriter {
+	directory: PathBuf,
+	pending:   BTreeMap<String, PendingSlip>,
+	pub log:   Vec<String>
+}
+#[derive(Debug, Default)]
+struct PendingSlip {
+	subject:     String,
+	departments: BTreeSet<Option<Arc<str>>>,
+	items:       Vec<Arc<str>>
+}
+impl PackingSlipWriter {
+	pub fn new(directory: PathBuf) -> Self {
+		PackingSlipWriter {
+			directory,
+			pending: BTreeMap::new(),
+			log: Vec::new()
+		}
+	}
+}
+impl ActionExecutor for PackingSlipWriter {
+	fn execute(&mut self, action: &ProcessAction, context: ActionContext) -> io::Result<()> {
+		let pending = || PendingSlip {
+			subject: context.to_string(),
+			..Default::default()
+		};
+		match action {
+			ProcessAction::PackingSlip { department } => {
+				self.pending
+					.entry(context.idempotency_key.to_string())
+					.or_insert_with(pending)
+					.departments
+					.insert(department.clone());
+			},
+			ProcessAction::AttachItem { item } => {
+				self.pending
+					.entry(context.idempotency_key.to_string())
+					.or_insert_with(pending)
+					.items
+					.push(item.clone());
+			},
+			_ => {}
+		}
+		Ok(())
+	}
+	fn finish(&mut self) -> io::Result<()> {
+		if self.pending.is_empty() {
+			return Ok(());
+		}
+		fs::create_dir_all(&self.directory)?;
+		for (key, mut slip) in std::mem::take(&mut self.pending) {
+			// items still have to be shipped with something
+			if slip.departments.is_empty() {
+				slip.departments.insert(None);
+			}
+			for department in &slip.departments {
+				let name = match department {
+					Some(department) => format!("{}-{}.txt", key, file_safe(department)),
+					None => format!("{}.txt", key)
+				};
+				let mut contents = format!("Packing slip {}\n", key);
+				if let Some(department) = department {
+					contents += &format!("Duplicate for the {} department\n", department);
+				}
+				contents += &format!("For: {}\n", slip.subject);
+				for item in &slip.items {
+					contents += &format!("Attached: {}\n", item);
+				}
+				write_once(&self.directory.join(name), &contents, &mut self.log)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Leaves one `.eml` file per e-mail in an outbox directory for a mail client to pick up.
+#[derive(Debug)]
+pub struct EmailOutbox {
+	directory: PathBuf,
+	pub log:   Vec<String>
+}
+impl EmailOutbox {
+	pub fn new(directory: PathBuf) -> Self {
+		EmailOutbox {
+			directory,
+			log: Vec::new()
+		}
+	}
+}
+impl ActionExecutor for EmailOutbox {
+	fn execute(&mut self, action: &ProcessAction, context: ActionContext) -> io::Result<()> {
+		let ProcessAction::Email { recipient, subject } = action else {
+			return Ok(());
+		};
+		fs::create_dir_all(&self.directory)?;
+		let name = format!(
+			"{}-{:016x}.eml",
+			context.idempotency_key,
+			stable_hash(&[recipient, subject])
+		);
+		let contents = format!(
+			"X-Idempotency-Key: {}\r\nTo: {}\r\nSubject: {}\r\n\r\nProcessing {}: {}.\r\n",
+			context.idempotency_key,
+			single_line(recipient),
+			single_line(subject),
+			context,
+			action.description()
+		);
+		write_once(&self.directory.join(name), &contents, &mut self.log)
+	}
+}
+
+/// Appends commission payments to a CSV ledger, never rewriting earlier rows. The ledger is
+/// locked while it is checked for a row and appended to, so runs in parallel can't both write it.
+#[derive(Debug)]
+pub struct CommissionLedger {
+	path:    PathBuf,
+	pub log: Vec<String>
+}
+impl CommissionLedger {
+	const HEADER: [&'static str; 4] = ["idempotency_key", "recipient", "purchase", "quantity"];
+
+	pub fn new(path: PathBuf) -> Self {
+		CommissionLedger {
+			path,
+			log: Vec::new()
+		}
+	}
+	/// Idempotency key and recipient of every row.
+	fn read_recorded(file: &File) -> io::Result<BTreeSet<(String, String)>> {
+		let mut recorded = BTreeSet::new();
+		for row in csv::ReaderBuilder::new()
+			.flexible(true)
+			.from_reader(file)
+			.records()
+		{
+			let row = row?;
+			if let (Some(key), Some(recipient)) = (row.get(0), row.get(1)) {
+				recorded.insert((key.to_string(), recipient.to_string()));
+			}
+		}
+		Ok(recorded)
+	}
+}
+impl ActionExecutor for CommissionLedger {
+	fn execute(&mut self, action: &ProcessAction, context: ActionContext) -> io::Result<()> {
+		let ProcessAction::Commission { recipient } = action else {
+			return Ok(());
+		};
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let file = OpenOptions::new()
+			.create(true)
+			.read(true)
+			.append(true)
+			.open(&self.path)?;
+		// released when the file is dropped
+		file.lock()?;
+		let entry = (context.idempotency_key.to_string(), recipient.to_string());
+		if Self::read_recorded(&file)?.contains(&entry) {
+			self.log.push(format!(
+				"skipped commission to {} for {}, already in the ledger",
+				recipient, context
+			));
+			return Ok(());
+		}
+		let is_new = file.metadata()?.len() == 0;
+		let mut writer = csv::Writer::from_writer(&file);
+		if is_new {
+			writer.write_record(Self::HEADER)?;
+		}
+		let purchase = context
+			.purchase
+			.map(|purchase| purchase.title.to_string())
+			.unwrap_or_default();
+		writer.write_record([
+			entry.0.as_str(),
+			entry.1.as_str(),
+			&purchase,
+			&context.quantity.to_string()
+		])?;
+		writer.flush()?;
+		self.log.push(format!(
+			"recorded commission to {} for {} in {}",
+			recipient,
+			context,
+			self.path.display()
+		));
+		Ok(())
+	}
+}
+
+/// All file-writing executors, laid out inside a single directory.
+/// Membership and custom actions have no artifact and are left alone.
+#[derive(Debug)]
+pub struct ArtifactExecutor {
+	pub packing_slips: PackingSlipWriter,
+	pub outbox:        EmailOutbox,
+	pub ledger:        CommissionLedger
+}
+impl ArtifactExecutor {
+	pub fn in_directory(directory: &Path) -> Self {
+		ArtifactExecutor {
+			packing_slips: PackingSlipWriter::new(directory.join("packing_slips")),
+			outbox:        EmailOutbox::new(directory.join("outbox")),
+			ledger:        CommissionLedger::new(directory.join("commission_ledger.csv"))
+		}
+	}
+	pub fn log(&self) -> impl Iterator<Item = &String> {
+		self.packing_slips
+			.log
+			.iter()
+			.chain(&self.outbox.log)
+			.chain(&self.ledger.log)
+	}
+}
+impl ActionExecutor for ArtifactExecutor {
+	fn execute(&mut self, action: &ProcessAction, context: ActionContext) -> io::Result<()> {
+		self.packing_slips.execute(action, context)?;
+		self.outbox.execute(action, context)?;
+		self.ledger.execute(action, context)
+	}
+	fn finish(&mut self) -> io::Result<()> {
+		self.packing_slips.finish()?;
+		self.outbox.finish()?;
+		self.ledger.finish()
+	}
+}
+
+/// Existing files were written by an earlier run with the same idempotency key.
+fn write_once(path: &Path, contents: &str, log: &mut Vec<String>) -> io::Result<()> {
+	match OpenOptions::new().write(true).create_new(true).open(path) {
+		Ok(mut file) => {
+			file.write_all(contents.as_bytes())?;
+			log.push(format!("wrote {}", path.display()));
+		},
+		Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+			log.push(format!("skipped {}, already written", path.display()));
+		},
+		Err(error) => return Err(error)
+	}
+	Ok(())
+}
+fn file_safe(name: &str) -> String {
+	name.chars()
+		.map(|c| {
+			if c.is_alphanumeric() || c == '-' {
+				c
+			} else {
+				'_'
+			}
+		})
+		.collect()
+}
+fn single_line(text: &str) -> String { text.replace(['\r', '\n'], " ") }
+
+#[cfg(test)]
+mod tests {
+	use chrono::Utc;
+
+	use super::*;
+	use crate::{
+		testing::{purchase, rule, TempDir},
+		types::{Order, PurchaseCollection}
+	};
+
+	#[test]
+	fn artifacts_are_written_once() {
+		let directory = TempDir::new("artifacts");
+		let rules = [
+			rule("slip", "packing_slip", "always"),
+			rule("royalty", "packing_slip(royalty)", "always"),
+			rule("video", "attach(First Aid video)", "always"),
+			rule("mail", "email(owner, new order)", "always"),
+			rule("commission", "commission(agent)", "always")
+		]
+		.into();
+		let order = Order {
+			purchases: PurchaseCollection::single(purchase("Book", &["book"]))
+		};
+		let plan = order.get_processing_plan(&rules, Utc::now());
+
+		let mut first = ArtifactExecutor::in_directory(directory.path());
+		plan.execute(&mut first, "order-1").unwrap();
+		assert_eq!(
+			first
+				.log()
+				.filter(|entry| entry.starts_with("wrote"))
+				.count(),
+			3
+		);
+		let mut second = ArtifactExecutor::in_directory(directory.path());
+		plan.execute(&mut second, "order-1").unwrap();
+		assert!(second.log().all(|entry| entry.starts_with("skipped")));
+		let ledger = fs::read_to_string(directory.join("commission_ledger.csv")).unwrap();
+		assert_eq!(ledger.lines().count(), 2);
+
+		let mut other = ArtifactExecutor::in_directory(directory.path());
+		plan.execute(&mut other, "order-2").unwrap();
+		let ledger = fs::read_to_string(directory.join("commission_ledger.csv")).unwrap();
+		assert_eq!(ledger.lines().count(), 3);
+	}
+
+	#[test]
+	fn lines_alike_but_for_their_id_keep_their_own_artifacts() {
+		let directory = TempDir::new("artifacts-lines");
+		let rules = [
+			rule("slip", "packing_slip", "always"),
+			rule("commission", "commission(agent)", "always")
+		]
+		.into();
+		let order = Order {
+			purchases: PurchaseCollection(
+				[
+					(purchase("Book", &["book"]), 1),
+					(purchase("Book", &["book"]), 1)
+				]
+				.into()
+			)
+		};
+		let plan = order.get_processing_plan(&rules, Utc::now());
+		let mut executor = ArtifactExecutor::in_directory(directory.path());
+		plan.execute(&mut executor, "order-1").unwrap();
+		assert_eq!(
+			executor
+				.log()
+				.filter(|entry| entry.starts_with("wrote"))
+				.count(),
+			2
+		);
+		let ledger = fs::read_to_string(directory.join("commission_ledger.csv")).unwrap();
+		assert_eq!(ledger.lines().count(), 3);
+	}
+
+	#[test]
+	fn ledger_reads_back_quoted_rows() {
+		let directory = TempDir::new("ledger");
+		let path = directory.join("commission_ledger.csv");
+		let purchase = purchase("Skis, \"new\"\nand waxed", &[]);
+		let action = ProcessAction::Commission {
+			recipient: "agent, north".into()
+		};
+		for _ in 0..2 {
+			let mut ledger = CommissionLedger::new(path.clone());
+			let context = ActionContext {
+				purchase:        Some(&purchase),
+				quantity:        1,
+				idempotency_key: "order-1\nline"
+			};
+			ledger.execute(&action, context).unwrap();
+		}
+		let rows = csv::Reader::from_path(&path)
+			.unwrap()
+			.records()
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(rows.len(), 1);
+		assert_eq!(&rows[0][2], purchase.title.as_ref());
+	}
+
+	#[test]
+	fn parallel_runs_write_the_ledger_once() {
+		let directory = TempDir::new("ledger-parallel");
+		let path = directory.join("commission_ledger.csv");
+		let runs = (0..8)
+			.map(|_| {
+				let path = path.clone();
+				std::thread::spawn(move || {
+					let action = ProcessAction::Commission {
+						recipient: "agent".into()
+					};
+					let context = ActionContext {
+						purchase:        None,
+						quantity:        1,
+						idempotency_key: "order-1"
+					};
+					CommissionLedger::new(path)
+						.execute(&action, context)
+						.unwrap();
+				})
+			})
+			.collect::<Vec<_>>();
+		for run in runs {
+			run.join().unwrap();
+		}
+		assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+	}
+}
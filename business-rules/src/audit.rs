@@ -0,0 +1,330 @@
+use std::{
+	env,
+	fmt::Display,
+	fs::{read_to_string, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf}
+};
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, from_value, to_string, to_value, Value};
+
+use super::{
+	dataset::{ApplicationData, PathFindable, Saved},
+	storage::Change,
+	types::{EntryId, Purchase, Rule}
+};
+
+pub const AUDIT_LOG_FILE: &str = "audit_log.jsonl";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub enum EventKind {
+	Edit,
+	/// Reverted the edit with the given id.
+	Undo {
+		of: u64
+	},
+	/// Repeated the edit with the given id after it was undone.
+	Redo {
+		of: u64
+	}
+}
+
+/// A single change to the data, as stored in the append-only audit log.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct AuditEvent {
+	pub id:     u64,
+	pub at:     String,
+	pub user:   String,
+	/// `Purchase` or `Rule`.
+	pub entry:  String,
+	pub kind:   EventKind,
+	/// `None` when the entry was added.
+	pub before: Option<Value>,
+	/// `None` when the entry was deleted.
+	pub after:  Option<Value>
+}
+impl AuditEvent {
+	fn verb(&self) -> &'static str {
+		match (&self.before, &self.after) {
+			(None, _) => "added",
+			(Some(_), Some(_)) => "modified",
+			(Some(_), None) => "deleted"
+		}
+	}
+	/// Whether the entry had the title or ID before or after the event.
+	fn mentions(&self, title_or_id: &str) -> bool {
+		[&self.before, &self.after]
+			.into_iter()
+			.flatten()
+			.any(|snapshot| {
+				let has_title = snapshot
+					.get("title")
+					.and_then(Value::as_str)
+					.is_some_and(|found| found.trim().eq_ignore_ascii_case(title_or_id.trim()));
+				let has_id = snapshot
+					.get("id")
+					.and_then(|id| from_value::<EntryId>(id.clone()).ok())
+					.is_some_and(|id| id.matches(title_or_id));
+				has_title || has_id
+			})
+	}
+	/// Shows a snapshot the way the entry itself is shown.
+	fn describe(&self, snapshot: &Value) -> String {
+		let described = match self.entry.as_str() {
+			"Purchase" => from_value::<Purchase>(snapshot.clone()).map(|entry| entry.to_string()),
+			"Rule" => from_value::<Rule>(snapshot.clone()).map(|entry| entry.to_string()),
+			_ => Ok(snapshot.to_string())
+		};
+		described.unwrap_or_else(|_| snapshot.to_string())
+	}
+}
+impl Display for AuditEvent {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "#{} {} {} ", self.id, self.at, self.user)?;
+		match self.kind {
+			EventKind::Edit => {},
+			EventKind::Undo { of } => write!(f, "undid #{} and ", of)?,
+			EventKind::Redo { of } => write!(f, "redid #{} and ", of)?
+		}
+		write!(f, "{} a {}", self.verb(), self.entry.to_lowercase())?;
+		if let Some(before) = &self.before {
+			write!(f, "\nBefore:\n{}", self.describe(before))?;
+		}
+		if let Some(after) = &self.after {
+			write!(f, "\nAfter:\n{}", self.describe(after))?;
+		}
+		Ok(())
+	}
+}
+
+/// The audit log kept next to the data file at `data_path`.
+pub fn log_path(data_path: &Path) -> PathBuf { data_path.with_file_name(AUDIT_LOG_FILE) }
+
+pub fn read_log(path: &Path) -> io::Result<Vec<AuditEvent>> {
+	let contents = match read_to_string(path) {
+		Ok(contents) => contents,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(error) => return Err(error)
+	};
+	contents
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.map(|line| from_str(line).map_err(io::Error::from))
+		.collect()
+}
+
+/// Appends an event for a change that was just applied to the data at `data_path`.
+pub fn record<T: Saved>(
+	data_path: &Path,
+	change: &Change<T>,
+	kind: EventKind
+) -> io::Result<AuditEvent> {
+	let path = log_path(data_path);
+	let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+	// held until the event is written, so concurrent writers never reuse an id
+	file.lock()?;
+	let id = read_log(&path)?.last().map_or(1, |last| last.id + 1);
+	let snapshot = |entry: Option<&T>| entry.map(to_value).transpose();
+	let event = AuditEvent {
+		id,
+		at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+		user: env::var("USER")
+			.or_else(|_| env::var("USERNAME"))
+			.unwrap_or_else(|_| "unknown".to_string()),
+		entry: T::type_name_pretty().to_string(),
+		kind,
+		before: snapshot(change.before())?,
+		after: snapshot(change.after())?
+	};
+	writeln!(file, "{}", to_string(&event)?)?;
+	Ok(event)
+}
+
+/// The edits that can currently be undone and redone, most recent last.
+fn undo_redo_stacks(log: &[AuditEvent]) -> (Vec<&AuditEvent>, Vec<&AuditEvent>) {
+	let mut undoable: Vec<&AuditEvent> = Vec::new();
+	let mut redoable: Vec<&AuditEvent> = Vec::new();
+	for event in log {
+		match event.kind {
+			EventKind::Edit => {
+				undoable.push(event);
+				redoable.clear();
+			},
+			EventKind::Undo { of } => {
+				if let Some(index) = undoable.iter().rposition(|edit| edit.id == of) {
+					redoable.push(undoable.remove(index));
+				}
+			},
+			EventKind::Redo { of } => {
+				if let Some(index) = redoable.iter().rposition(|edit| edit.id == of) {
+					undoable.push(redoable.remove(index));
+				}
+			}
+		}
+	}
+	(undoable, redoable)
+}
+
+#[derive(Debug)]
+pub enum UndoError {
+	/// There is nothing to undo or redo.
+	Empty,
+	/// The entry was changed since, so the edit can't be reverted or repeated as is.
+	Conflict {
+		id:    u64,
+		entry: String
+	},
+	Io(io::Error)
+}
+impl Display for UndoError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			UndoError::Empty => write!(f, "There is nothing to do."),
+			UndoError::Conflict { id, entry } => write!(
+				f,
+				"The {} of #{} was changed since, so it can't be done automatically.",
+				entry.to_lowercase(),
+				id
+			),
+			UndoError::Io(error) => write!(f, "IO error: {}", error)
+		}
+	}
+}
+impl From<io::Error> for UndoError {
+	fn from(value: io::Error) -> Self { UndoError::Io(value) }
+}
+
+/// Reverts the most recent edit that hasn't been undone yet.
+pub fn undo(data: &ApplicationData) -> Result<AuditEvent, UndoError> {
+	let log = read_log(&log_path(&data.purchase_path))?;
+	let (undoable, _) = undo_redo_stacks(&log);
+	let edit = undoable.last().ok_or(UndoError::Empty)?;
+	replay(data, edit, true, EventKind::Undo { of: edit.id })
+}
+/// Repeats the most recently undone edit.
+pub fn redo(data: &ApplicationData) -> Result<AuditEvent, UndoError> {
+	let log = read_log(&log_path(&data.purchase_path))?;
+	let (_, redoable) = undo_redo_stacks(&log);
+	let edit = redoable.last().ok_or(UndoError::Empty)?;
+	replay(data, edit, false, EventKind::Redo { of: edit.id })
+}
+fn replay(
+	data: &ApplicationData,
+	edit: &AuditEvent,
+	reverse: bool,
+	kind: EventKind
+) -> Result<AuditEvent, UndoError> {
+	match edit.entry.as_str() {
+		"Purchase" => replay_as::<Purchase>(data, edit, reverse, kind),
+		"Rule" => replay_as::<Rule>(data, edit, reverse, kind),
+		other => Err(UndoError::Io(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("unknown entry '{}' in the audit log", other)
+		)))
+	}
+}
+fn replay_as<T: Saved + PathFindable>(
+	data: &ApplicationData,
+	edit: &AuditEvent,
+	reverse: bool,
+	kind: EventKind
+) -> Result<AuditEvent, UndoError> {
+	let typed = |snapshot: &Option<Value>| {
+		snapshot
+			.clone()
+			.map(from_value::<T>)
+			.transpose()
+			.map_err(io::Error::from)
+	};
+	let (mut before, mut after) = (typed(&edit.before)?, typed(&edit.after)?);
+	if reverse {
+		(before, after) = (after, before);
+	}
+	let change = match (before, after) {
+		(None, Some(new)) => Change::Insert(new),
+		(Some(old), Some(new)) => Change::Replace { old, new },
+		(Some(old), None) => Change::Remove(old),
+		(None, None) => {
+			return Err(UndoError::Conflict {
+				id:    edit.id,
+				entry: edit.entry.clone()
+			})
+		},
+	};
+	match T::apply_recorded(T::get_path(data), &change, kind)? {
+		Some(event) => Ok(event),
+		None => Err(UndoError::Conflict {
+			id:    edit.id,
+			entry: edit.entry.clone()
+		})
+	}
+}
+
+/// Every event that involved an entry with the title or ID, oldest first.
+pub fn history(data: &ApplicationData, title_or_id: &str) -> io::Result<Vec<AuditEvent>> {
+	let mut log = read_log(&log_path(&data.purchase_path))?;
+	log.retain(|event| event.mentions(title_or_id));
+	Ok(log)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{collections::BTreeSet, fs};
+
+	use super::*;
+	use crate::testing::{self, TempDir};
+
+	#[test]
+	fn undoes_and_redoes_edits() {
+		let directory = TempDir::new("audit");
+		let data = ApplicationData::new(
+			directory.join("all_purchases.json"),
+			directory.join("all_rules.json")
+		);
+		fs::write(&data.purchase_path, "").unwrap();
+		// renaming keeps the ID
+		let id = EntryId::generate();
+		let purchase = |title: &str| Purchase {
+			id,
+			..testing::purchase(title, &[])
+		};
+		let path = Purchase::get_path(&data);
+		Purchase::apply_to_disk(path, &Change::Insert(purchase("1984"))).unwrap();
+		let renaming = Change::Replace {
+			old: purchase("1984"),
+			new: purchase("Animal Farm")
+		};
+		Purchase::apply_to_disk(path, &renaming).unwrap();
+		Purchase::apply_to_disk(path, &Change::Remove(purchase("Animal Farm"))).unwrap();
+		assert!(Purchase::load_from_disk(path).unwrap().is_empty());
+
+		assert_eq!(undo(&data).unwrap().kind, EventKind::Undo { of: 3 });
+		assert_eq!(undo(&data).unwrap().kind, EventKind::Undo { of: 2 });
+		assert_eq!(
+			Purchase::load_from_disk(path).unwrap(),
+			BTreeSet::from([purchase("1984")])
+		);
+		assert_eq!(redo(&data).unwrap().kind, EventKind::Redo { of: 2 });
+		assert_eq!(
+			Purchase::load_from_disk(path).unwrap(),
+			BTreeSet::from([purchase("Animal Farm")])
+		);
+
+		// a new edit means the undone removal can't be redone anymore
+		let other = Purchase {
+			id: EntryId::generate(),
+			..purchase("1984")
+		};
+		Purchase::apply_to_disk(path, &Change::Insert(other)).unwrap();
+		assert!(matches!(redo(&data), Err(UndoError::Empty)));
+		let ids = |events: Vec<AuditEvent>| events.iter().map(|event| event.id).collect::<Vec<_>>();
+		assert_eq!(ids(history(&data, "animal farm").unwrap()), [2, 3, 4, 5, 6]);
+		assert_eq!(ids(history(&data, "1984").unwrap()), [1, 2, 5, 6, 7]);
+		assert_eq!(
+			ids(history(&data, &id.to_string()[..8]).unwrap()),
+			[1, 2, 3, 4, 5, 6]
+		);
+	}
+}
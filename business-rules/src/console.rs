@@ -0,0 +1,63 @@
+//! Where the interactive menus show text and read the replies of the user.
+
+use std::{collections::VecDeque, fmt::Debug, sync::Mutex};
+
+/// Shows text and reads replies, so the menus can be run without a terminal.
+pub trait Console: Debug + Send + Sync {
+	/// Shows a line of text.
+	fn write_line(&self, line: &str);
+	/// Shows text without ending the line, before a reply is read.
+	fn write(&self, text: &str);
+	/// The next reply, `None` once there are no replies left.
+	fn read_line(&self) -> Option<String>;
+}
+
+/// Replays recorded replies in order and keeps everything shown, replies included.
+#[derive(Debug, Default)]
+pub struct ScriptedConsole {
+	replies:    Mutex<VecDeque<String>>,
+	transcript: Mutex<String>
+}
+impl ScriptedConsole {
+	pub fn new<S: Into<String>>(replies: impl IntoIterator<Item = S>) -> Self {
+		ScriptedConsole {
+			replies:    Mutex::new(replies.into_iter().map(Into::into).collect()),
+			transcript: Mutex::default()
+		}
+	}
+	/// Everything shown so far, with each reply after the text shown before it.
+	pub fn transcript(&self) -> String { self.transcript.lock().unwrap().clone() }
+	/// How many replies were not read yet.
+	pub fn remaining(&self) -> usize { self.replies.lock().unwrap().len() }
+}
+impl Console for ScriptedConsole {
+	fn write_line(&self, line: &str) {
+		let mut transcript = self.transcript.lock().unwrap();
+		transcript.push_str(line);
+		transcript.push('\n');
+	}
+	fn write(&self, text: &str) { self.transcript.lock().unwrap().push_str(text); }
+	fn read_line(&self) -> Option<String> {
+		let reply = self.replies.lock().unwrap().pop_front()?;
+		self.write_line(&reply);
+		Some(reply)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn replays_replies_into_the_transcript() {
+		let console = ScriptedConsole::new(["yes", "Corsair"]);
+		console.write_line("Continue? (Y/N)");
+		console.write("> ");
+		assert_eq!(console.read_line().as_deref(), Some("yes"));
+		console.write("> ");
+		assert_eq!(console.read_line().as_deref(), Some("Corsair"));
+		assert_eq!(console.read_line(), None);
+		assert_eq!(console.remaining(), 0);
+		assert_eq!(console.transcript(), "Continue? (Y/N)\n> yes\n> Corsair\n");
+	}
+}
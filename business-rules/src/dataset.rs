@@ -0,0 +1,128 @@
+//! Where the purchase and rule data is kept, and loading and saving it.
+
+use std::{
+	collections::BTreeSet,
+	io,
+	path::{Path, PathBuf},
+	sync::Arc
+};
+
+use crate::{
+	audit::{record, AuditEvent, EventKind},
+	console::{Console, ScriptedConsole},
+	storage::{storage_for, Change, Stored},
+	types::{Purchase, Rule},
+	validation::{validate_rules, Finding}
+};
+
+#[derive(Debug)]
+pub struct ApplicationData {
+	pub purchase_path: PathBuf,
+	pub rule_path:     PathBuf,
+	/// Asked whenever the user has to decide something, nobody answers unless one is given.
+	pub console:       Arc<dyn Console>
+}
+impl ApplicationData {
+	pub const DATABASE_FILE: &'static str = "business_rules.db";
+
+	pub fn new(
+		purchase_path: impl Into<PathBuf>,
+		rule_path: impl Into<PathBuf>
+	) -> ApplicationData {
+		ApplicationData {
+			purchase_path: purchase_path.into(),
+			rule_path:     rule_path.into(),
+			console:       Arc::new(ScriptedConsole::default())
+		}
+	}
+	pub fn with_console(self, console: Arc<dyn Console>) -> ApplicationData {
+		ApplicationData { console, ..self }
+	}
+
+	/// Uses the SQLite database once it has been created by migrating, the JSON files otherwise.
+	pub fn from_src_path(path: impl AsRef<Path>) -> ApplicationData {
+		let database_path = path.as_ref().join(Self::DATABASE_FILE);
+		if database_path.exists() {
+			ApplicationData::new(database_path.clone(), database_path)
+		} else {
+			Self::json_in(path)
+		}
+	}
+	pub fn json_in(path: impl AsRef<Path>) -> ApplicationData {
+		let directory = path.as_ref();
+		ApplicationData::new(
+			directory.join("all_purchases.json"),
+			directory.join("all_rules.json")
+		)
+	}
+}
+
+pub trait PathFindable {
+	fn get_path(data: &ApplicationData) -> &Path;
+}
+impl PathFindable for Purchase {
+	fn get_path(data: &ApplicationData) -> &Path { data.purchase_path.as_path() }
+}
+impl PathFindable for Rule {
+	fn get_path(data: &ApplicationData) -> &Path { data.rule_path.as_path() }
+}
+
+pub trait Saved
+where
+	Self: Stored
+{
+	/// Only reads, the IDs given to entries saved before IDs existed are kept by the next save.
+	fn load_from_disk(path: &Path) -> io::Result<BTreeSet<Self>> {
+		storage_for::<Self>(path).load()
+	}
+	/// Compare with a later version to find out whether the data changed in the meantime.
+	fn disk_version(path: &Path) -> io::Result<u64> { storage_for::<Self>(path).version() }
+	fn save_to_disk(path: &Path, set: &BTreeSet<Self>) -> io::Result<()> {
+		let storage = storage_for(path);
+		let _lock = storage.lock()?;
+		storage.save(set)
+	}
+	/// Changes a single entry, leaving concurrent changes to other entries intact.
+	/// See `Change::apply_to` for what the result means.
+	fn apply_to_disk(path: &Path, change: &Change<Self>) -> io::Result<bool> {
+		Ok(Self::apply_recorded(path, change, EventKind::Edit)?.is_some())
+	}
+	/// Applies the change and adds it to the audit log, returning the event if it applied.
+	fn apply_recorded(
+		path: &Path,
+		change: &Change<Self>,
+		kind: EventKind
+	) -> io::Result<Option<AuditEvent>> {
+		let storage = storage_for(path);
+		let _lock = storage.lock()?;
+		if !storage.apply(change)? {
+			return Ok(None);
+		}
+		record(path, change, kind).map(Some)
+	}
+	/// Applies many changes with a single save, logging each one that applied.
+	/// Returns how many did.
+	fn apply_all_recorded(path: &Path, changes: &[Change<Self>]) -> io::Result<usize> {
+		let storage = storage_for(path);
+		let _lock = storage.lock()?;
+		let mut set = storage.load()?;
+		let applied = changes
+			.iter()
+			.filter(|change| change.apply_to(&mut set))
+			.collect::<Vec<_>>();
+		if applied.is_empty() {
+			return Ok(0);
+		}
+		storage.save(&set)?;
+		for change in &applied {
+			record(path, change, EventKind::Edit)?;
+		}
+		Ok(applied.len())
+	}
+	/// Problems with the data, worth reporting before saving it.
+	fn validate_set(_set: &BTreeSet<Self>) -> Vec<Finding> { Vec::new() }
+}
+impl Saved for Purchase {}
+impl Saved for Rule {
+	fn validate_set(set: &BTreeSet<Self>) -> Vec<Finding> { validate_rules(set, None) }
+}
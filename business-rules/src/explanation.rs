@@ -0,0 +1,340 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::types::{
+	rule_outcomes, AttributeValue, CombinationCondition, IdentifierCondition, OrderLine, Purchase,
+	PurchaseCollection, Rule, RuleOutcome, RuleTrigger
+};
+
+/// How a trigger was evaluated, one node per sub-trigger.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Trace {
+	pub label:    String,
+	pub result:   bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub detail:   Option<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub children: Vec<Trace>
+}
+impl Trace {
+	fn leaf(trigger: &RuleTrigger, result: bool, detail: Option<String>) -> Self {
+		Trace {
+			label: trigger.to_string(),
+			result,
+			detail,
+			children: Vec::new()
+		}
+	}
+	fn write_indented(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+		write!(
+			f,
+			"{}[{}] {}",
+			"  ".repeat(depth),
+			if self.result { "yes" } else { "no" },
+			self.label
+		)?;
+		if let Some(detail) = &self.detail {
+			write!(f, ": {}", detail)?;
+		}
+		for child in &self.children {
+			writeln!(f)?;
+			child.write_indented(f, depth + 1)?;
+		}
+		Ok(())
+	}
+}
+impl Display for Trace {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { self.write_indented(f, 0) }
+}
+
+impl RuleTrigger {
+	/// Evaluates the trigger for a purchase on its own, like `triggered_by`, keeping every step.
+	pub fn explain(&self, purchase: &Purchase) -> Trace {
+		let order = PurchaseCollection::single(purchase.clone());
+		self.explain_on(&OrderLine {
+			purchase,
+			quantity: 1,
+			order: &order
+		})
+	}
+
+	/// Gives the same result as `triggered_on`.
+	pub fn explain_on(&self, line: &OrderLine) -> Trace {
+		let purchase = line.purchase;
+		let counted = |label: String, triggers: &[RuleTrigger], is_met: &dyn Fn(usize) -> bool| {
+			let children = triggers
+				.iter()
+				.map(|trigger| trigger.explain_on(line))
+				.collect::<Vec<_>>();
+			let count = children.iter().filter(|child| child.result).count();
+			Trace {
+				label,
+				result: is_met(count),
+				detail: Some(format!("{} of {} true", count, children.len())),
+				children
+			}
+		};
+		match self {
+			RuleTrigger::Never => Trace::leaf(self, false, None),
+			RuleTrigger::Always => Trace::leaf(self, true, None),
+			RuleTrigger::Title { name } => Trace::leaf(
+				self,
+				purchase.title.to_lowercase().trim() == name.to_lowercase().trim(),
+				Some(format!("the title is '{}'", purchase.title))
+			),
+			RuleTrigger::Identifier {
+				identifiers,
+				condition
+			} => {
+				let (matched, missing): (Vec<_>, Vec<_>) = identifiers
+					.0
+					.iter()
+					.partition(|identifier| purchase.has_identifier(identifier));
+				let result = match condition {
+					IdentifierCondition::Any => !matched.is_empty(),
+					IdentifierCondition::All => missing.is_empty(),
+					IdentifierCondition::None => matched.is_empty()
+				};
+				let list = |identifiers: Vec<_>| {
+					identifiers
+						.iter()
+						.map(ToString::to_string)
+						.collect::<Vec<_>>()
+						.join(", ")
+				};
+				let detail = match (matched.is_empty(), missing.is_empty()) {
+					(true, true) => "no identifiers to match".to_string(),
+					(false, true) => format!("has {}", list(matched)),
+					(true, false) => format!("lacks {}", list(missing)),
+					(false, false) => format!("has {}; lacks {}", list(matched), list(missing))
+				};
+				Trace::leaf(self, result, Some(detail))
+			},
+			RuleTrigger::Combination { a, b, condition } => {
+				let (a, b) = (a.explain_on(line), b.explain_on(line));
+				let (label, result) = match condition {
+					CombinationCondition::None => ("nor", !(a.result || b.result)),
+					CombinationCondition::ExactlyOne => ("xor", a.result != b.result),
+					CombinationCondition::Either => ("or", a.result || b.result),
+					CombinationCondition::Both => ("and", a.result && b.result)
+				};
+				Trace {
+					label: label.to_string(),
+					result,
+					detail: None,
+					children: vec![a, b]
+				}
+			},
+			RuleTrigger::Not { flipped_rule } => {
+				let flipped = flipped_rule.explain_on(line);
+				Trace {
+					label:    "not".to_string(),
+					result:   !flipped.result,
+					detail:   None,
+					children: vec![flipped]
+				}
+			},
+			RuleTrigger::AllOf(triggers) => counted("all_of".to_string(), triggers, &|count| {
+				count == triggers.len()
+			}),
+			RuleTrigger::AnyOf(triggers) => {
+				counted("any_of".to_string(), triggers, &|count| count > 0)
+			},
+			RuleTrigger::NoneOf(triggers) => {
+				counted("none_of".to_string(), triggers, &|count| count == 0)
+			},
+			RuleTrigger::AtLeast(needed, triggers) => {
+				counted(format!("at_least({})", needed), triggers, &|count| {
+					count >= *needed
+				})
+			},
+			RuleTrigger::Quantity { comparison, amount } => Trace::leaf(
+				self,
+				comparison.compare(&line.quantity, amount),
+				Some(format!("the quantity is {}", line.quantity))
+			),
+			RuleTrigger::OrderSize { comparison, amount } => Trace::leaf(
+				self,
+				comparison.compare(&line.order.size(), amount),
+				Some(format!("the order has {} items", line.order.size()))
+			),
+			RuleTrigger::OrderContains { other } => {
+				let children = line
+					.order
+					.lines()
+					.filter(|other_line| other_line.purchase != purchase)
+					.map(|other_line| {
+						let inner = other.explain_on(&other_line);
+						Trace {
+							label:    format!("'{}'", other_line.purchase.title),
+							result:   inner.result,
+							detail:   None,
+							children: vec![inner]
+						}
+					})
+					.collect::<Vec<_>>();
+				Trace {
+					label: "order_contains".to_string(),
+					result: children.iter().any(|child| child.result),
+					detail: children
+						.is_empty()
+						.then(|| "no other purchases in the order".to_string()),
+					children
+				}
+			},
+			RuleTrigger::TitleContains { .. }
+			| RuleTrigger::TitleGlob { .. }
+			| RuleTrigger::TitleRegex { .. } => Trace::leaf(
+				self,
+				self.triggered_on(line),
+				Some(format!("the title is '{}'", purchase.title))
+			),
+			RuleTrigger::IdentifierPrefix { prefix } => {
+				let matched = purchase
+					.identifiers
+					.0
+					.iter()
+					.filter(|identifier| identifier.0.starts_with(prefix.as_ref()))
+					.map(ToString::to_string)
+					.collect::<Vec<_>>();
+				let detail = if matched.is_empty() {
+					"no identifier starts with it".to_string()
+				} else {
+					format!("has {}", matched.join(", "))
+				};
+				Trace::leaf(self, !matched.is_empty(), Some(detail))
+			},
+			RuleTrigger::Attribute { key, .. } => {
+				let detail = match purchase.attributes.0.get(key) {
+					Some(AttributeValue::Text(text)) => format!("{} is '{}'", key, text),
+					Some(value) => format!("{} is {}", key, value),
+					None => format!("there is no attribute {}", key)
+				};
+				Trace::leaf(self, self.triggered_on(line), Some(detail))
+			}
+		}
+	}
+}
+
+/// Why a rule did or didn't fire for a purchase.
+#[derive(Debug, Serialize)]
+pub struct RuleExplanation<'a> {
+	#[serde(serialize_with = "serialize_title")]
+	pub rule:    &'a Rule,
+	pub outcome: RuleOutcome,
+	pub trace:   Trace
+}
+fn serialize_title<S: serde::Serializer>(rule: &&Rule, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.serialize_str(&rule.title)
+}
+impl Display for RuleExplanation<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let outcome = match &self.outcome {
+			RuleOutcome::Fired => "fired".to_string(),
+			RuleOutcome::NotTriggered => "did not fire, its trigger didn't match".to_string(),
+			RuleOutcome::Inactive => format!(
+				"did not fire, it is only {}",
+				self.rule.validity().unwrap_or_default()
+			),
+			RuleOutcome::Excluded { group } => format!(
+				"did not fire, an earlier rule of exclusion group '{}' fired",
+				group
+			),
+			RuleOutcome::Stopped { by } => {
+				format!("was not considered, rule '{}' stopped processing", by)
+			}
+		};
+		writeln!(f, "Rule '{}' {}:", self.rule.title, outcome)?;
+		write!(f, "{}", self.trace)
+	}
+}
+
+impl Purchase {
+	/// Every rule in processing order, as used by `get_processing_steps`.
+	pub fn explain_processing<'a>(
+		&self,
+		rules: &'a BTreeSet<Rule>,
+		at: DateTime<Utc>
+	) -> Vec<RuleExplanation<'a>> {
+		let mut traces = rules
+			.iter()
+			.map(|rule| (rule, rule.trigger.explain(self)))
+			.collect::<Vec<_>>();
+		let outcomes = rule_outcomes(rules.iter(), at, |rule| {
+			traces
+				.iter()
+				.any(|(traced, trace)| *traced == rule && trace.result)
+		});
+		outcomes
+			.into_iter()
+			.map(|(rule, outcome)| {
+				let index = traces
+					.iter()
+					.position(|(traced, _)| *traced == rule)
+					.expect("every rule was traced");
+				RuleExplanation {
+					rule,
+					outcome,
+					trace: traces.swap_remove(index).1
+				}
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{self, purchase};
+
+	#[test]
+	fn explains_triggers() {
+		let purchase = purchase("Learning to Ski", &["video", "physical"]);
+		let trigger: RuleTrigger = "title(\"First Aid\") or (any(video, book) and not \
+		                            all(physical, digital))"
+			.parse()
+			.unwrap();
+		let trace = trigger.explain(&purchase);
+		assert_eq!(trace.result, trigger.triggered_by(&purchase));
+		assert_eq!(
+			trace.to_string(),
+			"[yes] or
+  [no] title(\"First Aid\"): the title is 'Learning to Ski'
+  [yes] and
+    [yes] any(book, video): has 'video'; lacks 'book'
+    [yes] not
+      [no] all(digital, physical): has 'physical'; lacks 'digital'"
+		);
+	}
+
+	#[test]
+	fn explains_rule_outcomes() {
+		let rule = |title: &str, trigger: &str, stop_processing| Rule {
+			stop_processing,
+			..testing::rule(title, "packing_slip", trigger)
+		};
+		let rules = BTreeSet::from([
+			rule("a", "any(video)", false),
+			rule("b", "any(book)", false),
+			rule("c", "always", true),
+			rule("d", "always", false)
+		]);
+		let purchase = purchase("Learning to Ski", &["video"]);
+		let outcomes = purchase
+			.explain_processing(&rules, Utc::now())
+			.into_iter()
+			.map(|explanation| explanation.outcome)
+			.collect::<Vec<_>>();
+		assert_eq!(
+			outcomes,
+			[
+				RuleOutcome::Fired,
+				RuleOutcome::NotTriggered,
+				RuleOutcome::Fired,
+				RuleOutcome::Stopped { by: "c".into() }
+			]
+		);
+	}
+}
@@ -0,0 +1,732 @@
+//! One-line text syntax for rule triggers, for example:
+//! `title("Learning to Ski") or (any(book, physical) and not all(digital))`
+//!
+//! Operators from loosest to tightest binding: `or`/`nor`, `xor`, `and`, `not`.
+//! Binary operators are left associative, so the printed form of a trigger
+//! always parses back into the exact same tree. Longer lists of triggers are
+//! written as `all_of(..)`, `any_of(..)`, `none_of(..)` and `at_least(n, ..)`.
+//! Order-level triggers are `quantity >= 3`, `order_size > 10` and
+//! `order_contains(any(book))`. Titles can also be matched with `title_contains(ski)`,
+//! `title_glob("Learning*")` and `title_regex("^Learn")`, identifiers by their start with
+//! `ident_prefix(membership:)` and attributes with comparisons like `price > 100`, where an
+//! attribute named like a keyword is quoted: `"quantity" = 2`.
+
+use std::{
+	fmt::Display,
+	iter::Peekable,
+	str::{CharIndices, FromStr}
+};
+
+use super::types::{
+	AttributeValue, CombinationCondition, Comparison, Identifier, IdentifierCollection,
+	IdentifierCondition, RuleTrigger
+};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+	pub column:  usize,
+	pub message: String
+}
+impl ParseError {
+	/// The source line with a caret pointing at the failing column.
+	pub fn pointed(&self, source: &str) -> String {
+		format!(
+			"{}\n{}^ {}",
+			source,
+			" ".repeat(self.column.saturating_sub(1)),
+			self.message
+		)
+	}
+}
+impl Display for ParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "column {}: {}", self.column, self.message)
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum TokenKind {
+	Word(String),
+	Quoted(String),
+	OpenParen,
+	CloseParen,
+	Comma,
+	Comparison(Comparison),
+	End
+}
+impl Display for TokenKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			TokenKind::Word(word) => write!(f, "'{}'", word),
+			TokenKind::Quoted(text) => write!(f, "\"{}\"", text),
+			TokenKind::OpenParen => write!(f, "'('"),
+			TokenKind::CloseParen => write!(f, "')'"),
+			TokenKind::Comma => write!(f, "','"),
+			TokenKind::Comparison(comparison) => write!(f, "'{}'", comparison),
+			TokenKind::End => write!(f, "end of input")
+		}
+	}
+}
+#[derive(Debug, Clone)]
+struct Token {
+	kind:   TokenKind,
+	column: usize
+}
+
+fn is_word_char(c: char) -> bool { c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':') }
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+	let mut tokens = Vec::new();
+	let mut chars: Peekable<CharIndices> = source.char_indices().peekable();
+	let column_of = |byte_index: usize| source[..byte_index].chars().count() + 1;
+	while let Some((index, c)) = chars.next() {
+		let column = column_of(index);
+		let kind = match c {
+			c if c.is_whitespace() => continue,
+			'(' => TokenKind::OpenParen,
+			')' => TokenKind::CloseParen,
+			',' => TokenKind::Comma,
+			'<' | '>' | '=' | '!' => {
+				let mut symbol = String::from(c);
+				if let Some((_, next)) = chars.next_if(|(_, next)| *next == '=') {
+					symbol.push(next);
+				}
+				match Comparison::from_symbol(&symbol) {
+					Some(comparison) => TokenKind::Comparison(comparison),
+					None => {
+						return Err(ParseError {
+							column,
+							message: format!("unknown comparison '{}'", symbol)
+						});
+					}
+				}
+			},
+			'"' => {
+				let mut text = String::new();
+				'string_loop: loop {
+					match chars.next() {
+						Some((_, '"')) => break 'string_loop,
+						Some((_, '\\')) => match chars.next() {
+							Some((_, escaped @ ('"' | '\\'))) => text.push(escaped),
+							Some((escape_index, other)) => {
+								return Err(ParseError {
+									column:  column_of(escape_index),
+									message: format!("unknown escape '\\{}'", other)
+								});
+							},
+							None => {
+								return Err(ParseError {
+									column,
+									message: "unterminated string".to_string()
+								});
+							}
+						},
+						Some((_, other)) => text.push(other),
+						None => {
+							return Err(ParseError {
+								column,
+								message: "unterminated string".to_string()
+							});
+						}
+					}
+				}
+				TokenKind::Quoted(text)
+			},
+			c if is_word_char(c) => {
+				let mut word = String::from(c);
+				while let Some((_, next)) = chars.next_if(|(_, next)| is_word_char(*next)) {
+					word.push(next);
+				}
+				TokenKind::Word(word)
+			},
+			other => {
+				return Err(ParseError {
+					column,
+					message: format!("unexpected character '{}'", other)
+				});
+			}
+		};
+		tokens.push(Token { kind, column });
+	}
+	tokens.push(Token {
+		kind:   TokenKind::End,
+		column: source.chars().count() + 1
+	});
+	Ok(tokens)
+}
+
+struct Parser {
+	tokens:   Vec<Token>,
+	position: usize
+}
+impl Parser {
+	fn peek(&self) -> &Token { &self.tokens[self.position] }
+
+	fn advance(&mut self) -> Token {
+		let token = self.tokens[self.position].clone();
+		if token.kind != TokenKind::End {
+			self.position += 1;
+		}
+		token
+	}
+
+	fn peek_second(&self) -> &Token { &self.tokens[(self.position + 1).min(self.tokens.len() - 1)] }
+
+	fn peek_keyword(&self) -> Option<String> {
+		match &self.peek().kind {
+			TokenKind::Word(word) => Some(word.to_lowercase()),
+			_ => None
+		}
+	}
+
+	fn error_here(&self, message: impl Into<String>) -> ParseError {
+		ParseError {
+			column:  self.peek().column,
+			message: message.into()
+		}
+	}
+
+	fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+		if self.peek().kind == kind {
+			self.advance();
+			Ok(())
+		} else {
+			Err(self.error_here(format!("expected {}, found {}", kind, self.peek().kind)))
+		}
+	}
+
+	fn parse_binary(
+		&mut self,
+		operators: &[(&str, CombinationCondition)],
+		parse_operand: fn(&mut Self) -> Result<RuleTrigger, ParseError>
+	) -> Result<RuleTrigger, ParseError> {
+		let mut trigger = parse_operand(self)?;
+		while let Some(condition) = self.peek_keyword().and_then(|keyword| {
+			operators
+				.iter()
+				.find(|(operator, _)| *operator == keyword)
+				.map(|(_, condition)| condition.clone())
+		}) {
+			self.advance();
+			trigger = RuleTrigger::Combination {
+				a: Box::new(trigger),
+				b: Box::new(parse_operand(self)?),
+				condition
+			};
+		}
+		Ok(trigger)
+	}
+
+	fn parse_or(&mut self) -> Result<RuleTrigger, ParseError> {
+		self.parse_binary(
+			&[
+				("or", CombinationCondition::Either),
+				("nor", CombinationCondition::None)
+			],
+			Self::parse_xor
+		)
+	}
+
+	fn parse_xor(&mut self) -> Result<RuleTrigger, ParseError> {
+		self.parse_binary(
+			&[("xor", CombinationCondition::ExactlyOne)],
+			Self::parse_and
+		)
+	}
+
+	fn parse_and(&mut self) -> Result<RuleTrigger, ParseError> {
+		self.parse_binary(&[("and", CombinationCondition::Both)], Self::parse_unary)
+	}
+
+	fn parse_unary(&mut self) -> Result<RuleTrigger, ParseError> {
+		if self.peek_keyword().as_deref() == Some("not") {
+			self.advance();
+			Ok(RuleTrigger::Not {
+				flipped_rule: Box::new(self.parse_unary()?)
+			})
+		} else {
+			self.parse_primary()
+		}
+	}
+
+	fn parse_primary(&mut self) -> Result<RuleTrigger, ParseError> {
+		if self.peek().kind == TokenKind::OpenParen {
+			self.advance();
+			let trigger = self.parse_or()?;
+			self.expect(TokenKind::CloseParen)?;
+			return Ok(trigger);
+		}
+		let is_attribute = matches!(self.peek_second().kind, TokenKind::Comparison(_))
+			&& !matches!(
+				self.peek_keyword().as_deref(),
+				Some("quantity" | "order_size")
+			);
+		if is_attribute {
+			let key = self.parse_text("an attribute")?;
+			let TokenKind::Comparison(comparison) = self.advance().kind else {
+				unreachable!("checked to be a comparison");
+			};
+			let value = match self.peek().kind.clone() {
+				TokenKind::Word(word) => AttributeValue::from_text(&word),
+				TokenKind::Quoted(text) => AttributeValue::Text(text.into()),
+				other => return Err(self.error_here(format!("expected a value, found {}", other)))
+			};
+			self.advance();
+			return Ok(RuleTrigger::Attribute {
+				key: key.into(),
+				comparison,
+				value
+			});
+		}
+		let Some(keyword) = self.peek_keyword() else {
+			return Err(self.error_here(format!("expected a trigger, found {}", self.peek().kind)));
+		};
+		let condition = match keyword.as_str() {
+			"always" => {
+				self.advance();
+				return Ok(RuleTrigger::Always);
+			},
+			"never" => {
+				self.advance();
+				return Ok(RuleTrigger::Never);
+			},
+			"title" => {
+				self.advance();
+				self.expect(TokenKind::OpenParen)?;
+				let name = self.parse_text("a title")?;
+				self.expect(TokenKind::CloseParen)?;
+				return Ok(RuleTrigger::Title { name: name.into() });
+			},
+			"title_contains" | "title_glob" | "title_regex" | "ident_prefix" => {
+				self.advance();
+				self.expect(TokenKind::OpenParen)?;
+				let column = self.peek().column;
+				let text = self.parse_text("a text")?;
+				self.expect(TokenKind::CloseParen)?;
+				return Ok(match keyword.as_str() {
+					"title_contains" => RuleTrigger::TitleContains { text: text.into() },
+					"title_glob" => RuleTrigger::TitleGlob {
+						pattern: text.into()
+					},
+					"title_regex" => RuleTrigger::TitleRegex {
+						pattern: text.parse().map_err(|error: regex::Error| {
+							// the last line of the error says what is wrong
+							let reason = error.to_string();
+							ParseError {
+								column,
+								message: format!(
+									"invalid regular expression, {}",
+									reason
+										.lines()
+										.last()
+										.unwrap_or_default()
+										.trim_start_matches("error:")
+										.trim()
+								)
+							}
+						})?
+					},
+					_ => RuleTrigger::IdentifierPrefix {
+						prefix: text.into()
+					}
+				});
+			},
+			"all_of" | "any_of" | "none_of" | "at_least" => {
+				self.advance();
+				self.expect(TokenKind::OpenParen)?;
+				let count = if keyword == "at_least" {
+					let count = self.parse_count()?;
+					if self.peek().kind != TokenKind::CloseParen {
+						self.expect(TokenKind::Comma)?;
+					}
+					Some(count)
+				} else {
+					None
+				};
+				let mut triggers = Vec::new();
+				if self.peek().kind != TokenKind::CloseParen {
+					'trigger_loop: loop {
+						triggers.push(self.parse_or()?);
+						if self.peek().kind == TokenKind::Comma {
+							self.advance();
+						} else {
+							break 'trigger_loop;
+						}
+					}
+				}
+				self.expect(TokenKind::CloseParen)?;
+				return Ok(match (keyword.as_str(), count) {
+					("all_of", _) => RuleTrigger::AllOf(triggers),
+					("any_of", _) => RuleTrigger::AnyOf(triggers),
+					("none_of", _) => RuleTrigger::NoneOf(triggers),
+					(_, count) => RuleTrigger::AtLeast(count.unwrap_or_default(), triggers)
+				});
+			},
+			"quantity" | "order_size" => {
+				self.advance();
+				let comparison = match self.peek().kind {
+					TokenKind::Comparison(comparison) => comparison,
+					ref other => {
+						return Err(
+							self.error_here(format!("expected a comparison, found {}", other))
+						);
+					}
+				};
+				self.advance();
+				let amount = self.parse_count()?;
+				return Ok(if keyword == "quantity" {
+					RuleTrigger::Quantity { comparison, amount }
+				} else {
+					RuleTrigger::OrderSize { comparison, amount }
+				});
+			},
+			"order_contains" => {
+				self.advance();
+				self.expect(TokenKind::OpenParen)?;
+				let other = self.parse_or()?;
+				self.expect(TokenKind::CloseParen)?;
+				return Ok(RuleTrigger::OrderContains {
+					other: Box::new(other)
+				});
+			},
+			"any" => IdentifierCondition::Any,
+			"all" => IdentifierCondition::All,
+			"none" => IdentifierCondition::None,
+			_ => {
+				return Err(self.error_here(format!(
+					"unknown trigger '{}', expected one of always, never, title, title_contains, \
+					 title_glob, title_regex, any, all, none, ident_prefix, all_of, any_of, none_of, \
+					 at_least, quantity, order_size, order_contains, not or an attribute comparison",
+					keyword
+				)));
+			}
+		};
+		self.advance();
+		self.expect(TokenKind::OpenParen)?;
+		let mut identifiers = IdentifierCollection::default();
+		if self.peek().kind != TokenKind::CloseParen {
+			'identifier_loop: loop {
+				let identifier_column = self.peek().column;
+				let identifier = Identifier::from(self.parse_text("an identifier")?);
+				if !identifiers.0.insert(identifier.clone()) {
+					return Err(ParseError {
+						column:  identifier_column,
+						message: format!("identifier {} is listed twice", identifier)
+					});
+				}
+				if self.peek().kind == TokenKind::Comma {
+					self.advance();
+				} else {
+					break 'identifier_loop;
+				}
+			}
+		}
+		self.expect(TokenKind::CloseParen)?;
+		Ok(RuleTrigger::Identifier {
+			identifiers,
+			condition
+		})
+	}
+
+	fn parse_count(&mut self) -> Result<usize, ParseError> {
+		match &self.peek().kind {
+			TokenKind::Word(word) => match word.parse() {
+				Ok(count) => {
+					self.advance();
+					Ok(count)
+				},
+				Err(_) => Err(self.error_here(format!("'{}' is not a valid count", word)))
+			},
+			other => Err(self.error_here(format!("expected a count, found {}", other)))
+		}
+	}
+
+	fn parse_text(&mut self, expected: &str) -> Result<String, ParseError> {
+		match self.peek().kind.clone() {
+			TokenKind::Word(text) | TokenKind::Quoted(text) => {
+				self.advance();
+				Ok(text)
+			},
+			other => Err(self.error_here(format!("expected {}, found {}", expected, other)))
+		}
+	}
+}
+
+impl FromStr for RuleTrigger {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parser = Parser {
+			tokens:   tokenize(s)?,
+			position: 0
+		};
+		let trigger = parser.parse_or()?;
+		if parser.peek().kind == TokenKind::End {
+			Ok(trigger)
+		} else {
+			Err(parser.error_here(format!(
+				"expected an operator or end of input, found {}",
+				parser.peek().kind
+			)))
+		}
+	}
+}
+
+const KEYWORDS: [&str; 22] = [
+	"always",
+	"never",
+	"title",
+	"title_contains",
+	"title_glob",
+	"title_regex",
+	"ident_prefix",
+	"any",
+	"all",
+	"none",
+	"all_of",
+	"any_of",
+	"none_of",
+	"at_least",
+	"quantity",
+	"order_size",
+	"order_contains",
+	"not",
+	"and",
+	"or",
+	"xor",
+	"nor"
+];
+
+fn write_text(f: &mut std::fmt::Formatter<'_>, text: &str, allow_bare: bool) -> std::fmt::Result {
+	let is_bare = allow_bare
+		&& !text.is_empty()
+		&& text.chars().all(is_word_char)
+		&& !KEYWORDS.contains(&text.to_lowercase().as_str());
+	if is_bare {
+		write!(f, "{}", text)
+	} else {
+		write!(f, "\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+	}
+}
+
+fn write_trigger_list(
+	f: &mut std::fmt::Formatter<'_>,
+	function: &str,
+	count: Option<usize>,
+	triggers: &[RuleTrigger]
+) -> std::fmt::Result {
+	let arguments = count
+		.map(|count| count.to_string())
+		.into_iter()
+		.chain(triggers.iter().map(ToString::to_string))
+		.collect::<Vec<_>>();
+	write!(f, "{}({})", function, arguments.join(", "))
+}
+
+impl RuleTrigger {
+	/// Higher binds tighter, matching the parser.
+	fn precedence(&self) -> u8 {
+		match self {
+			RuleTrigger::Combination { condition, .. } => match condition {
+				CombinationCondition::Either | CombinationCondition::None => 1,
+				CombinationCondition::ExactlyOne => 2,
+				CombinationCondition::Both => 3
+			},
+			RuleTrigger::Not { .. } => 4,
+			_ => 5
+		}
+	}
+
+	fn write_operand(
+		&self,
+		f: &mut std::fmt::Formatter<'_>,
+		needs_parentheses: bool
+	) -> std::fmt::Result {
+		if needs_parentheses {
+			write!(f, "({})", self)
+		} else {
+			write!(f, "{}", self)
+		}
+	}
+}
+
+impl Display for RuleTrigger {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RuleTrigger::Never => write!(f, "never"),
+			RuleTrigger::Always => write!(f, "always"),
+			RuleTrigger::Title { name } => {
+				write!(f, "title(")?;
+				write_text(f, name, false)?;
+				write!(f, ")")
+			},
+			RuleTrigger::Identifier {
+				identifiers,
+				condition
+			} => {
+				let function = match condition {
+					IdentifierCondition::None => "none",
+					IdentifierCondition::Any => "any",
+					IdentifierCondition::All => "all"
+				};
+				write!(f, "{}(", function)?;
+				for (index, identifier) in identifiers.0.iter().enumerate() {
+					if index > 0 {
+						write!(f, ", ")?;
+					}
+					write_text(f, &identifier.0, true)?;
+				}
+				write!(f, ")")
+			},
+			RuleTrigger::Combination { a, b, condition } => {
+				let operator = match condition {
+					CombinationCondition::None => "nor",
+					CombinationCondition::ExactlyOne => "xor",
+					CombinationCondition::Either => "or",
+					CombinationCondition::Both => "and"
+				};
+				a.write_operand(f, a.precedence() < self.precedence())?;
+				write!(f, " {} ", operator)?;
+				b.write_operand(f, b.precedence() <= self.precedence())
+			},
+			RuleTrigger::Not { flipped_rule } => {
+				write!(f, "not ")?;
+				flipped_rule.write_operand(f, flipped_rule.precedence() < self.precedence())
+			},
+			RuleTrigger::AllOf(triggers) => write_trigger_list(f, "all_of", None, triggers),
+			RuleTrigger::AnyOf(triggers) => write_trigger_list(f, "any_of", None, triggers),
+			RuleTrigger::NoneOf(triggers) => write_trigger_list(f, "none_of", None, triggers),
+			RuleTrigger::AtLeast(count, triggers) => {
+				write_trigger_list(f, "at_least", Some(*count), triggers)
+			},
+			RuleTrigger::Quantity { comparison, amount } => {
+				write!(f, "quantity {} {}", comparison, amount)
+			},
+			RuleTrigger::OrderSize { comparison, amount } => {
+				write!(f, "order_size {} {}", comparison, amount)
+			},
+			RuleTrigger::OrderContains { other } => write!(f, "order_contains({})", other),
+			RuleTrigger::TitleContains { text } => {
+				write!(f, "title_contains(")?;
+				write_text(f, text, false)?;
+				write!(f, ")")
+			},
+			RuleTrigger::TitleGlob { pattern } => {
+				write!(f, "title_glob(")?;
+				write_text(f, pattern, false)?;
+				write!(f, ")")
+			},
+			RuleTrigger::TitleRegex { pattern } => {
+				write!(f, "title_regex(")?;
+				write_text(f, pattern.as_str(), false)?;
+				write!(f, ")")
+			},
+			RuleTrigger::IdentifierPrefix { prefix } => {
+				write!(f, "ident_prefix(")?;
+				write_text(f, prefix, true)?;
+				write!(f, ")")
+			},
+			RuleTrigger::Attribute {
+				key,
+				comparison,
+				value
+			} => {
+				write_text(f, key, true)?;
+				write!(f, " {} ", comparison)?;
+				match value {
+					// bare text that reads as a number or flag is quoted to stay text
+					AttributeValue::Text(text) => write_text(
+						f,
+						text,
+						matches!(AttributeValue::from_text(text), AttributeValue::Text(_))
+					),
+					value => write!(f, "{}", value)
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn identifiers(names: &[&str]) -> IdentifierCollection {
+		IdentifierCollection(names.iter().map(Identifier::from).collect())
+	}
+
+	#[test]
+	fn parses_example() {
+		let parsed: RuleTrigger =
+			r#"title("Learning to Ski") or (any(book, physical) and not all(digital))"#
+				.parse()
+				.unwrap();
+		let expected = RuleTrigger::Combination {
+			a:         Box::new(RuleTrigger::Title {
+				name: "Learning to Ski".into()
+			}),
+			b:         Box::new(RuleTrigger::Combination {
+				a:         Box::new(RuleTrigger::Identifier {
+					identifiers: identifiers(&["book", "physical"]),
+					condition:   IdentifierCondition::Any
+				}),
+				b:         Box::new(RuleTrigger::Not {
+					flipped_rule: Box::new(RuleTrigger::Identifier {
+						identifiers: identifiers(&["digital"]),
+						condition:   IdentifierCondition::All
+					})
+				}),
+				condition: CombinationCondition::Both
+			}),
+			condition: CombinationCondition::Either
+		};
+		assert_eq!(parsed, expected);
+	}
+
+	#[test]
+	fn round_trips() {
+		for source in [
+			"always",
+			"never",
+			r#"title("Learning to Ski")"#,
+			r#"any("membership upgrade", book)"#,
+			"none()",
+			"any(a) or any(b) or any(c)",
+			"any(a) or (any(b) or any(c))",
+			"(any(a) or any(b)) and not (all(c) xor none(d))",
+			r#"not not any(a) nor any("and")"#,
+			"all_of(any(a), not any(b) or never, any_of())",
+			"none_of(title(x), at_least(2, any(a), any(b), any(c)))",
+			"at_least(0)",
+			"quantity >= 3 and not order_size < 10",
+			"order_contains(any(book) and quantity != 1) or quantity = 2",
+			r#"title_contains(ski) or title_glob("Learn*") and title_regex("^(?i)learn\\w+$")"#,
+			r#"ident_prefix(membership:) and price > 99.5 and region = EU and gift = true"#,
+			r#""quantity" >= 2 or code = "007" or "two words" != "false""#
+		] {
+			let source: RuleTrigger = source.parse().unwrap();
+			let printed = source.to_string();
+			assert_eq!(printed.parse::<RuleTrigger>(), Ok(source), "{}", printed);
+		}
+	}
+
+	#[test]
+	fn reports_error_columns() {
+		let error = "any(book) and".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 14);
+		let error = "any(book) & all(x)".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 11);
+		let error = "title(\"open".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 7);
+		let error = "sometimes".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 1);
+		let error = "at_least(two, any(a))".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 10);
+		let error = "quantity => 3".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 11);
+		let error = "title_regex(\"(ski\")".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 13);
+		let error = "price > (".parse::<RuleTrigger>().unwrap_err();
+		assert_eq!(error.column, 9);
+	}
+}
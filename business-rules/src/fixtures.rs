@@ -0,0 +1,264 @@
+//! Rule fixtures: purchases with the processing steps they are expected to get, for regression
+//! testing the rules.
+//!
+//! Fixture files are YAML (or JSON) lists, steps are written like rule actions:
+//!
+//! ```yaml
+//! - name: books get a duplicate slip
+//!   purchase:
+//!     title: '1984'
+//!     identifiers: [book, physical]
+//!   steps:
+//!     - packing_slip
+//!     - packing_slip(royalty)
+//! ```
+//!
+//! A purchase with a `purchased_at` time is processed with the rules active at that time.
+
+use std::{collections::BTreeSet, fmt::Display, io, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+	actions::ProcessAction,
+	types::{AttributeMap, EntryId, IdentifierCollection, Purchase, Rule, RuleOutcome}
+};
+
+/// Name of the fixture file next to the data, used when no other file is given.
+pub const FIXTURE_FILE: &str = "rule_fixtures.yaml";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct FixturePurchase {
+	pub title:        String,
+	#[serde(default)]
+	pub identifiers:  Vec<String>,
+	#[serde(default, skip_serializing_if = "AttributeMap::is_empty")]
+	pub attributes:   AttributeMap,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub purchased_at: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct RuleFixture {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub name:     Option<String>,
+	pub purchase: FixturePurchase,
+	/// Expected processing steps in the order they are carried out.
+	#[serde(default)]
+	pub steps:    Vec<String>
+}
+impl RuleFixture {
+	fn label(&self) -> &str { self.name.as_deref().unwrap_or(&self.purchase.title) }
+	fn purchase(&self) -> Purchase {
+		Purchase {
+			title:        self.purchase.title.as_str().into(),
+			identifiers:  IdentifierCollection(
+				self.purchase.identifiers.iter().map(Into::into).collect()
+			),
+			attributes:   self.purchase.attributes.clone(),
+			purchased_at: self.purchase.purchased_at,
+			id:           EntryId::default()
+		}
+	}
+	/// Steps the rules give the purchase, written the same way as the expected steps.
+	fn actual_steps(&self, rules: &BTreeSet<Rule>, as_of: Option<DateTime<Utc>>) -> Vec<String> {
+		let purchase = self.purchase();
+		purchase
+			.get_processing_steps(rules, purchase.evaluation_time(as_of))
+			.iter()
+			.map(ToString::to_string)
+			.collect()
+	}
+	/// Expected steps written the way actions are displayed, so spacing doesn't matter.
+	fn expected_steps(&self) -> Vec<String> {
+		self.steps
+			.iter()
+			.map(|step| ProcessAction::from(step.as_str()).to_string())
+			.collect()
+	}
+}
+
+pub fn read_fixtures(contents: &str) -> io::Result<Vec<RuleFixture>> {
+	serde_yaml::from_str(contents)
+		.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+pub fn write_fixtures(fixtures: &[RuleFixture]) -> io::Result<String> {
+	serde_yaml::to_string(fixtures).map_err(io::Error::other)
+}
+
+/// The fixtures with their steps replaced by what the rules currently give.
+pub fn updated_fixtures(
+	fixtures: &[RuleFixture],
+	rules: &BTreeSet<Rule>,
+	as_of: Option<DateTime<Utc>>
+) -> Vec<RuleFixture> {
+	fixtures
+		.iter()
+		.map(|fixture| RuleFixture {
+			steps: fixture.actual_steps(rules, as_of),
+			..fixture.clone()
+		})
+		.collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct FixtureResult {
+	pub name:     String,
+	pub passed:   bool,
+	pub expected: Vec<String>,
+	pub actual:   Vec<String>
+}
+impl Display for FixtureResult {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.passed {
+			return write!(f, "ok     {}", self.name);
+		}
+		write!(f, "FAILED {}", self.name)?;
+		for (change, step) in diff_steps(&self.expected, &self.actual) {
+			write!(f, "\n  {} {}", change, step)?;
+		}
+		Ok(())
+	}
+}
+
+/// Results of running fixtures, with the rules no fixture fires.
+#[derive(Debug, Serialize)]
+pub struct FixtureReport {
+	pub results:   Vec<FixtureResult>,
+	pub uncovered: Vec<Arc<str>>,
+	pub rules:     usize
+}
+impl FixtureReport {
+	/// Fixtures are evaluated as of the given time, else as of when their purchase was made.
+	pub fn run(
+		fixtures: &[RuleFixture],
+		rules: &BTreeSet<Rule>,
+		as_of: Option<DateTime<Utc>>
+	) -> Self {
+		let mut fired = BTreeSet::new();
+		let results = fixtures
+			.iter()
+			.map(|fixture| {
+				let purchase = fixture.purchase();
+				let at = purchase.evaluation_time(as_of);
+				for explanation in purchase.explain_processing(rules, at) {
+					if explanation.outcome == RuleOutcome::Fired {
+						fired.insert(explanation.rule.title.clone());
+					}
+				}
+				let (expected, actual) =
+					(fixture.expected_steps(), fixture.actual_steps(rules, as_of));
+				FixtureResult {
+					name: fixture.label().to_string(),
+					passed: expected == actual,
+					expected,
+					actual
+				}
+			})
+			.collect();
+		let uncovered = rules
+			.iter()
+			.map(|rule| rule.title.clone())
+			.filter(|title| !fired.contains(title))
+			.collect();
+		FixtureReport {
+			results,
+			uncovered,
+			rules: rules.len()
+		}
+	}
+
+	pub fn failed(&self) -> usize { self.results.iter().filter(|result| !result.passed).count() }
+}
+impl Display for FixtureReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for result in &self.results {
+			writeln!(f, "{}", result)?;
+		}
+		if !self.uncovered.is_empty() {
+			writeln!(f, "\nRules no fixture fires:")?;
+			for title in &self.uncovered {
+				writeln!(f, "  {}", title)?;
+			}
+		}
+		write!(
+			f,
+			"\n{} of {} fixtures passed, fixtures fire {} of {} rules.",
+			self.results.len() - self.failed(),
+			self.results.len(),
+			self.rules - self.uncovered.len(),
+			self.rules
+		)
+	}
+}
+
+/// Lines of a diff from the expected to the actual steps: ' ' kept, '-' missing, '+' unexpected.
+fn diff_steps<'a>(expected: &'a [String], actual: &'a [String]) -> Vec<(char, &'a str)> {
+	// longest common subsequence lengths of every pair of suffixes
+	let mut common = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+	for i in (0..expected.len()).rev() {
+		for j in (0..actual.len()).rev() {
+			common[i][j] = if expected[i] == actual[j] {
+				common[i + 1][j + 1] + 1
+			} else {
+				common[i + 1][j].max(common[i][j + 1])
+			};
+		}
+	}
+	let (mut i, mut j) = (0, 0);
+	let mut lines = Vec::new();
+	while i < expected.len() || j < actual.len() {
+		if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+			lines.push((' ', expected[i].as_str()));
+			(i, j) = (i + 1, j + 1);
+		} else if j == actual.len() || (i < expected.len() && common[i + 1][j] >= common[i][j + 1])
+		{
+			lines.push(('-', expected[i].as_str()));
+			i += 1;
+		} else {
+			lines.push(('+', actual[j].as_str()));
+			j += 1;
+		}
+	}
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::rule;
+
+	#[test]
+	fn runs_fixtures_with_coverage() {
+		let rules = BTreeSet::from([
+			rule("books", "packing_slip(royalty)", "any(book)"),
+			rule("physical", "packing_slip", "any(physical)"),
+			rule("videos", "attach(First Aid)", "any(video)")
+		]);
+		let fixtures = read_fixtures(
+			"
+- name: book
+  purchase: {title: '1984', identifiers: [book, physical]}
+  steps: [packing_slip(royalty), packing_slip]
+- purchase: {title: Ski Wax, identifiers: [physical]}
+  steps: [packing_slip(royalty),  packing_slip ]
+"
+		)
+		.unwrap();
+		let report = FixtureReport::run(&fixtures, &rules, None);
+		assert_eq!(report.failed(), 1);
+		assert_eq!(report.results[1].name, "Ski Wax");
+		assert_eq!(
+			report.results[1].to_string(),
+			"FAILED Ski Wax\n  - packing_slip(royalty)\n    packing_slip"
+		);
+		assert_eq!(report.uncovered, [Arc::from("videos")]);
+
+		let updated = updated_fixtures(&fixtures, &rules, None);
+		assert_eq!(updated[1].steps, ["packing_slip"]);
+		let reread = read_fixtures(&write_fixtures(&updated).unwrap()).unwrap();
+		assert_eq!(reread, updated);
+		assert_eq!(FixtureReport::run(&reread, &rules, None).failed(), 0);
+	}
+}
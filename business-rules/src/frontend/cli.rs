@@ -0,0 +1,1653 @@
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	env,
+	fmt::Display,
+	io,
+	path::{Path, PathBuf},
+	process::ExitCode,
+	sync::Arc
+};
+
+use business_rules::{
+	actions::{DryRunExecutor, ProcessAction},
+	artifacts::ArtifactExecutor,
+	audit::{history, redo, undo, UndoError},
+	dataset::{ApplicationData, PathFindable, Saved},
+	expression::ParseError,
+	fixtures::{read_fixtures, updated_fixtures, write_fixtures, FixtureReport, FIXTURE_FILE},
+	impact::TriggerImpact,
+	location::{Config, DataLocation, LocationError, DATA_DIR_VARIABLE, PROFILE_VARIABLE},
+	orders::{OrderBatch, OrderFileFormat},
+	query::Query,
+	searching::Searchable,
+	storage::{has_entries, storage_for, Stored},
+	transfer::{read_rows, write_rows, Format, ImportPlan, RowReport, Transferable},
+	types::{
+		parse_attribute, parse_date, parse_time, AttributeMap, EntryId, Identifier,
+		IdentifierCollection, Order, ProcessingPlan, Purchase, PurchaseCollection, Rule, RuleScope,
+		RuleTrigger
+	},
+	validation::{validate_rules, Finding}
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::{json, to_string_pretty};
+
+use super::{server::Server, tui, DatabaseEntry};
+
+pub(crate) const USAGE: &str = "\
+Usage: business-rules [--data-dir <DIR>] [--profile <NAME>] [COMMAND]
+
+Without a command the interactive menu is started.
+
+Data:
+  --data-dir <DIR>      where the data is kept, else $BUSINESS_RULES_DATA_DIR, else 'data_dir' of
+                        the config file, else ./src when it holds data, else the data directory
+                        of the user
+  --profile <NAME>      uses the named dataset instead of the default one, else
+                        $BUSINESS_RULES_PROFILE, else 'profile' of the config file
+The config file is config.yaml in the business-rules config directory of the user, e.g.
+~/.config/business-rules/config.yaml, with 'profiles' mapping profile names to directories.
+Other profiles are kept in DIR/profiles/<NAME>, datasets that don't exist yet are created empty.
+Every save of the JSON files keeps the previous version in a 'backups' directory next to them,
+up to the last 10.
+
+Commands:
+  purchase    lists, searches, shows, adds, modifies and deletes purchases
+  rule        lists, searches, shows, adds, modifies and deletes rules
+  process     lists or executes the processing steps of purchases or of an order
+  orders      processes every order of an order file
+  explain     shows why each rule did or didn't fire for a purchase
+  validate    reports rules that can never fire and other problems with the rules
+  test-rules  checks the processing steps of a fixture file
+  import      adds or modifies entries from a CSV or YAML file
+  export      writes all entries as CSV or YAML
+  migrate     moves the JSON files into an SQLite database
+  undo        reverts the last change
+  redo        repeats the last undone change
+  history     lists the logged changes to an entry
+  serve       answers HTTP requests in JSON
+  tui         opens a full-screen view for browsing and editing
+  help        shows this help, 'help <COMMAND>' shows the help of a command
+
+Options take their value as '--name <VALUE>' or as '--name=<VALUE>', which also takes values
+starting with '--'.
+
+Exit codes: 0 on success, 1 when an entry could not be found or saved, validation found errors
+or a fixture failed, 2 on invalid usage.";
+
+const PURCHASE_HELP: &str = "\
+Usage:
+  business-rules purchase list [--json]
+  business-rules purchase search <QUERY>... [--limit <N>] [--json]
+  business-rules purchase show <PURCHASE> [--json]
+  business-rules purchase add --title <TITLE> [--identifier <ID>]... [--attribute <KEY>=<VALUE>]...
+                              [--purchased-at <TIME>]
+  business-rules purchase modify <PURCHASE> [--new-title <TITLE>] [--add-identifier <ID>]...
+                                 [--remove-identifier <ID>]... [--set-attribute <KEY>=<VALUE>]...
+                                 [--remove-attribute <KEY>]... [--purchased-at <TIME>]
+  business-rules purchase delete <PURCHASE>
+
+Purchases are given as '--title <TITLE> [--identifier <ID>]...' or '--id <ENTRY ID>'.
+Identifier values may contain several identifiers separated by ';'. Attribute values are
+numbers, true, false or text.";
+
+const RULE_HELP: &str = "\
+Usage:
+  business-rules rule list [--json]
+  business-rules rule search <QUERY>... [--limit <N>] [--json]
+  business-rules rule show <RULE> [--json]
+  business-rules rule add --title <TITLE> --action <ACTION> --trigger <TRIGGER> [RULE OPTIONS]
+  business-rules rule modify <RULE> [--new-title <TITLE>] [--action <ACTION>]
+                             [--trigger <TRIGGER>] [RULE OPTIONS] [--what-if]
+  business-rules rule delete <RULE>
+
+Rules are given as '--title <TITLE>' or '--id <ENTRY ID>'.
+
+Rule options:
+  --scope <line|order>  whether the rule runs per purchase or once per order
+  --priority <N>        higher priorities are processed first, defaults to 0
+  --stop <yes|no>       whether firing the rule stops lower priority rules from firing
+  --group <NAME>        only the first rule to fire in an exclusion group applies, '' for none
+  --valid-from <DATE>   first day the rule applies, as YYYY-MM-DD, '' for no limit
+  --valid-until <DATE>  last day the rule applies, as YYYY-MM-DD, '' for no limit
+  --force               saves the rule even when validating it finds errors
+'rule modify --what-if' lists the purchases that would newly trigger or stop triggering the rule
+with the new trigger, each purchase on its own, without saving anything.
+
+Actions are written as packing_slip, packing_slip(<DEPARTMENT>), attach(<ITEM>),
+activate_membership, upgrade_membership, email(<RECIPIENT>, <SUBJECT>), commission(<RECIPIENT>)
+or any other text for a custom action. Arguments holding ',', '(', ')' or '\"' are quoted, as in
+'email(owner, \"Re: a, b\")', and so is custom text that would read as another action.
+Triggers are written as expressions, e.g. 'title(\"Learning to Ski\") or (any(book, physical) and
+not all(digital))', combined with 'and', 'or', 'xor', 'nor' and 'not'.
+Titles can also be matched with 'title_contains(ski)', 'title_glob(\"Learn*\")' (whole title,
+'*' and '?' as wildcards) or 'title_regex(\"^Learn\")', identifiers by their start with
+'ident_prefix(membership:)' and purchase attributes with comparisons, e.g. 'price > 100' or
+'region = EU'. Attribute values are numbers, true, false or text, where text compares ignoring
+case and only values of the same kind compare.
+Rules only fire on the days of their validity window.";
+
+/// How purchases and rules are found, shared by their help.
+const ENTRIES_HELP: &str = "\
+Titles are matched exactly, falling back to a case-insensitive match.
+Search queries match fuzzily and list the closest matches first with a score from 0 to 1, e.g.
+'ski title:\"first aid\" ident:video -ident:digital action:slip id:3f2a'. Plain words search the
+title, '-' leaves out what a term matches and quotes keep spaces inside a term.
+Every purchase and rule has a generated ID, shown by 'show', which stays the same when the entry
+is modified. Entry IDs can be shortened to any unique start of at least 4 characters.";
+
+const PROCESS_HELP: &str = "\
+Usage:
+  business-rules process (--purchase <TITLE> [--identifier <ID>]... | --purchase-id <ENTRY ID>
+                         | --all) [--json] [--as-of <TIME>] [EXECUTION]
+  business-rules process --order <TITLE>... [--json] [--as-of <TIME>] [EXECUTION]
+
+Lists the processing steps of each purchase on its own, or of an order of the purchases with the
+titles given by '--order'. Repeating a title in '--order' adds another one of that purchase to
+the order. Rules with the 'order' scope are listed once per order instead of for every purchase.
+Processing steps are listed highest priority first, equal priorities by rule title.";
+
+const ORDERS_HELP: &str = "\
+Usage:
+  business-rules orders run <ORDER FILE> [--rules <FILE>] [--json] [--as-of <TIME>]
+                            [EXECUTION]
+
+Processes every order of an order file, with a line per purchase in an order: CSV with the
+columns order, purchase and quantity, or one JSON object per line with the same fields, e.g.
+{\"order\": \"A-1\", \"purchase\": \"Learning to Ski\", \"quantity\": 2}. Purchases are given by title
+or ID and the quantity defaults to 1. Orders with lines that can't be found are skipped.
+'--rules' uses the rules of another data file, or of a CSV or YAML file written by 'export',
+instead of the current rules. When executing, the order column is the reference.";
+
+/// Shared by the commands that process orders.
+const EXECUTION_HELP: &str = "\
+Execution:
+  --dry-run             lists what executing the processing steps would do, without doing it
+  --execute <DIR>       writes packing slips, an e-mail outbox and a commission ledger into DIR
+  --reference <ID>      identifies the order, required with '--execute', executing again with
+                        the same reference and purchases skips everything already written";
+
+/// Shared by the commands that evaluate rules.
+const TIME_HELP: &str = "\
+Rules are evaluated as of the time given by '--as-of', else as of when the purchase was made (the
+latest purchase of an order), else now. Times are written as YYYY-MM-DD, the start of that day in
+UTC, or like 2024-05-01T14:30:00Z.";
+
+const EXPLAIN_HELP: &str = "\
+Usage:
+  business-rules explain (--purchase <TITLE> [--identifier <ID>]... | --purchase-id <ENTRY ID>)
+                         [--rule <TITLE>] [--json] [--as-of <TIME>]
+
+Shows why each rule, or only the given one, did or didn't fire for a purchase on its own, by
+evaluating every part of its trigger.";
+
+const VALIDATE_HELP: &str = "\
+Usage:
+  business-rules validate [--json]
+
+Reports rules that can never fire, parts of triggers that are always true or false, identifiers
+no purchase has and rules repeating the trigger and action of another rule.";
+
+const TEST_RULES_HELP: &str = "\
+Usage:
+  business-rules test-rules [<FIXTURE FILE>] [--rules <FILE>] [--update] [--json]
+                            [--as-of <TIME>]
+
+Processes the purchases of a fixture file, rule_fixtures.yaml next to the data unless a FILE is
+given, and shows a diff for every purchase that doesn't get exactly the expected steps, followed
+by the rules no fixture fires. Fixtures are listed in YAML as
+'- {name: <NAME>, purchase: {title: <TITLE>, identifiers: [<ID>, ..]}, steps: [<ACTION>, ..]}',
+with the name optional and purchase attributes given as 'attributes: {<KEY>: <VALUE>, ..}' and
+the time of purchase as 'purchased_at: <TIME>'. '--rules' uses the rules of another file, as in
+'orders run'. '--update' replaces the expected steps with what the rules give.";
+
+const TRANSFER_HELP: &str = "\
+Usage:
+  business-rules import (purchases | rules) <FILE> [--format <csv|yaml>] [--dry-run] [--force]
+  business-rules export (purchases | rules) [<FILE>] [--format <csv|yaml>]
+
+'import' adds the rows of a CSV or YAML file, or modifies the entries with the ID of a row, and
+lists the changes as a diff. Rows repeating an earlier row or an existing entry are skipped,
+invalid rows are reported and left out. '--dry-run' only lists the changes. 'export' writes all
+entries to FILE, or to the output when no FILE is given. The format follows the file extension
+unless '--format' is given. Purchases have the columns title, identifiers (separated by ';'),
+attributes (KEY=VALUE separated by ';'), purchased_at and id, rules have title, action, trigger,
+scope, priority, stop, group, valid_from, valid_until and id.";
+
+const MIGRATE_HELP: &str = "\
+Usage:
+  business-rules migrate [--force]
+
+Imports all_purchases.json and all_rules.json into business_rules.db, an SQLite database that is
+used instead of the JSON files from then on. '--force' replaces what the database already
+holds.";
+
+const HISTORY_HELP: &str = "\
+Usage:
+  business-rules undo
+  business-rules redo
+  business-rules history (<TITLE> | <ENTRY ID>) [--json]
+
+'undo' reverts the last purchase or rule change that wasn't undone yet, 'redo' repeats the last
+undone change. Every change is logged in audit_log.jsonl next to the data, 'history' lists the
+logged changes to entries with the title or ID.";
+
+const SERVE_HELP: &str = "\
+Usage:
+  business-rules serve [--address <HOST:PORT>]
+
+Answers HTTP requests in JSON on 127.0.0.1:8080 unless another address is given, until it is
+stopped. 'GET', 'POST' /purchases and 'GET', 'PUT', 'DELETE' /purchases/<ENTRY ID> list, add,
+show, replace and delete purchases, the same goes for /rules, with entries written as in
+all_purchases.json and all_rules.json. Rules with validation errors are refused unless '?force'
+is added. 'POST /process' takes {\"purchase\": <PURCHASE>} and gives its steps, or
+{\"order\": [{\"purchase\": <PURCHASE>, \"quantity\": <N>}, ..]} and gives the plan of the order,
+where a purchase is the title or ID of a stored purchase or a whole purchase. Both take an
+optional \"as_of\" time.";
+
+const TUI_HELP: &str = "\
+Usage:
+  business-rules tui
+
+Opens a full-screen view for browsing, filtering and editing purchases and rules, showing which
+rules fire for the highlighted purchase.";
+
+/// The help shown by `help <COMMAND>`, `None` for unknown commands.
+fn command_help(command: &str) -> Option<String> {
+	let sections: &[&str] = match command {
+		"purchase" => &[PURCHASE_HELP, ENTRIES_HELP, TIME_HELP],
+		"rule" => &[RULE_HELP, ENTRIES_HELP],
+		"process" => &[PROCESS_HELP, EXECUTION_HELP, TIME_HELP],
+		"orders" => &[ORDERS_HELP, EXECUTION_HELP, TIME_HELP],
+		"explain" => &[EXPLAIN_HELP, TIME_HELP],
+		"validate" => &[VALIDATE_HELP],
+		"test-rules" => &[TEST_RULES_HELP, TIME_HELP],
+		"import" | "export" => &[TRANSFER_HELP],
+		"migrate" => &[MIGRATE_HELP],
+		"undo" | "redo" | "history" => &[HISTORY_HELP],
+		"serve" => &[SERVE_HELP],
+		"tui" => &[TUI_HELP],
+		"help" => &[USAGE],
+		_ => return None
+	};
+	Some(sections.join("\n\n"))
+}
+
+#[derive(Debug)]
+pub(crate) enum CliError {
+	Usage(String),
+	NotFound(String),
+	Conflict(String),
+	Invalid(String),
+	Io(io::Error)
+}
+impl CliError {
+	fn exit_code(&self) -> ExitCode {
+		match self {
+			CliError::Usage(_) => ExitCode::from(2),
+			CliError::NotFound(_)
+			| CliError::Conflict(_)
+			| CliError::Invalid(_)
+			| CliError::Io(_) => ExitCode::FAILURE
+		}
+	}
+}
+impl Display for CliError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			CliError::Usage(message)
+			| CliError::NotFound(message)
+			| CliError::Conflict(message)
+			| CliError::Invalid(message) => write!(f, "{}", message),
+			CliError::Io(error) => write!(f, "IO error: {}", error)
+		}
+	}
+}
+impl From<io::Error> for CliError {
+	fn from(value: io::Error) -> Self {
+		match value.kind() {
+			io::ErrorKind::AlreadyExists => {
+				CliError::Conflict(format!("Nothing was saved, {}.", value))
+			},
+			_ => CliError::Io(value)
+		}
+	}
+}
+impl From<LocationError> for CliError {
+	fn from(value: LocationError) -> Self {
+		match value {
+			LocationError::Io(error) => CliError::Io(error),
+			LocationError::Config { .. } => CliError::Invalid(value.to_string()),
+			LocationError::NoDataDir | LocationError::InvalidProfile(_) => {
+				CliError::Usage(value.to_string())
+			},
+		}
+	}
+}
+
+/// Options that never take a value, so the argument after them stays a positional.
+const SWITCHES: [&str; 6] = ["all", "dry-run", "force", "json", "update", "what-if"];
+
+/// Command line arguments split into positionals and `--name [value]` or `--name=value` options.
+#[derive(Debug, Default)]
+pub(crate) struct Arguments {
+	positional: Vec<String>,
+	options:    BTreeMap<String, Vec<Option<String>>>
+}
+impl Arguments {
+	pub(crate) fn parse(args: impl IntoIterator<Item = String>) -> Self {
+		let mut parsed = Arguments::default();
+		let mut args = args.into_iter().peekable();
+		while let Some(arg) = args.next() {
+			if let Some(option) = arg.strip_prefix("--") {
+				let (name, value) = match option.split_once('=') {
+					Some((name, value)) => (name, Some(value.to_string())),
+					None if SWITCHES.contains(&option) => (option, None),
+					None => (option, args.next_if(|next| !next.starts_with("--")))
+				};
+				parsed
+					.options
+					.entry(name.to_string())
+					.or_default()
+					.push(value);
+			} else {
+				parsed.positional.push(arg);
+			}
+		}
+		parsed
+	}
+
+	pub(crate) fn next_positional(&mut self) -> Option<String> {
+		if self.positional.is_empty() {
+			None
+		} else {
+			Some(self.positional.remove(0))
+		}
+	}
+
+	pub(crate) fn switch(&mut self, name: &str) -> Result<bool, CliError> {
+		match self.options.remove(name) {
+			None => Ok(false),
+			Some(values) if values.iter().all(Option::is_none) => Ok(true),
+			Some(_) => Err(CliError::Usage(format!(
+				"'--{}' does not take a value.",
+				name
+			)))
+		}
+	}
+
+	pub(crate) fn values(&mut self, name: &str) -> Result<Vec<String>, CliError> {
+		self.options
+			.remove(name)
+			.unwrap_or_default()
+			.into_iter()
+			.map(|value| {
+				value.ok_or_else(|| CliError::Usage(format!("'--{}' needs a value.", name)))
+			})
+			.collect()
+	}
+
+	pub(crate) fn value(&mut self, name: &str) -> Result<Option<String>, CliError> {
+		let mut values = self.values(name)?;
+		match values.len() {
+			0 => Ok(None),
+			1 => Ok(values.pop()),
+			_ => Err(CliError::Usage(format!(
+				"'--{}' can only be given once.",
+				name
+			)))
+		}
+	}
+
+	pub(crate) fn required_value(&mut self, name: &str) -> Result<String, CliError> {
+		self.value(name)?
+			.ok_or_else(|| CliError::Usage(format!("'--{}' is required.", name)))
+	}
+
+	/// Fails on anything that was provided but never asked for.
+	pub(crate) fn finish(self) -> Result<(), CliError> {
+		if let Some(unexpected) = self.positional.first() {
+			Err(CliError::Usage(format!(
+				"Unexpected argument '{}'.",
+				unexpected
+			)))
+		} else if let Some(unexpected) = self.options.keys().next() {
+			Err(CliError::Usage(format!(
+				"Unexpected option '--{}'.",
+				unexpected
+			)))
+		} else {
+			Ok(())
+		}
+	}
+	pub(crate) fn is_empty(&self) -> bool { self.positional.is_empty() && self.options.is_empty() }
+}
+
+/// Takes the `--data-dir` and `--profile` options and opens the dataset they choose, as described
+/// in `USAGE`.
+pub(crate) fn open_data(args: &mut Arguments) -> Result<(DataLocation, ApplicationData), CliError> {
+	let data_dir = args
+		.value("data-dir")?
+		.map(PathBuf::from)
+		.or_else(|| env::var_os(DATA_DIR_VARIABLE).map(PathBuf::from))
+		.filter(|directory| !directory.as_os_str().is_empty());
+	let profile = args
+		.value("profile")?
+		.or_else(|| env::var(PROFILE_VARIABLE).ok())
+		.filter(|profile| !profile.is_empty());
+	let config = match Config::default_path() {
+		Some(path) => Config::load(&path)?,
+		None => Config::default()
+	};
+	let data_dir = data_dir.or_else(|| config.data_dir.is_none().then(crate_data_dir).flatten());
+	let location = DataLocation::resolve(data_dir, profile, config)?;
+	let data = location.open()?;
+	Ok((location, data))
+}
+/// The data kept in the crate, when the program is started in the crate directory.
+fn crate_data_dir() -> Option<PathBuf> {
+	let directory = env::current_dir().ok()?.join("src");
+	let json = ApplicationData::json_in(&directory);
+	(json.purchase_path.exists() || directory.join(ApplicationData::DATABASE_FILE).exists())
+		.then_some(directory)
+}
+
+pub(crate) fn run(args: Arguments, data: &ApplicationData) -> ExitCode {
+	// usage errors point to the help of the command they came from
+	let command = args
+		.positional
+		.first()
+		.filter(|command| *command != "help" && command_help(command).is_some())
+		.cloned();
+	match run_command(args, data) {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(error) => fail_in(error, command.as_deref())
+	}
+}
+pub(crate) fn fail(error: CliError) -> ExitCode { fail_in(error, None) }
+fn fail_in(error: CliError, command: Option<&str>) -> ExitCode {
+	match (&error, command) {
+		(CliError::Usage(_), Some(command)) => eprintln!(
+			"{}\n\nSee 'business-rules help {}' for its usage.",
+			error, command
+		),
+		(CliError::Usage(_), None) => {
+			eprintln!("{}\n\nSee 'business-rules help' for usage.", error)
+		},
+		_ => eprintln!("{}", error)
+	}
+	error.exit_code()
+}
+
+fn run_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let command = args.next_positional();
+	match command.as_deref() {
+		Some("purchase") => run_purchase_command(args, data),
+		Some("rule") => run_rule_command(args, data),
+		Some("process") => run_process_command(args, data),
+		Some("orders") => run_orders_command(args, data),
+		Some("validate") => run_validate_command(args, data),
+		Some("test-rules") => run_test_rules_command(args, data),
+		Some("explain") => run_explain_command(args, data),
+		Some("serve") => run_serve_command(args, data),
+		Some("tui") => run_tui_command(args, data),
+		Some("migrate") => run_migrate_command(args, data),
+		Some("import") => match args.next_positional().as_deref() {
+			Some("purchases") => run_import_command::<Purchase>(args, data),
+			Some("rules") => run_import_command::<Rule>(args, data),
+			_ => Err(CliError::Usage(
+				"Expected 'purchases' or 'rules' to import.".to_string()
+			))
+		},
+		Some("export") => match args.next_positional().as_deref() {
+			Some("purchases") => run_export_command::<Purchase>(args, data),
+			Some("rules") => run_export_command::<Rule>(args, data),
+			_ => Err(CliError::Usage(
+				"Expected 'purchases' or 'rules' to export.".to_string()
+			))
+		},
+		Some(command @ ("undo" | "redo")) => {
+			args.finish()?;
+			let done = if command == "undo" {
+				undo(data)
+			} else {
+				redo(data)
+			};
+			match done {
+				Ok(event) => {
+					println!("{}", event);
+					Ok(())
+				},
+				Err(UndoError::Empty) => Err(CliError::NotFound(format!(
+					"There is nothing to {}.",
+					command
+				))),
+				Err(UndoError::Io(error)) => Err(CliError::Io(error)),
+				Err(conflict) => Err(CliError::Conflict(conflict.to_string()))
+			}
+		},
+		Some("history") => {
+			let json = args.switch("json")?;
+			let title = args
+				.next_positional()
+				.ok_or_else(|| CliError::Usage("No title provided.".to_string()))?;
+			args.finish()?;
+			let events = history(data, &title)?;
+			if json {
+				println!("{}", to_json(&events)?);
+			} else if events.is_empty() {
+				println!("No changes to '{}' were logged.", title);
+			} else {
+				let described = events.iter().map(ToString::to_string).collect::<Vec<_>>();
+				println!("{}", described.join("\n\n"));
+			}
+			Ok(())
+		},
+		Some("help") => {
+			let command = args.next_positional();
+			args.finish()?;
+			match command {
+				None => println!("{}", USAGE),
+				Some(command) => println!(
+					"{}",
+					command_help(&command).ok_or_else(|| CliError::Usage(format!(
+						"There is no command '{}'.",
+						command
+					)))?
+				)
+			}
+			Ok(())
+		},
+		Some(unknown) => Err(CliError::Usage(format!("Unknown command '{}'.", unknown))),
+		None => Err(CliError::Usage("No command provided.".to_string()))
+	}
+}
+
+fn run_purchase_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let subcommand = args.next_positional();
+	match subcommand.as_deref() {
+		Some("list") => list_entries::<Purchase>(args, data),
+		Some("search") => search_entries::<Purchase>(args, data),
+		Some("show") => {
+			let json = args.switch("json")?;
+			let all = Purchase::load_from_disk(Purchase::get_path(data))?;
+			let found = find_purchase(&mut args, &all)?;
+			args.finish()?;
+			show_entry(found, json)
+		},
+		Some("add") => {
+			let new = Purchase {
+				title:        args.required_value("title")?.into(),
+				identifiers:  identifiers_from_values(args.values("identifier")?),
+				attributes:   attributes_from_values(args.values("attribute")?)?,
+				purchased_at: parse_time_value(args.value("purchased-at")?.as_deref())?,
+				id:           EntryId::generate()
+			};
+			args.finish()?;
+			if Purchase::insert_entry(data, new)? {
+				println!("Saved {} into its dataset.", Purchase::type_name_pretty());
+				Ok(())
+			} else {
+				Err(CliError::Conflict(format!(
+					"This exact {} already exists in its dataset.",
+					Purchase::type_name_pretty()
+				)))
+			}
+		},
+		Some("modify") => {
+			let all = Purchase::load_from_disk(Purchase::get_path(data))?;
+			let found = find_purchase(&mut args, &all)?;
+			let mut modified = found.clone();
+			if let Some(new_title) = args.value("new-title")? {
+				modified.title = new_title.into();
+			}
+			for identifier in identifiers_from_values(args.values("add-identifier")?).0 {
+				modified.identifiers.0.insert(identifier);
+			}
+			for identifier in identifiers_from_values(args.values("remove-identifier")?).0 {
+				if !modified.identifiers.0.remove(&identifier) {
+					return Err(CliError::NotFound(format!(
+						"{} is not an identifier for this entry.",
+						identifier
+					)));
+				}
+			}
+			modified
+				.attributes
+				.0
+				.extend(attributes_from_values(args.values("set-attribute")?)?.0);
+			for key in args.values("remove-attribute")? {
+				if modified.attributes.0.remove(key.trim()).is_none() {
+					return Err(CliError::NotFound(format!(
+						"'{}' is not an attribute of this entry.",
+						key.trim()
+					)));
+				}
+			}
+			if let Some(purchased_at) = args.value("purchased-at")? {
+				modified.purchased_at = parse_time_value(Some(&purchased_at))?;
+			}
+			args.finish()?;
+			replace_entry(data, found, modified)
+		},
+		Some("delete") => {
+			let all = Purchase::load_from_disk(Purchase::get_path(data))?;
+			let found = find_purchase(&mut args, &all)?;
+			args.finish()?;
+			delete_entry(data, found)
+		},
+		Some(unknown) => Err(CliError::Usage(format!(
+			"Unknown purchase command '{}'.",
+			unknown
+		))),
+		None => Err(CliError::Usage("No purchase command provided.".to_string()))
+	}
+}
+
+fn run_rule_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let subcommand = args.next_positional();
+	match subcommand.as_deref() {
+		Some("list") => list_entries::<Rule>(args, data),
+		Some("search") => search_entries::<Rule>(args, data),
+		Some("show") => {
+			let json = args.switch("json")?;
+			let all = Rule::load_from_disk(Rule::get_path(data))?;
+			let found = find_rule(&mut args, &all)?;
+			args.finish()?;
+			show_entry(found, json)
+		},
+		Some("add") => {
+			let new = Rule {
+				title:           args.required_value("title")?.into(),
+				process_action:  ProcessAction::from(args.required_value("action")?.as_str()),
+				trigger:         parse_trigger(&args.required_value("trigger")?)?,
+				scope:           parse_scope(args.value("scope")?.as_deref())?.unwrap_or_default(),
+				priority:        parse_priority(args.value("priority")?.as_deref())?
+					.unwrap_or_default(),
+				stop_processing: parse_yes_no(args.value("stop")?.as_deref())?.unwrap_or_default(),
+				exclusion_group: args
+					.value("group")?
+					.filter(|group| !group.is_empty())
+					.map(Arc::from),
+				valid_from:      parse_date_value(args.value("valid-from")?.as_deref())?,
+				valid_until:     parse_date_value(args.value("valid-until")?.as_deref())?,
+				id:              EntryId::generate()
+			};
+			let force = args.switch("force")?;
+			args.finish()?;
+			check_rule(data, None, &new, force)?;
+			if Rule::insert_entry(data, new)? {
+				println!("Saved {} into its dataset.", Rule::type_name_pretty());
+				Ok(())
+			} else {
+				Err(CliError::Conflict(format!(
+					"This exact {} already exists in its dataset.",
+					Rule::type_name_pretty()
+				)))
+			}
+		},
+		Some("modify") => {
+			let all = Rule::load_from_disk(Rule::get_path(data))?;
+			let found = find_rule(&mut args, &all)?;
+			let mut modified = found.clone();
+			if let Some(new_title) = args.value("new-title")? {
+				modified.title = new_title.into();
+			}
+			if let Some(action) = args.value("action")? {
+				modified.process_action = ProcessAction::from(action.as_str());
+			}
+			if let Some(trigger) = args.value("trigger")? {
+				modified.trigger = parse_trigger(&trigger)?;
+			}
+			if let Some(scope) = parse_scope(args.value("scope")?.as_deref())? {
+				modified.scope = scope;
+			}
+			if let Some(priority) = parse_priority(args.value("priority")?.as_deref())? {
+				modified.priority = priority;
+			}
+			if let Some(stop_processing) = parse_yes_no(args.value("stop")?.as_deref())? {
+				modified.stop_processing = stop_processing;
+			}
+			if let Some(group) = args.value("group")? {
+				modified.exclusion_group =
+					Some(group).filter(|group| !group.is_empty()).map(Arc::from);
+			}
+			if let Some(valid_from) = args.value("valid-from")? {
+				modified.valid_from = parse_date_value(Some(&valid_from))?;
+			}
+			if let Some(valid_until) = args.value("valid-until")? {
+				modified.valid_until = parse_date_value(Some(&valid_until))?;
+			}
+			let force = args.switch("force")?;
+			let what_if = args.switch("what-if")?;
+			args.finish()?;
+			if what_if {
+				let purchases = Purchase::load_from_disk(Purchase::get_path(data))?;
+				println!(
+					"{}",
+					TriggerImpact::between(&found.trigger, &modified.trigger, &purchases)
+				);
+				return Ok(());
+			}
+			check_rule(data, Some(found), &modified, force)?;
+			replace_entry(data, found, modified)
+		},
+		Some("delete") => {
+			let all = Rule::load_from_disk(Rule::get_path(data))?;
+			let found = find_rule(&mut args, &all)?;
+			args.finish()?;
+			delete_entry(data, found)
+		},
+		Some(unknown) => Err(CliError::Usage(format!(
+			"Unknown rule command '{}'.",
+			unknown
+		))),
+		None => Err(CliError::Usage("No rule command provided.".to_string()))
+	}
+}
+
+fn run_explain_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let json = args.switch("json")?;
+	let all_purchases = Purchase::load_from_disk(Purchase::get_path(data))?;
+	let purchase = find_purchase_by(&mut args, &all_purchases, "purchase", "purchase-id")?;
+	let rule_title = args.value("rule")?;
+	let as_of = parse_time_value(args.value("as-of")?.as_deref())?;
+	args.finish()?;
+	let rules = Rule::load_from_disk(Rule::get_path(data))?;
+	let mut explanations = purchase.explain_processing(&rules, purchase.evaluation_time(as_of));
+	if let Some(title) = rule_title {
+		let found = single_match(
+			Rule::find_all_by_title(&title, rules.iter()),
+			&title,
+			"rename one of them interactively"
+		)?;
+		explanations.retain(|explanation| explanation.rule == found);
+	}
+	if json {
+		println!("{}", to_json(&explanations)?);
+	} else {
+		let explained = explanations
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Vec<_>>();
+		println!("{}", explained.join("\n\n"));
+	}
+	Ok(())
+}
+
+fn run_serve_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let address = args
+		.value("address")?
+		.unwrap_or_else(|| "127.0.0.1:8080".to_string());
+	args.finish()?;
+	let server = Server::bind(&address)?;
+	match server.address() {
+		Some(address) => println!("Listening on http://{}, stop with Ctrl+C.", address),
+		None => println!("Listening on {}, stop with Ctrl+C.", address)
+	}
+	server.run(data);
+	Ok(())
+}
+
+fn run_tui_command(args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	args.finish()?;
+	Ok(tui::run(data)?)
+}
+
+fn run_migrate_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let force = args.switch("force")?;
+	args.finish()?;
+	let directory = data.purchase_path.parent().unwrap_or(Path::new(""));
+	let database_path = directory.join(ApplicationData::DATABASE_FILE);
+	if !force && (has_entries::<Purchase>(&database_path)? || has_entries::<Rule>(&database_path)?)
+	{
+		return Err(CliError::Conflict(format!(
+			"{} already holds data, use '--force' to replace it.",
+			database_path.display()
+		)));
+	}
+	let purchases = Purchase::load_from_disk(&directory.join("all_purchases.json"))?;
+	let rules = Rule::load_from_disk(&directory.join("all_rules.json"))?;
+	Purchase::save_to_disk(&database_path, &purchases)?;
+	Rule::save_to_disk(&database_path, &rules)?;
+	println!(
+		"Imported {} purchases and {} rules into {}, which is used from now on.",
+		purchases.len(),
+		rules.len(),
+		database_path.display()
+	);
+	Ok(())
+}
+
+/// The format option, falling back to the extension of the file.
+fn transfer_format(args: &mut Arguments, path: Option<&Path>) -> Result<Format, CliError> {
+	match args.value("format")? {
+		Some(name) => Format::from_name(&name).ok_or_else(|| {
+			CliError::Usage(format!(
+				"Invalid format '{}', expected 'csv' or 'yaml'.",
+				name
+			))
+		}),
+		None => path.and_then(Format::of_path).ok_or_else(|| {
+			CliError::Usage("Use '--format' to choose between 'csv' and 'yaml'.".to_string())
+		})
+	}
+}
+
+fn run_import_command<T>(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError>
+where
+	T: DatabaseEntry + Transferable
+{
+	let dry_run = args.switch("dry-run")?;
+	let force = args.switch("force")?;
+	let file = args
+		.next_positional()
+		.ok_or_else(|| CliError::Usage("No file to import provided.".to_string()))?;
+	let format = transfer_format(&mut args, Some(Path::new(&file)))?;
+	args.finish()?;
+	let contents = std::fs::read_to_string(&file)?;
+	let rows = read_rows::<T>(&contents, format)
+		.map_err(|error| CliError::Invalid(format!("{} could not be read: {}", file, error)))?;
+	let path = T::get_path(data);
+	let existing = T::load_from_disk(path)?;
+	let plan = ImportPlan::new(&existing, rows);
+	println!("{}", plan);
+	let mut after = existing.clone();
+	for (_, change) in &plan.changes {
+		change.apply_to(&mut after);
+	}
+	let changed_titles = plan
+		.changes
+		.iter()
+		.filter_map(|(_, change)| change.after())
+		.map(|entry| entry.name())
+		.collect::<BTreeSet<_>>();
+	let findings = T::validate_set(&after)
+		.into_iter()
+		.filter(|finding| changed_titles.contains(finding.rule.as_ref()))
+		.collect::<Vec<_>>();
+	for finding in &findings {
+		eprintln!("{}", finding);
+	}
+	if !force && findings.iter().any(Finding::is_error) {
+		return Err(CliError::Invalid(
+			"Nothing imported, use '--force' to import anyway.".to_string()
+		));
+	}
+	if !dry_run {
+		let changes = plan
+			.changes
+			.into_iter()
+			.map(|(_, change)| change)
+			.collect::<Vec<_>>();
+		let applied = T::apply_all_recorded(path, &changes)?;
+		println!("Imported {} of {} changes.", applied, changes.len());
+	}
+	if plan.errors.is_empty() {
+		Ok(())
+	} else {
+		Err(CliError::Invalid(format!(
+			"{} invalid rows were left out.",
+			plan.errors.len()
+		)))
+	}
+}
+
+fn run_export_command<T>(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError>
+where
+	T: DatabaseEntry + Transferable
+{
+	let file = args.next_positional();
+	let format = transfer_format(&mut args, file.as_deref().map(Path::new))?;
+	args.finish()?;
+	let all = T::load_from_disk(T::get_path(data))?;
+	let written = write_rows(all.iter(), format)?;
+	match file {
+		Some(file) => {
+			std::fs::write(&file, written)?;
+			println!("Exported {} entries to {}.", all.len(), file);
+		},
+		None => print!("{}", written)
+	}
+	Ok(())
+}
+
+fn run_orders_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	match args.next_positional().as_deref() {
+		Some("run") => {},
+		Some(unknown) => {
+			return Err(CliError::Usage(format!(
+				"Unknown orders command '{}'.",
+				unknown
+			)))
+		},
+		None => return Err(CliError::Usage("No orders command provided.".to_string()))
+	}
+	let json = args.switch("json")?;
+	let dry_run = args.switch("dry-run")?;
+	let execute_into = args.value("execute")?;
+	if dry_run && execute_into.is_some() {
+		return Err(CliError::Usage(
+			"'--dry-run' and '--execute' can't be combined.".to_string()
+		));
+	}
+	let rules_file = args.value("rules")?;
+	let as_of = parse_time_value(args.value("as-of")?.as_deref())?;
+	let file = args
+		.next_positional()
+		.ok_or_else(|| CliError::Usage("No order file provided.".to_string()))?;
+	args.finish()?;
+	let purchases = Purchase::load_from_disk(Purchase::get_path(data))?;
+	let contents = std::fs::read_to_string(&file)?;
+	let batch = OrderBatch::read(
+		&contents,
+		OrderFileFormat::of_path(Path::new(&file)),
+		&purchases
+	)
+	.map_err(|error| CliError::Invalid(format!("{} could not be read: {}", file, error)))?;
+	let rules = match rules_file {
+		Some(rules_file) => load_rules_from(Path::new(&rules_file))?,
+		None => Rule::load_from_disk(Rule::get_path(data))?
+	};
+	for error in &batch.errors {
+		eprintln!("Skipped {}", error);
+	}
+	let (valid, invalid): (Vec<_>, Vec<_>) = batch
+		.orders
+		.iter()
+		.partition(|order| order.errors.is_empty());
+	for order in &invalid {
+		for error in &order.errors {
+			eprintln!("Skipped order '{}', {}", order.reference, error);
+		}
+	}
+	let references = valid
+		.iter()
+		.map(|order| (&order.order, order.reference.as_str()));
+	if !execute_orders(references, &rules, as_of, dry_run, execute_into.as_deref())? {
+		if json {
+			let reports = valid
+				.iter()
+				.map(|order| {
+					let plan = order
+						.order
+						.get_processing_plan(&rules, order.order.evaluation_time(as_of));
+					let mut report = plan_to_json(&plan);
+					report["order"] = json!(order.reference);
+					report
+				})
+				.collect::<Vec<_>>();
+			println!("{}", to_json(&reports)?);
+		} else {
+			for (index, order) in valid.iter().enumerate() {
+				if index > 0 {
+					println!();
+				}
+				println!("Order '{}':", order.reference);
+				let plan = order
+					.order
+					.get_processing_plan(&rules, order.order.evaluation_time(as_of));
+				print_plan(&plan, false)?;
+			}
+		}
+	}
+	match batch.errors.len() + invalid.len() {
+		0 => Ok(()),
+		_ => Err(CliError::Invalid(format!(
+			"{} of {} orders and {} unreadable lines were skipped.",
+			invalid.len(),
+			batch.orders.len(),
+			batch.errors.len()
+		)))
+	}
+}
+/// Rules from a CSV or YAML file as written by 'export', or from any data file.
+/// Data files are only read.
+fn load_rules_from(path: &Path) -> Result<BTreeSet<Rule>, CliError> {
+	let Some(format) = Format::of_path(path) else {
+		return Ok(storage_for::<Rule>(path).load()?);
+	};
+	let mut rules = BTreeSet::new();
+	let mut errors = Vec::new();
+	for (row, rule) in read_rows::<Rule>(&std::fs::read_to_string(path)?, format)? {
+		match rule {
+			Ok(rule) => {
+				rules.insert(rule);
+			},
+			Err(message) => errors.push(RowReport { row, message }.to_string())
+		}
+	}
+	if errors.is_empty() {
+		Ok(rules)
+	} else {
+		Err(CliError::Invalid(format!(
+			"{} has invalid rules:\n{}",
+			path.display(),
+			errors.join("\n")
+		)))
+	}
+}
+
+/// Runs the rule fixtures, or with '--update' records what the rules give as the new
+/// expectations.
+fn run_test_rules_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let json = args.switch("json")?;
+	let update = args.switch("update")?;
+	let rules_path = args.value("rules")?;
+	let as_of = parse_time_value(args.value("as-of")?.as_deref())?;
+	let path = args
+		.next_positional()
+		.map(PathBuf::from)
+		.unwrap_or_else(|| Rule::get_path(data).with_file_name(FIXTURE_FILE));
+	args.finish()?;
+	let fixtures = read_fixtures(&std::fs::read_to_string(&path)?)?;
+	let rules = match rules_path {
+		Some(rules_path) => load_rules_from(Path::new(&rules_path))?,
+		None => Rule::load_from_disk(Rule::get_path(data))?
+	};
+	let report = FixtureReport::run(&fixtures, &rules, as_of);
+	if update {
+		let updated = updated_fixtures(&fixtures, &rules, as_of);
+		std::fs::write(&path, write_fixtures(&updated)?)?;
+		println!(
+			"Updated the expected steps of {} of {} fixtures in {}.",
+			report.failed(),
+			fixtures.len(),
+			path.display()
+		);
+		return Ok(());
+	}
+	if json {
+		println!("{}", to_json(&report)?);
+	} else {
+		println!("{}", report);
+	}
+	match report.failed() {
+		0 => Ok(()),
+		failed => Err(CliError::Invalid(format!("{} fixture(s) failed.", failed)))
+	}
+}
+
+fn run_validate_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let json = args.switch("json")?;
+	args.finish()?;
+	let rules = Rule::load_from_disk(Rule::get_path(data))?;
+	let purchases = Purchase::load_from_disk(Purchase::get_path(data))?;
+	let findings = validate_rules(&rules, Some(&purchases));
+	if json {
+		println!("{}", to_json(&findings)?);
+	} else if findings.is_empty() {
+		println!("No problems found in {} rules.", rules.len());
+	} else {
+		for finding in &findings {
+			println!("{}", finding);
+		}
+	}
+	match findings.iter().filter(|finding| finding.is_error()).count() {
+		0 => Ok(()),
+		errors => Err(CliError::Invalid(format!(
+			"Validation found {} error(s).",
+			errors
+		)))
+	}
+}
+
+/// Reports problems with `new` among the other rules, refusing errors unless `force` is given.
+fn check_rule(
+	data: &ApplicationData,
+	old: Option<&Rule>,
+	new: &Rule,
+	force: bool
+) -> Result<(), CliError> {
+	let mut rules = Rule::load_from_disk(Rule::get_path(data))?;
+	if let Some(old) = old {
+		rules.remove(old);
+	}
+	rules.insert(new.clone());
+	let findings = validate_rules(&rules, None)
+		.into_iter()
+		.filter(|finding| finding.rule == new.title)
+		.collect::<Vec<_>>();
+	for finding in &findings {
+		eprintln!("{}", finding);
+	}
+	if !force && findings.iter().any(Finding::is_error) {
+		return Err(CliError::Invalid(
+			"Not saved, use '--force' to save the rule anyway.".to_string()
+		));
+	}
+	Ok(())
+}
+
+fn run_process_command(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError> {
+	let json = args.switch("json")?;
+	let dry_run = args.switch("dry-run")?;
+	let execute_into = args.value("execute")?;
+	let reference = args.value("reference")?;
+	let as_of = parse_time_value(args.value("as-of")?.as_deref())?;
+	if dry_run && execute_into.is_some() {
+		return Err(CliError::Usage(
+			"'--dry-run' and '--execute' can't be combined.".to_string()
+		));
+	}
+	if execute_into.is_some() && reference.is_none() {
+		return Err(CliError::Usage(
+			"'--execute' needs a '--reference' identifying the order.".to_string()
+		));
+	}
+	let reference = reference.unwrap_or_default();
+	let all_purchases = Purchase::load_from_disk(Purchase::get_path(data))?;
+	let order_titles = args.values("order")?;
+	// each purchase processed on its own counts as a single item order
+	let (orders, is_single_order) = if !order_titles.is_empty() {
+		let mut purchases = PurchaseCollection(BTreeMap::new());
+		for title in order_titles {
+			let found = single_match(
+				Purchase::find_all_by_title(&title, all_purchases.iter()),
+				&title,
+				"use '--purchase' with '--identifier' instead"
+			)?;
+			*purchases.0.entry(found.clone()).or_default() += 1;
+		}
+		(vec![Order { purchases }], true)
+	} else if args.switch("all")? {
+		let orders = all_purchases
+			.iter()
+			.map(|purchase| Order {
+				purchases: PurchaseCollection::single(purchase.clone())
+			})
+			.collect();
+		(orders, false)
+	} else {
+		let found = find_purchase_by(&mut args, &all_purchases, "purchase", "purchase-id")?;
+		let order = Order {
+			purchases: PurchaseCollection::single(found.clone())
+		};
+		(vec![order], false)
+	};
+	args.finish()?;
+	let rules = Rule::load_from_disk(Rule::get_path(data))?;
+	let references = orders.iter().map(|order| (order, reference.as_str()));
+	if execute_orders(references, &rules, as_of, dry_run, execute_into.as_deref())? {
+		return Ok(());
+	}
+	if is_single_order {
+		let at = orders[0].evaluation_time(as_of);
+		return print_plan(&orders[0].get_processing_plan(&rules, at), json);
+	}
+	let purchases = orders
+		.iter()
+		.flat_map(|order| order.purchases.0.keys())
+		.collect::<Vec<_>>();
+	let label_steps = purchases.len() > 1;
+	if json {
+		let processed = purchases
+			.iter()
+			.map(|purchase| {
+				json!({
+					"title": purchase.title,
+					"steps": purchase.get_processing_steps(&rules, purchase.evaluation_time(as_of))
+				})
+			})
+			.collect::<Vec<_>>();
+		println!("{}", to_json(&processed)?);
+	} else {
+		for purchase in purchases {
+			for step in purchase.get_processing_steps(&rules, purchase.evaluation_time(as_of)) {
+				if label_steps {
+					println!("{}\t{}", purchase.title, step.description());
+				} else {
+					println!("{}", step.description());
+				}
+			}
+		}
+	}
+	Ok(())
+}
+/// Runs the processing steps of every order through the executor chosen by '--dry-run' or
+/// '--execute', returning `false` if neither was chosen.
+fn execute_orders<'a>(
+	orders: impl Iterator<Item = (&'a Order, &'a str)>,
+	rules: &BTreeSet<Rule>,
+	as_of: Option<DateTime<Utc>>,
+	dry_run: bool,
+	execute_into: Option<&str>
+) -> Result<bool, CliError> {
+	if dry_run {
+		let mut executor = DryRunExecutor::default();
+		for (order, reference) in orders {
+			order
+				.get_processing_plan(rules, order.evaluation_time(as_of))
+				.execute(&mut executor, reference)?;
+		}
+		for entry in executor.log {
+			println!("{}", entry);
+		}
+		return Ok(true);
+	}
+	if let Some(directory) = execute_into {
+		let mut executor = ArtifactExecutor::in_directory(Path::new(directory));
+		for (order, reference) in orders {
+			order
+				.get_processing_plan(rules, order.evaluation_time(as_of))
+				.execute(&mut executor, reference)?;
+		}
+		for entry in executor.log() {
+			println!("{}", entry);
+		}
+		return Ok(true);
+	}
+	Ok(false)
+}
+pub(crate) fn plan_to_json(plan: &ProcessingPlan) -> serde_json::Value {
+	let lines = plan
+		.lines
+		.iter()
+		.map(|line| {
+			json!({
+				"title": line.purchase.title,
+				"quantity": line.quantity,
+				"steps": line.steps
+			})
+		})
+		.collect::<Vec<_>>();
+	json!({ "lines": lines, "order_steps": plan.order_steps })
+}
+/// Order-wide steps are labelled with an empty title.
+fn print_plan(plan: &ProcessingPlan, json: bool) -> Result<(), CliError> {
+	if json {
+		println!("{}", to_json(&plan_to_json(plan))?);
+	} else {
+		for line in &plan.lines {
+			for step in &line.steps {
+				println!("{}\t{}", line.purchase.title, step.description());
+			}
+		}
+		for step in &plan.order_steps {
+			println!("\t{}", step.description());
+		}
+	}
+	Ok(())
+}
+
+fn list_entries<T>(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError>
+where
+	T: DatabaseEntry
+{
+	let json = args.switch("json")?;
+	args.finish()?;
+	let all = T::load_from_disk(T::get_path(data))?;
+	if json {
+		println!("{}", to_json(&all)?);
+	} else {
+		for entry in &all {
+			println!("{}", entry.name());
+		}
+	}
+	Ok(())
+}
+
+/// Lists the matches of the query, best first, with their score.
+fn search_entries<T>(mut args: Arguments, data: &ApplicationData) -> Result<(), CliError>
+where
+	T: DatabaseEntry
+{
+	let json = args.switch("json")?;
+	let limit = args
+		.value("limit")?
+		.map(|limit| {
+			limit.parse::<usize>().map_err(|_| {
+				CliError::Usage(format!("Invalid limit '{}', expected a number.", limit))
+			})
+		})
+		.transpose()?;
+	let source = std::iter::from_fn(|| args.next_positional())
+		.collect::<Vec<_>>()
+		.join(" ");
+	args.finish()?;
+	let query = source
+		.parse::<Query>()
+		.map_err(|error| CliError::Usage(format!("Invalid query:\n{}", error.pointed(&source))))?;
+	let all = T::load_from_disk(T::get_path(data))?;
+	let mut ranked = T::search(&query, all.iter());
+	ranked.truncate(limit.unwrap_or(usize::MAX));
+	if json {
+		println!("{}", to_json(&ranked)?);
+	} else if ranked.is_empty() {
+		return Err(CliError::NotFound(format!(
+			"No {} matches '{}'.",
+			T::type_name_pretty().to_lowercase(),
+			query
+		)));
+	} else {
+		for scored in &ranked {
+			println!("{}\t{}", scored, scored.entry.id());
+		}
+	}
+	Ok(())
+}
+
+fn show_entry<T>(entry: &T, json: bool) -> Result<(), CliError>
+where
+	T: DatabaseEntry
+{
+	if json {
+		println!("{}", to_json(entry)?);
+	} else {
+		println!("{}", entry);
+	}
+	Ok(())
+}
+
+fn replace_entry<T>(data: &ApplicationData, old: &T, new: T) -> Result<(), CliError>
+where
+	T: DatabaseEntry
+{
+	if *old == new {
+		println!("No modifications were made.");
+		Ok(())
+	} else if T::replace_entry(data, old, new)? {
+		println!("Saved modification to disk.");
+		Ok(())
+	} else {
+		Err(CliError::NotFound(format!(
+			"{} disappeared from its dataset before it could be modified.",
+			T::type_name_pretty()
+		)))
+	}
+}
+
+fn delete_entry<T>(data: &ApplicationData, old: &T) -> Result<(), CliError>
+where
+	T: DatabaseEntry
+{
+	if T::remove_entry(data, old)? {
+		println!("{} was removed from dataset.", T::type_name_pretty());
+		Ok(())
+	} else {
+		Err(CliError::NotFound(format!(
+			"{} disappeared from its dataset before it could be deleted.",
+			T::type_name_pretty()
+		)))
+	}
+}
+
+fn find_purchase<'a>(
+	args: &mut Arguments,
+	all: &'a BTreeSet<Purchase>
+) -> Result<&'a Purchase, CliError> {
+	find_purchase_by(args, all, "title", "id")
+}
+/// Finds a purchase by its ID option, or else by its title option narrowed by any
+/// `--identifier` options.
+fn find_purchase_by<'a>(
+	args: &mut Arguments,
+	all: &'a BTreeSet<Purchase>,
+	title_option: &str,
+	id_option: &str
+) -> Result<&'a Purchase, CliError> {
+	if let Some(id) = args.value(id_option)? {
+		return find_by_id(&id, all);
+	}
+	let title = args.required_value(title_option)?;
+	let narrowing = identifiers_from_values(args.values("identifier")?);
+	let mut found = Purchase::find_all_by_title(&title, all.iter());
+	found.retain(|purchase| {
+		narrowing
+			.0
+			.iter()
+			.all(|identifier| purchase.has_identifier(identifier))
+	});
+	single_match(found, &title, "narrow the search with '--identifier'")
+}
+fn find_rule<'a>(args: &mut Arguments, all: &'a BTreeSet<Rule>) -> Result<&'a Rule, CliError> {
+	if let Some(id) = args.value("id")? {
+		return find_by_id(&id, all);
+	}
+	let title = args.required_value("title")?;
+	single_match(
+		Rule::find_all_by_title(&title, all.iter()),
+		&title,
+		"rename one of them interactively"
+	)
+}
+fn find_by_id<'a, T>(id: &str, all: &'a BTreeSet<T>) -> Result<&'a T, CliError>
+where
+	T: DatabaseEntry
+{
+	if id.trim().len() < EntryId::MIN_PREFIX {
+		return Err(CliError::Usage(format!(
+			"Entry IDs need at least {} characters.",
+			EntryId::MIN_PREFIX
+		)));
+	}
+	let mut found = T::find_all_by_id(id, all.iter());
+	match found.len() {
+		0 => Err(CliError::NotFound(format!(
+			"No {} with the ID '{}' could be found.",
+			T::type_name_pretty().to_lowercase(),
+			id
+		))),
+		1 => Ok(found.remove(0)),
+		_ => Err(CliError::Conflict(format!(
+			"{} entries have an ID starting with '{}', give more of it.",
+			found.len(),
+			id
+		)))
+	}
+}
+fn single_match<'a, T>(mut found: Vec<&'a T>, title: &str, hint: &str) -> Result<&'a T, CliError>
+where
+	T: DatabaseEntry
+{
+	match found.len() {
+		0 => Err(CliError::NotFound(format!(
+			"No {} with the title '{}' could be found.",
+			T::type_name_pretty().to_lowercase(),
+			title
+		))),
+		1 => Ok(found.remove(0)),
+		_ => Err(CliError::Conflict(format!(
+			"{} entries have the title '{}', {}:\n{}",
+			found.len(),
+			title,
+			hint,
+			found
+				.iter()
+				.map(ToString::to_string)
+				.collect::<Vec<_>>()
+				.join("\n\n")
+		)))
+	}
+}
+
+fn identifiers_from_values(values: Vec<String>) -> IdentifierCollection {
+	IdentifierCollection(
+		values
+			.iter()
+			.flat_map(|value| value.split(';'))
+			.map(str::trim)
+			.filter(|identifier| !identifier.is_empty())
+			.map(Identifier::from)
+			.collect()
+	)
+}
+
+/// Attributes given as `key=value`.
+fn attributes_from_values(values: Vec<String>) -> Result<AttributeMap, CliError> {
+	let mut attributes = AttributeMap::default();
+	for value in values {
+		let (key, value) = parse_attribute(&value).map_err(CliError::Usage)?;
+		attributes.0.insert(key, value);
+	}
+	Ok(attributes)
+}
+fn parse_trigger(trigger: &str) -> Result<RuleTrigger, CliError> {
+	trigger.parse().map_err(|error: ParseError| {
+		CliError::Usage(format!("Invalid trigger:\n{}", error.pointed(trigger)))
+	})
+}
+
+fn parse_scope(scope: Option<&str>) -> Result<Option<RuleScope>, CliError> {
+	match scope {
+		None => Ok(None),
+		Some("line") => Ok(Some(RuleScope::Line)),
+		Some("order") => Ok(Some(RuleScope::Order)),
+		Some(other) => Err(CliError::Usage(format!(
+			"Invalid scope '{}', expected 'line' or 'order'.",
+			other
+		)))
+	}
+}
+
+fn parse_priority(priority: Option<&str>) -> Result<Option<i32>, CliError> {
+	priority
+		.map(|priority| {
+			priority.parse().map_err(|_| {
+				CliError::Usage(format!(
+					"Invalid priority '{}', expected an integer.",
+					priority
+				))
+			})
+		})
+		.transpose()
+}
+
+/// An empty date clears the date.
+fn parse_date_value(date: Option<&str>) -> Result<Option<NaiveDate>, CliError> {
+	match date.map(str::trim) {
+		None | Some("") => Ok(None),
+		Some(date) => parse_date(date).map(Some).map_err(CliError::Usage)
+	}
+}
+
+/// An empty time clears the time.
+fn parse_time_value(time: Option<&str>) -> Result<Option<DateTime<Utc>>, CliError> {
+	match time.map(str::trim) {
+		None | Some("") => Ok(None),
+		Some(time) => parse_time(time).map(Some).map_err(CliError::Usage)
+	}
+}
+
+fn parse_yes_no(answer: Option<&str>) -> Result<Option<bool>, CliError> {
+	match answer {
+		None => Ok(None),
+		Some("yes") => Ok(Some(true)),
+		Some("no") => Ok(Some(false)),
+		Some(other) => Err(CliError::Usage(format!(
+			"Invalid answer '{}', expected 'yes' or 'no'.",
+			other
+		)))
+	}
+}
+
+fn to_json(value: &impl Serialize) -> Result<String, CliError> {
+	to_string_pretty(value).map_err(|error| CliError::Io(error.into()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn arguments(args: &[&str]) -> Arguments {
+		Arguments::parse(args.iter().map(ToString::to_string))
+	}
+
+	#[test]
+	fn parses_options_and_positionals() {
+		let mut args = arguments(&[
+			"purchase",
+			"add",
+			"--title",
+			"1984",
+			"--identifier",
+			"book",
+			"--identifier",
+			"physical;novel",
+			"--json"
+		]);
+		assert_eq!(args.next_positional().as_deref(), Some("purchase"));
+		assert_eq!(args.next_positional().as_deref(), Some("add"));
+		assert_eq!(args.required_value("title").unwrap(), "1984");
+		assert_eq!(
+			identifiers_from_values(args.values("identifier").unwrap()),
+			IdentifierCollection(["book".into(), "novel".into(), "physical".into()].into())
+		);
+		assert!(args.switch("json").unwrap());
+		assert!(args.finish().is_ok());
+	}
+
+	#[test]
+	fn rejects_leftover_and_repeated_options() {
+		let mut args = arguments(&["--title", "a", "--title", "b"]);
+		assert!(matches!(args.value("title"), Err(CliError::Usage(_))));
+		assert!(matches!(
+			arguments(&["--unknown"]).finish(),
+			Err(CliError::Usage(_))
+		));
+		assert!(matches!(
+			arguments(&["--title"]).required_value("title"),
+			Err(CliError::Usage(_))
+		));
+	}
+
+	#[test]
+	fn parses_values_after_equals_signs() {
+		let mut args = arguments(&[
+			"--title=--draft--",
+			"--attribute=size=XL",
+			"--group=",
+			"--json",
+			"--priority",
+			"2"
+		]);
+		assert_eq!(args.required_value("title").unwrap(), "--draft--");
+		assert_eq!(args.required_value("attribute").unwrap(), "size=XL");
+		assert_eq!(args.required_value("group").unwrap(), "");
+		assert!(args.switch("json").unwrap());
+		assert_eq!(args.required_value("priority").unwrap(), "2");
+		assert!(args.finish().is_ok());
+		assert!(matches!(
+			arguments(&["--json=yes"]).switch("json"),
+			Err(CliError::Usage(_))
+		));
+	}
+
+	#[test]
+	fn switches_leave_positionals_alone() {
+		let mut args = arguments(&["history", "--json", "1984"]);
+		assert!(args.switch("json").unwrap());
+		assert_eq!(args.next_positional().as_deref(), Some("history"));
+		assert_eq!(args.next_positional().as_deref(), Some("1984"));
+		assert!(args.finish().is_ok());
+
+		let mut args = arguments(&[
+			"purchase", "search", "--json", "ski", "--force", "--title", "x"
+		]);
+		assert_eq!(args.next_positional().as_deref(), Some("purchase"));
+		assert_eq!(args.next_positional().as_deref(), Some("search"));
+		assert!(args.switch("json").unwrap());
+		assert!(args.switch("force").unwrap());
+		assert_eq!(args.next_positional().as_deref(), Some("ski"));
+		assert_eq!(args.required_value("title").unwrap(), "x");
+		assert!(args.finish().is_ok());
+	}
+
+	#[test]
+	fn every_command_has_help() {
+		let commands = USAGE
+			.split("Commands:\n")
+			.nth(1)
+			.unwrap()
+			.lines()
+			.take_while(|line| !line.is_empty())
+			.map(|line| line.split_whitespace().next().unwrap())
+			.collect::<Vec<_>>();
+		assert_eq!(commands.len(), 16);
+		for command in commands {
+			assert!(command_help(command).is_some(), "{}", command);
+		}
+		assert!(command_help("purchases").is_none());
+	}
+}
@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
-use crate::library::io::get_reply;
+use business_rules::console::Console;
+
+use super::io::{say, try_get_reply};
 
 pub struct Decision<F: 'static> {
 	pub prompt:           Arc<str>,
@@ -50,18 +52,26 @@ impl<T> Decision<T>
 where
 	T: Clone
 {
-	pub fn run_prompt(&self) -> Option<T> {
-		println!();
-		println!("{}", self.prompt);
+	/// `None` when canceled, or once there are no replies left.
+	pub fn run_prompt(&self, console: &dyn Console) -> Option<T> {
+		say!(console);
+		say!(console, "{}", self.prompt);
 		for action_answer in self.possible_choices.iter().map(|action| &action.answer) {
-			println!(" - [{}] {}", action_answer.key, action_answer.choice_text);
+			say!(
+				console,
+				" - [{}] {}",
+				action_answer.key,
+				action_answer.choice_text
+			);
 		}
-		println!(
+		say!(
+			console,
 			" - [{}] {}",
-			self.cancel_answer.key, self.cancel_answer.choice_text
+			self.cancel_answer.key,
+			self.cancel_answer.choice_text
 		);
 		let chosen_action = 'input_loop: loop {
-			let reply = get_reply().to_uppercase();
+			let reply = try_get_reply(console)?.to_uppercase();
 			for action in &self.possible_choices {
 				if reply.contains(&action.answer.key.to_uppercase()) {
 					break 'input_loop action;
@@ -70,7 +80,10 @@ where
 			if reply.contains(self.cancel_answer.key) {
 				return None;
 			}
-			println!("You must use one of the key letters above marked in '[]' Try again.");
+			say!(
+				console,
+				"You must use one of the key letters above marked in '[]' Try again."
+			);
 		};
 		Some(chosen_action.value.clone())
 	}
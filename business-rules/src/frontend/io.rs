@@ -0,0 +1,198 @@
+use std::{
+	collections::BTreeSet,
+	io,
+	io::{stdin, stdout, Write},
+	path::Path
+};
+
+use business_rules::{
+	audit::{record, EventKind},
+	console::Console,
+	dataset::Saved,
+	storage::{storage_for, Change},
+	types::Named,
+	validation::Finding
+};
+
+/// Like `println!`, but shown on the given console.
+macro_rules! say {
+	($console:expr) => {
+		$console.write_line("")
+	};
+	($console:expr, $($arg:tt)*) => {
+		$console.write_line(&format!($($arg)*))
+	};
+}
+pub(crate) use say;
+
+/// Saving that asks the user what to do when it fails or finds problems.
+pub(crate) trait InteractiveSaved
+where
+	Self: Saved
+{
+	fn load_from_disk_retrying(console: &dyn Console, path: &Path) -> io::Result<BTreeSet<Self>> {
+		'attempt_loop: loop {
+			let load_result = Self::load_from_disk(path);
+			if load_result.is_ok()
+				|| !prompt_yes_no_question(
+					console,
+					format!(
+						"Attempt to load {} failed. Do you want to try again?",
+						Self::type_name_pretty()
+					)
+				) {
+				break 'attempt_loop load_result;
+			}
+		}
+	}
+	/// Reports problems with the entry the change leaves in the data, failing if the user doesn't
+	/// want to save errors. Problems elsewhere in the data are left to `validate`.
+	fn confirm_valid(
+		console: &dyn Console,
+		set: &BTreeSet<Self>,
+		change: &Change<Self>
+	) -> io::Result<()> {
+		let Some(name) = change.after().map(Named::name) else {
+			return Ok(());
+		};
+		let findings = Self::validate_set(set)
+			.into_iter()
+			.filter(|finding| finding.rule.as_ref() == name)
+			.collect::<Vec<_>>();
+		for finding in &findings {
+			say!(console, "{}", finding);
+		}
+		if findings.iter().any(Finding::is_error)
+			&& !prompt_yes_no_question(
+				console,
+				format!(
+					"The {} data has errors. Do you want to save it anyway?",
+					Self::type_name_pretty()
+				)
+			) {
+			say!(console, "Canceled saving.");
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				"validation found errors"
+			));
+		}
+		Ok(())
+	}
+	/// Validates the data as it will be after the change, and asks before applying it on top of
+	/// changes made since `loaded_version`.
+	fn apply_to_disk_retrying(
+		console: &dyn Console,
+		path: &Path,
+		change: Change<Self>,
+		loaded_version: Option<u64>
+	) -> io::Result<bool> {
+		let storage = storage_for(path);
+		let _lock = storage.lock()?;
+		let is_outdated = match loaded_version {
+			Some(loaded_version) => storage.version()? != loaded_version,
+			None => false
+		};
+		let mut changed = storage.load()?;
+		if !change.apply_to(&mut changed) {
+			return Ok(false);
+		}
+		if is_outdated
+			&& !prompt_yes_no_question(
+				console,
+				format!(
+					"The {} data was changed by someone else since it was loaded. Do you want to \
+				 apply your change on top of theirs?",
+					Self::type_name_pretty()
+				)
+			) {
+			say!(console, "Canceled saving.");
+			return Err(io::Error::new(
+				io::ErrorKind::Interrupted,
+				"saving canceled"
+			));
+		}
+		Self::confirm_valid(console, &changed, &change)?;
+		let applied = 'attempt_loop: loop {
+			let apply_result = storage.apply(&change);
+			// trying again can't free an ID another entry has
+			if let Err(error) = &apply_result {
+				if error.kind() == io::ErrorKind::AlreadyExists {
+					say!(console, "Could not save, {}.", error);
+					break 'attempt_loop apply_result?;
+				}
+			}
+			if apply_result.is_ok()
+				|| !prompt_yes_no_question(
+					console,
+					format!(
+						"Attempt to save {} failed. Do you want to try again?",
+						Self::type_name_pretty()
+					)
+				) {
+				break 'attempt_loop apply_result?;
+			}
+		};
+		if applied {
+			record(path, &change, EventKind::Edit)?;
+		}
+		Ok(applied)
+	}
+}
+impl<T: Saved> InteractiveSaved for T {}
+pub(crate) fn prompt_yes_no_question(console: &dyn Console, question: impl AsRef<str>) -> bool {
+	say!(console, "{} (Y/N)", question.as_ref());
+	get_yes_no_reply(console)
+}
+/// No once there are no replies left.
+pub(crate) fn get_yes_no_reply(console: &dyn Console) -> bool {
+	loop {
+		let Some(reply) = try_get_reply(console) else {
+			break false;
+		};
+		let reply = reply.to_lowercase();
+		let unsure_answer = if reply.contains('y') {
+			Some(true)
+		} else if reply.contains('n') {
+			Some(false)
+		} else {
+			None
+		};
+		if let Some(valid_answer) = unsure_answer {
+			break valid_answer;
+		}
+		say!(console, "You need to answer with a yes [Y] or no [N].");
+	}
+}
+
+pub(crate) fn try_prompt_question(
+	console: &dyn Console,
+	question: impl AsRef<str>
+) -> Option<String> {
+	say!(console, "{}", question.as_ref());
+	try_get_reply(console)
+}
+pub(crate) fn try_get_reply(console: &dyn Console) -> Option<String> {
+	console.write("> ");
+	Some(console.read_line()?.trim().to_string())
+}
+
+/// The console of the terminal the program runs in.
+#[derive(Debug)]
+pub(crate) struct Terminal;
+impl Console for Terminal {
+	fn write_line(&self, line: &str) {
+		println!("{}", line);
+	}
+	fn write(&self, text: &str) {
+		print!("{}", text);
+		// flush enables us to write without a newline and have it display pre-input
+		let _ = stdout().flush(); // possibly breaks everything in certain terminal environments
+	}
+	fn read_line(&self) -> Option<String> {
+		let mut buffer = String::new();
+		match stdin().read_line(&mut buffer) {
+			Ok(0) | Err(_) => None,
+			Ok(_) => Some(buffer)
+		}
+	}
+}
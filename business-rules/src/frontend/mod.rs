@@ -0,0 +1,584 @@
+//! The interactive menus and the command line, on top of the `business_rules` library.
+
+use std::fmt::Display;
+
+use business_rules::{
+	console::Console,
+	dataset::{ApplicationData, PathFindable, Saved},
+	impact::TriggerImpact,
+	query::Query,
+	storage::Change,
+	types::{Purchase, Rule}
+};
+use lazy_static::lazy_static;
+
+#[allow(clippy::wildcard_imports)]
+use crate::frontend::{decisions::*, io::*, printing::*, searching::*, user_creation::*};
+use crate::{
+	try_modify_purchase_attributes, try_modify_purchase_identifiers, try_modify_purchase_time,
+	try_modify_purchase_title, try_modify_rule_process_action, try_modify_rule_processing_order,
+	try_modify_rule_scope, try_modify_rule_title, try_modify_rule_trigger,
+	try_modify_rule_validity
+};
+
+pub(crate) mod cli;
+pub(crate) mod decisions;
+pub(crate) mod io;
+pub(crate) mod printing;
+pub(crate) mod searching;
+pub(crate) mod server;
+#[cfg(test)]
+mod testing;
+pub(crate) mod tui;
+pub(crate) mod user_creation;
+
+pub type PathDataFn = fn(&ApplicationData);
+/// Asks how to change an entry, `None` when the user cancels.
+pub type ModifyFn<T> = fn(&dyn Console, T) -> Option<T>;
+
+pub(crate) trait DatabaseEntry:
+	NeatPrintable + Display + PathFindable + Saved + TryUserCreate + for<'a> Selectable<'a>
+{
+	fn print_decision(data: &ApplicationData);
+	fn entry_action_decision(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		let decision: Decision<PathDataFn> = Decision {
+			prompt: "What do you want to modify in purchase data?".into(),
+			possible_choices: vec![
+				(
+					("A", "Add a new purchase entry").into(),
+					Self::add_entry as PathDataFn
+				)
+					.into(),
+				(
+					("M", "Modify an existing purchase entry").into(),
+					Self::modify_entry as PathDataFn
+				)
+					.into(),
+				(
+					("D", "Delete an existing purchase entry").into(),
+					Self::delete_entry as PathDataFn
+				)
+					.into(),
+				(
+					("P", "Print information about the data").into(),
+					Purchase::print_decision as PathDataFn
+				)
+					.into(),
+			],
+			..Default::default()
+		};
+		if let Some(action) = decision.run_prompt(console) {
+			action(data);
+		} else {
+			say!(console, "Canceled entry action, returning...");
+		}
+	}
+	fn add_entry(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		if let Some(new) = Self::try_prompt_creation(console) {
+			say!(console);
+			match Self::apply_to_disk_retrying(
+				console,
+				Self::get_path(data),
+				Change::Insert(new),
+				None
+			) {
+				Ok(true) => say!(
+					console,
+					"Saved {} into its dataset.",
+					Self::type_name_pretty()
+				),
+				Ok(false) => say!(
+					console,
+					"This exact {} already exists in its dataset, skipping saving.",
+					Self::type_name_pretty()
+				),
+				Err(_) => {}
+			}
+		} else {
+			say!(console, "Failed to create entry.");
+		}
+	}
+	fn try_ask_modify_fn(console: &dyn Console) -> Option<ModifyFn<Self>>;
+	/// What modifying `old` into `new` changes beyond the entry itself, shown before saving.
+	fn describe_impact(_data: &ApplicationData, _old: &Self, _new: &Self) -> Option<String> { None }
+	fn modify_entry(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		// loading first, since loading gives IDs to entries saved without one
+		let Ok(all) = Self::load_from_disk_retrying(console, Self::get_path(data)) else {
+			return;
+		};
+		let loaded_version = Self::disk_version(Self::get_path(data)).ok();
+		say!(
+			console,
+			"In order to modify a {} we must first find it.",
+			Self::type_name_pretty().to_lowercase()
+		);
+		if let Some(found) = Self::try_find_single(console, all.clone().iter()) {
+			say!(
+				console,
+				"Modifying {}:\n{}",
+				Self::type_name_pretty().to_lowercase(),
+				found
+			);
+			let mut entry_modified = found.clone();
+			'modify_loop: loop {
+				if let Some(modifying_fn) = Self::try_ask_modify_fn(console) {
+					say!(console);
+					if let Some(modified_entry) = modifying_fn(console, entry_modified.clone()) {
+						entry_modified = modified_entry;
+						let impact = Self::describe_impact(data, found, &entry_modified)
+							.map(|impact| format!("\n\n{}", impact))
+							.unwrap_or_default();
+						if prompt_yes_no_question(
+							console,
+							format!(
+								"Are you satisfied with the changes made to the {}?\n{}{}",
+								Self::type_name_pretty().to_lowercase(),
+								entry_modified,
+								impact
+							)
+						) {
+							break 'modify_loop;
+						}
+					} else {
+						say!(
+							console,
+							"{} modification canceled.",
+							Self::type_name_pretty()
+						);
+						if *found == entry_modified {
+							say!(console, "No modifications were made, returning...");
+							return;
+						} else if !prompt_yes_no_question(
+							console,
+							"Do you want to make more modifications?"
+						) {
+							break 'modify_loop;
+						}
+					}
+				} else {
+					say!(console, "Canceled, returning...");
+					return;
+				}
+			}
+			if *found == entry_modified {
+				say!(console, "No modifications were made, returning...");
+				return;
+			}
+			let change = Change::Replace {
+				old: found.clone(),
+				new: entry_modified
+			};
+			match Self::apply_to_disk_retrying(
+				console,
+				Self::get_path(data),
+				change,
+				loaded_version
+			) {
+				Ok(true) => say!(console, "Saved modification to disk."),
+				Ok(false) => {
+					say!(console,
+					"{} was changed or removed by someone else in the meantime, nothing was saved.",
+					Self::type_name_pretty()
+				)
+				},
+				Err(_) => {}
+			}
+		} else {
+			say!(console, "Failed to find entry using this name.");
+		}
+	}
+	fn delete_entry(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		say!(
+			console,
+			"In order to delete a {} we must first find it.",
+			Self::type_name_pretty()
+		);
+		let Ok(all) = Self::load_from_disk_retrying(console, Self::get_path(data)) else {
+			return;
+		};
+		let loaded_version = Self::disk_version(Self::get_path(data)).ok();
+		if let Some(found) = Self::try_find_single(console, all.iter()) {
+			let confirmation_question =
+				format!("Are you sure you want to delete...\n{}\n...?", found);
+			if prompt_yes_no_question(console, confirmation_question) {
+				let change = Change::Remove(found.clone());
+				match Self::apply_to_disk_retrying(
+					console,
+					Self::get_path(data),
+					change,
+					loaded_version
+				) {
+					Ok(true) => say!(
+						console,
+						"{} was removed from dataset.",
+						Self::type_name_pretty()
+					),
+					Ok(false) => say!(
+						console,
+						"{} was already removed by someone else.",
+						Self::type_name_pretty()
+					),
+					Err(_) => {}
+				}
+			} else {
+				say!(console, "{} was kept in dataset.", Self::type_name_pretty());
+			}
+		} else {
+			say!(
+				console,
+				"No {} with the provided specifications could be found.",
+				Self::type_name_pretty()
+			);
+		}
+	}
+	fn print_data_individual(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		let Ok(all) = Self::load_from_disk_retrying(console, Self::get_path(data)) else {
+			return;
+		};
+		say!(console);
+		if let Some(item) = Self::try_find_single(console, all.iter()) {
+			item.print(console);
+		} else {
+			say!(
+				console,
+				"No {} with the provided specifications could be found.",
+				Self::type_name_pretty()
+			);
+		}
+	}
+	fn print_data_all(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		let Ok(all) = Self::load_from_disk_retrying(console, Self::get_path(data)) else {
+			return;
+		};
+		all.print(console);
+	}
+	fn print_data_search(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		let Ok(all) = Self::load_from_disk_retrying(console, Self::get_path(data)) else {
+			return;
+		};
+		let Some(reply) = try_prompt_question(
+			console,
+			"What are you searching for? For example: ski title:\"first aid\" ident:video \
+			 -ident:digital action:slip id:3f2a"
+		) else {
+			return;
+		};
+		let query = match reply.parse::<Query>() {
+			Ok(query) => query,
+			Err(error) => {
+				say!(console, "{}", error.pointed(&reply));
+				return;
+			}
+		};
+		let ranked = Self::search(&query, all.iter());
+		if ranked.is_empty() {
+			say!(
+				console,
+				"No {} matches the search.",
+				Self::type_name_pretty().to_lowercase()
+			);
+		}
+		for scored in ranked {
+			say!(console, "{}", scored);
+		}
+	}
+	/// Non-interactive counterpart to `add_entry`.
+	/// Returns `false` if the exact entry was already in the dataset.
+	fn insert_entry(data: &ApplicationData, new: Self) -> std::io::Result<bool> {
+		Self::apply_to_disk(Self::get_path(data), &Change::Insert(new))
+	}
+	/// Non-interactive counterpart to `modify_entry`.
+	/// Returns `false` if `old` was not in the dataset.
+	fn replace_entry(data: &ApplicationData, old: &Self, new: Self) -> std::io::Result<bool> {
+		let change = Change::Replace {
+			old: old.clone(),
+			new
+		};
+		Self::apply_to_disk(Self::get_path(data), &change)
+	}
+	/// Non-interactive counterpart to `delete_entry`.
+	/// Returns `false` if `old` was not in the dataset.
+	fn remove_entry(data: &ApplicationData, old: &Self) -> std::io::Result<bool> {
+		Self::apply_to_disk(Self::get_path(data), &Change::Remove(old.clone()))
+	}
+}
+
+impl DatabaseEntry for Purchase {
+	fn print_decision(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		lazy_static! {
+			static ref DECISION: Decision<PathDataFn> = Decision {
+				prompt: "What purchase data do you want to print out?".into(),
+				possible_choices: vec![
+					(
+						("A", "All of them").into(),
+						Purchase::print_data_all as PathDataFn
+					)
+						.into(),
+					(
+						("O", "An order of them").into(),
+						print_purchase_data_order as PathDataFn
+					)
+						.into(),
+					(
+						("I", "Individual purchase").into(),
+						Purchase::print_data_individual as PathDataFn
+					)
+						.into(),
+					(
+						("S", "Search with a query").into(),
+						Purchase::print_data_search as PathDataFn
+					)
+						.into(),
+				],
+				..Default::default()
+			};
+		}
+		if let Some(action) = DECISION.run_prompt(console) {
+			action(data);
+		}
+	}
+
+	fn try_ask_modify_fn(console: &dyn Console) -> Option<ModifyFn<Self>> {
+		type FnType = ModifyFn<Purchase>;
+		lazy_static! {
+			static ref DECISION: Decision<FnType> = Decision {
+				prompt: "What do you want to change about this purchase?".into(),
+				possible_choices: vec![
+					(
+						("T", "Modify title").into(),
+						try_modify_purchase_title as FnType
+					)
+						.into(),
+					(
+						("I", "Modify identifiers").into(),
+						try_modify_purchase_identifiers as FnType
+					)
+						.into(),
+					(
+						("A", "Modify attributes").into(),
+						try_modify_purchase_attributes as FnType
+					)
+						.into(),
+					(
+						("D", "Modify time of purchase").into(),
+						try_modify_purchase_time as FnType
+					)
+						.into(),
+				],
+				..Default::default()
+			};
+		}
+		DECISION.run_prompt(console)
+	}
+}
+impl DatabaseEntry for Rule {
+	fn print_decision(data: &ApplicationData) {
+		let console = data.console.as_ref();
+		lazy_static! {
+			static ref DECISION: Decision<PathDataFn> = Decision {
+				prompt: "What rule data do you want to print out?".into(),
+				possible_choices: vec![
+					(
+						("A", "All of them").into(),
+						Rule::print_data_all as PathDataFn
+					)
+						.into(),
+					(
+						("I", "Individual rule").into(),
+						Rule::print_data_individual as PathDataFn
+					)
+						.into(),
+					(
+						("S", "Search with a query").into(),
+						Rule::print_data_search as PathDataFn
+					)
+						.into(),
+				],
+				..Default::default()
+			};
+		}
+		if let Some(action) = DECISION.run_prompt(console) {
+			action(data);
+		}
+	}
+
+	/// Lists the purchases that newly trigger or stop triggering the rule.
+	fn describe_impact(data: &ApplicationData, old: &Self, new: &Self) -> Option<String> {
+		if old.trigger == new.trigger {
+			return None;
+		}
+		let purchases =
+			Purchase::load_from_disk_retrying(data.console.as_ref(), Purchase::get_path(data))
+				.ok()?;
+		Some(format!(
+			"Changing the trigger affects these purchases:\n{}",
+			TriggerImpact::between(&old.trigger, &new.trigger, &purchases)
+		))
+	}
+
+	fn try_ask_modify_fn(console: &dyn Console) -> Option<ModifyFn<Self>> {
+		type FnType = ModifyFn<Rule>;
+		lazy_static! {
+			static ref DECISION: Decision<FnType> = Decision {
+				prompt: "What do you want to change about this rule?".into(),
+				possible_choices: vec![
+					(
+						("T", "Modify title").into(),
+						try_modify_rule_title as FnType
+					)
+						.into(),
+					(
+						("P", "Modify process action").into(),
+						try_modify_rule_process_action as FnType
+					)
+						.into(),
+					(
+						("R", "Modify rule trigger").into(),
+						try_modify_rule_trigger as FnType
+					)
+						.into(),
+					(
+						("S", "Modify rule scope").into(),
+						try_modify_rule_scope as FnType
+					)
+						.into(),
+					(
+						("O", "Modify priority, stop and exclusion group").into(),
+						try_modify_rule_processing_order as FnType
+					)
+						.into(),
+					(
+						("V", "Modify validity window").into(),
+						try_modify_rule_validity as FnType
+					)
+						.into(),
+				],
+				..Default::default()
+			};
+		}
+		DECISION.run_prompt(console)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, sync::Arc};
+
+	use business_rules::console::ScriptedConsole;
+
+	use super::{testing::TempDir, *};
+
+	/// Data in a fresh directory holding a single purchase, answered by the given replies. The data
+	/// lives as long as the directory.
+	fn scripted_data(
+		name: &str,
+		replies: &[&str]
+	) -> (TempDir, ApplicationData, Arc<ScriptedConsole>) {
+		let directory = TempDir::new(&format!("session-{}", name));
+		let console = Arc::new(ScriptedConsole::new(replies.iter().copied()));
+		let data = ApplicationData::new(
+			directory.join("all_purchases.json"),
+			directory.join("all_rules.json")
+		)
+		.with_console(console.clone());
+		fs::write(
+			&data.purchase_path,
+			r#"[{"title": "Corsair K95", "identifiers": ["keyboard"]}]"#
+		)
+		.unwrap();
+		fs::write(&data.rule_path, "[]").unwrap();
+		(directory, data, console)
+	}
+
+	#[test]
+	fn creates_a_rule() {
+		let (_directory, data, console) = scripted_data(
+			"create",
+			&[
+				"Keyboard slips",
+				"packing_slip(keyboards)",
+				"E",
+				"any(keyboard)",
+				"n",
+				"n",
+				"n"
+			]
+		);
+		Rule::add_entry(&data);
+		assert_eq!(console.remaining(), 0);
+		assert!(console
+			.transcript()
+			.contains("Saved Rule into its dataset."));
+		let rules = Rule::load_from_disk(&data.rule_path).unwrap();
+		let rule = rules.first().unwrap();
+		assert_eq!(rule.title.as_ref(), "Keyboard slips");
+		assert_eq!(rule.trigger, "any(keyboard)".parse().unwrap());
+		assert_eq!(rule.process_action, "packing_slip(keyboards)".into());
+	}
+
+	#[test]
+	fn ignores_errors_in_other_rules() {
+		let (_directory, data, console) = scripted_data(
+			"other-errors",
+			&[
+				"Keyboard slips",
+				"packing_slip(keyboards)",
+				"E",
+				"any(keyboard)",
+				"n",
+				"n",
+				"n"
+			]
+		);
+		fs::write(
+			&data.rule_path,
+			r#"[{"title": "Broken", "process_action": "packing_slip", "trigger": "never"}]"#
+		)
+		.unwrap();
+		Rule::add_entry(&data);
+		assert_eq!(console.remaining(), 0);
+		assert!(!console.transcript().contains("Broken"));
+		assert_eq!(Rule::load_from_disk(&data.rule_path).unwrap().len(), 2);
+	}
+
+	#[test]
+	fn modifies_and_deletes_a_purchase() {
+		let (_directory, data, console) = scripted_data(
+			"modify",
+			&["corsair", "T", "Corsair K100", "y", "Corsair K100", "y"]
+		);
+		Purchase::modify_entry(&data);
+		let purchases = Purchase::load_from_disk(&data.purchase_path).unwrap();
+		assert_eq!(purchases.first().unwrap().title.as_ref(), "Corsair K100");
+		Purchase::delete_entry(&data);
+		assert!(Purchase::load_from_disk(&data.purchase_path)
+			.unwrap()
+			.is_empty());
+		assert_eq!(console.remaining(), 0);
+		let transcript = console.transcript();
+		assert!(transcript.contains("Single exact match found."));
+		assert!(transcript.contains("Saved modification to disk."));
+		assert!(transcript.contains("Purchase was removed from dataset."));
+	}
+
+	#[test]
+	fn stops_when_replies_run_out() {
+		let (_directory, data, _) = scripted_data("eof", &["A", "Keyboard slips"]);
+		Rule::entry_action_decision(&data);
+		assert!(Rule::load_from_disk(&data.rule_path).unwrap().is_empty());
+
+		// the confirmation is answered with no
+		let (_directory, data, _) = scripted_data("eof-confirm", &["corsair", "T", "Corsair K100"]);
+		Purchase::modify_entry(&data);
+		let purchases = Purchase::load_from_disk(&data.purchase_path).unwrap();
+		assert_eq!(purchases.first().unwrap().title.as_ref(), "Corsair K95");
+	}
+}
@@ -0,0 +1,265 @@
+use std::{collections::BTreeSet, fmt::Display, fs, path::Path};
+
+use business_rules::{
+	actions::ProcessAction,
+	console::Console,
+	dataset::{ApplicationData, PathFindable},
+	orders::{append_order, OrderBatch, OrderFileFormat},
+	storage::Stored,
+	types::{Order, ProcessingPlan, Purchase, Rule}
+};
+
+use super::{
+	io::{prompt_yes_no_question, say, try_prompt_question, InteractiveSaved},
+	searching::Selectable,
+	user_creation::UserSelected
+};
+
+pub trait NeatPrintable {
+	fn print(&self, console: &dyn Console);
+}
+impl NeatPrintable for Purchase {
+	fn print(&self, console: &dyn Console) {
+		say!(console, "Purchase:\n{}", self);
+	}
+}
+impl NeatPrintable for Rule {
+	fn print(&self, console: &dyn Console) {
+		say!(console, "Rule:\n{}", self);
+	}
+}
+
+impl NeatPrintable for Order {
+	fn print(&self, console: &dyn Console) {
+		if self.purchases.0.is_empty() {
+			say!(console, "No purchases in order to print.");
+		} else {
+			for (index, (purchase, amount)) in self.purchases.0.iter().enumerate() {
+				say!(
+					console,
+					"Purchase no. {} (x{}):\n{}",
+					index + 1,
+					amount,
+					purchase
+				);
+				say!(console);
+			}
+		}
+	}
+}
+pub(crate) fn print_purchase_data_order(data: &ApplicationData) {
+	let console = data.console.as_ref();
+	Order::prompt_data_selection(data).print(console);
+}
+impl<T: Display + Stored> NeatPrintable for BTreeSet<T> {
+	fn print(&self, console: &dyn Console) {
+		for (index, item) in self.iter().enumerate() {
+			say!(
+				console,
+				"{} no. {}:\n{}",
+				T::type_name_pretty(),
+				index + 1,
+				item
+			);
+			say!(console);
+		}
+	}
+}
+
+pub(crate) fn print_processing_individual(data: &ApplicationData) {
+	let console = data.console.as_ref();
+	let Ok(rules) = Rule::load_from_disk_retrying(console, Rule::get_path(data)) else {
+		return;
+	};
+	if rules.is_empty() {
+		say!(
+			console,
+			"There are currently no rules to trigger any processes."
+		);
+	} else {
+		let Ok(purchases) = Purchase::load_from_disk_retrying(console, Purchase::get_path(data))
+		else {
+			return;
+		};
+		let possible_purchase = Purchase::try_find_single(console, purchases.iter());
+		say!(console);
+		if let Some(purchase) = possible_purchase {
+			print_processing_steps(console, purchase, &rules);
+			say!(console);
+			if prompt_yes_no_question(
+				console,
+				"Do you want to see why each rule did or didn't fire?"
+			) {
+				for explanation in
+					purchase.explain_processing(&rules, purchase.evaluation_time(None))
+				{
+					say!(console, "\n{}", explanation);
+				}
+			}
+		} else {
+			say!(
+				console,
+				"No item with the provided specifications could be found."
+			);
+		}
+	}
+}
+pub(crate) fn print_processing_order(data: &ApplicationData) {
+	let console = data.console.as_ref();
+	let order = Order::prompt_data_selection(data);
+	say!(console); // post-user-entry spacing
+	if order.purchases.0.is_empty() {
+		say!(console, "No purchases in order to print.");
+	} else {
+		let Ok(rules) = Rule::load_from_disk_retrying(console, Rule::get_path(data)) else {
+			return;
+		};
+
+		if rules.is_empty() {
+			say!(
+				console,
+				"There are currently no rules to trigger any processes."
+			);
+		} else {
+			order
+				.get_processing_plan(&rules, order.evaluation_time(None))
+				.print(console);
+		}
+		say!(console);
+		if prompt_yes_no_question(console, "Do you want to add this order to an order file?") {
+			save_order(console, &order);
+		}
+	}
+}
+fn save_order(console: &dyn Console, order: &Order) {
+	let Some(path) = try_prompt_question(
+		console,
+		"Which order file should it be added to? Files ending in .csv are CSV, other files get a \
+		 JSON object per line."
+	) else {
+		return;
+	};
+	let Some(reference) = try_prompt_question(console, "What is the reference of this order?")
+	else {
+		return;
+	};
+	match append_order(Path::new(path.trim()), reference.trim(), order) {
+		Ok(()) => say!(console, "Order added to {}.", path.trim()),
+		Err(error) => say!(console, "Failed to add the order: {}", error)
+	}
+}
+pub(crate) fn print_processing_order_file(data: &ApplicationData) {
+	let console = data.console.as_ref();
+	let Some(path) = try_prompt_question(console, "Which order file do you want to process?")
+	else {
+		return;
+	};
+	let path = Path::new(path.trim());
+	let contents = match fs::read_to_string(path) {
+		Ok(contents) => contents,
+		Err(error) => {
+			say!(console, "Failed to read {}: {}", path.display(), error);
+			return;
+		}
+	};
+	let Ok(purchases) = Purchase::load_from_disk_retrying(console, Purchase::get_path(data)) else {
+		return;
+	};
+	let Ok(rules) = Rule::load_from_disk_retrying(console, Rule::get_path(data)) else {
+		return;
+	};
+	let batch = match OrderBatch::read(&contents, OrderFileFormat::of_path(path), &purchases) {
+		Ok(batch) => batch,
+		Err(error) => {
+			say!(console, "Failed to read {}: {}", path.display(), error);
+			return;
+		}
+	};
+	for error in &batch.errors {
+		say!(console, "Skipped {}", error);
+	}
+	for order in &batch.orders {
+		say!(console, "\n--- Order '{}' ---", order.reference);
+		if order.errors.is_empty() {
+			let at = order.order.evaluation_time(None);
+			order.order.get_processing_plan(&rules, at).print(console);
+		} else {
+			for error in &order.errors {
+				say!(console, "Skipped the order, {}", error);
+			}
+		}
+	}
+}
+impl NeatPrintable for ProcessingPlan {
+	fn print(&self, console: &dyn Console) {
+		for (index, line) in self.lines.iter().enumerate() {
+			say!(
+				console,
+				"Purchase no. {} (x{}):\n{}",
+				index + 1,
+				line.quantity,
+				line.purchase
+			);
+			print_steps(console, &line.steps, "purchase");
+			say!(console); // extra spacing between each
+		}
+		say!(console, "Whole order:");
+		print_steps(console, &self.order_steps, "order");
+	}
+}
+
+pub(crate) fn print_processing_all(data: &ApplicationData) {
+	let console = data.console.as_ref();
+	let Ok(all_purchases) = Purchase::load_from_disk_retrying(console, Purchase::get_path(data))
+	else {
+		return;
+	};
+	let Ok(rules) = Rule::load_from_disk_retrying(console, Rule::get_path(data)) else {
+		return;
+	};
+	if rules.is_empty() {
+		say!(
+			console,
+			"There are currently no rules to trigger any processes."
+		);
+	} else {
+		let rules = &rules;
+		for (index, purchase) in all_purchases.iter().enumerate() {
+			say!(console, "Purchase no. {}:\n{}", index + 1, purchase);
+			print_processing_steps(console, purchase, rules);
+			say!(console);
+		}
+	}
+}
+
+pub(crate) fn print_processing_steps(
+	console: &dyn Console,
+	purchase: &Purchase,
+	rules: &BTreeSet<Rule>
+) {
+	print_steps(
+		console,
+		&purchase.get_processing_steps(rules, purchase.evaluation_time(None)),
+		"purchase"
+	);
+}
+fn print_steps(console: &dyn Console, processing_steps: &[ProcessAction], processed: &str) {
+	if processing_steps.is_empty() {
+		say!(
+			console,
+			"This {} does trigger any processing rules.",
+			processed
+		);
+	} else {
+		say!(
+			console,
+			"The processing steps for this {} are the following:\n - {}",
+			processed,
+			processing_steps
+				.iter()
+				.map(ProcessAction::description)
+				.collect::<Vec<_>>()
+				.join("\n - ")
+		);
+	}
+}
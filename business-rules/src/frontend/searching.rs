@@ -0,0 +1,228 @@
+use std::{
+	collections::BTreeSet,
+	fmt::{Debug, Display}
+};
+
+use business_rules::{
+	console::Console,
+	query::Query,
+	searching::{NameSearchable, Scored, SearchMatches, Searchable},
+	storage::Stored,
+	types::{Identifier, Purchase, Rule}
+};
+
+use super::{
+	io::{say, try_get_reply, try_prompt_question},
+	printing::NeatPrintable
+};
+
+fn print_evaluation<T: Display, I: Debug>(console: &dyn Console, matches: &SearchMatches<T, I>) {
+	match matches {
+		SearchMatches::MultipleExact(_) => say!(console, "Multiple exact matches found."),
+		SearchMatches::SingleExact(_) => say!(console, "Single exact match found."),
+		SearchMatches::None => say!(console, "No matches found."),
+		SearchMatches::SinglePartial(_) => say!(console, "Single partial match found."),
+		SearchMatches::MultiplePartial(_) => say!(console, "Multiple partial matches found.")
+	};
+	match matches {
+		SearchMatches::MultipleExact(values) | SearchMatches::MultiplePartial(values) => {
+			say!(console, "[{:?}]", values);
+		},
+		SearchMatches::SingleExact(value) | SearchMatches::SinglePartial(value) => {
+			say!(console, "{}", value);
+		},
+		SearchMatches::None => {}
+	};
+}
+
+/// Lets the user pick a single entry by its title or ID.
+pub(crate) trait Selectable<'a>
+where
+	Self: Searchable<'a> + Stored + NeatPrintable
+{
+	fn try_find_single<I>(console: &dyn Console, data: I) -> Option<&'a Self>
+	where
+		I: Iterator<Item = &'a Self>
+	{
+		let title = try_prompt_question(
+			console,
+			format!(
+				"What is the title or ID of the {}?",
+				Self::type_name_pretty().to_lowercase()
+			)
+		)?;
+		let data = data.collect::<Vec<_>>();
+		if let [single] = Self::find_all_by_id(title.as_str(), data.iter().copied())[..] {
+			say!(console, "Match by ID found.\n{}", single);
+			return Some(single);
+		}
+		let title_search = Self::filter_by_name(title.as_str(), data.iter().copied());
+		print_evaluation(console, &title_search);
+		match title_search {
+			SearchMatches::MultipleExact(multiple) | SearchMatches::MultiplePartial(multiple) => {
+				Self::find_specific(console, multiple, title.as_str())
+			},
+			SearchMatches::SingleExact(single) | SearchMatches::SinglePartial(single) => {
+				Some(single)
+			},
+			SearchMatches::None => {
+				// typos and queries like `ident:video` are left to the fuzzy search
+				let query = title.parse::<Query>().ok()?;
+				Self::try_choose_ranked(console, Self::search(&query, data.into_iter()))
+			}
+		}
+	}
+	/// Lets the user pick one of the search results, best matches first.
+	fn try_choose_ranked(
+		console: &dyn Console,
+		mut ranked: Vec<Scored<'a, Self>>
+	) -> Option<&'a Self> {
+		const SHOWN: usize = 10;
+		match ranked.len() {
+			0 => None,
+			1 => {
+				say!(console, "Single fuzzy match found.\n{}", ranked[0].entry);
+				Some(ranked.remove(0).entry)
+			},
+			found => {
+				say!(console, "Closest matches, with how well they match:");
+				for (index, scored) in ranked.iter().take(SHOWN).enumerate() {
+					say!(console, "{}. {}", index, scored);
+				}
+				if found > SHOWN {
+					say!(console, "...and {} more.", found - SHOWN);
+				}
+				say!(
+					console,
+					"Choose one using its index (number to the left), or nothing to cancel."
+				);
+				loop {
+					let reply = try_get_reply(console)?;
+					if reply.trim().is_empty() {
+						return None;
+					}
+					match reply.trim().parse::<usize>() {
+						Ok(index) if index < found.min(SHOWN) => {
+							break Some(ranked.swap_remove(index).entry);
+						},
+						_ => say!(console, "Reply was not one of the indices, try again.")
+					}
+				}
+			}
+		}
+	}
+	fn find_specific(
+		console: &dyn Console,
+		data: Vec<&'a Self>,
+		searched_title: &str
+	) -> Option<&'a Self>; // more specific than title
+}
+
+impl<'a> Selectable<'a> for Purchase {
+	fn find_specific(
+		console: &dyn Console,
+		mut data: Vec<&'a Self>,
+		searched_title: &str
+	) -> Option<&'a Self> {
+		let identifiers = data
+			.iter()
+			.flat_map(|item| &item.identifiers.0)
+			.collect::<BTreeSet<&Identifier>>(); // unique set of all identifiers
+		say!(
+			console,
+			"All identifiers for the search '{}': [{:?}]",
+			searched_title,
+			identifiers.iter().collect::<Vec<_>>()
+		);
+
+		'identifier_narrow: loop {
+			let identifier_to_narrow_with = 'identifier_filter_select: loop {
+				say!(
+					console,
+					"Provide one or more (';' separated) identifiers to narrow your search."
+				);
+				let answer = try_get_reply(console)?;
+				let identifier_search =
+					Identifier::filter_by_name(&answer, identifiers.clone().into_iter());
+				break 'identifier_filter_select match identifier_search {
+					SearchMatches::MultipleExact(_multiple) => {
+						unreachable!("It is impossible for two identifiers to have the same name.");
+					},
+					SearchMatches::SingleExact(single) | SearchMatches::SinglePartial(single) => {
+						say!(console, "Found matching identifier '{}'.", single);
+						single
+					},
+					SearchMatches::None => {
+						say!(console, "Identifier '{}' was not found, try again.", answer);
+						continue 'identifier_filter_select;
+					},
+					SearchMatches::MultiplePartial(multiple) => {
+						say!(
+							console,
+							"Multiple identifiers found from partial '{}': {:?}, try again.",
+							answer,
+							multiple
+						);
+						continue 'identifier_filter_select;
+					}
+				};
+			};
+			data.retain(|purchase| purchase.has_identifier(identifier_to_narrow_with));
+			match data.len() {
+				0 => unreachable!(
+					"Impossible to have no data remaining since only one is removed at a time."
+				),
+				1 => break 'identifier_narrow Some(data.remove(0)),
+				_ => continue 'identifier_narrow
+			}
+		}
+	}
+}
+impl<'a> Selectable<'a> for Rule {
+	fn find_specific(
+		console: &dyn Console,
+		mut found_matches: Vec<&'a Self>,
+		_searched_title: &str
+	) -> Option<&'a Self> {
+		if found_matches.is_empty() {
+			None
+		} else if found_matches.len() == 1 {
+			Some(found_matches.remove(0))
+		} else {
+			say!(
+				console,
+				"Rules with the same name were found in the dataset."
+			);
+			for (index, rule) in found_matches.iter().enumerate() {
+				say!(
+					console,
+					"Rule {} process_action: {}",
+					index,
+					rule.process_action
+				);
+			}
+			say!(
+				console,
+				"Choose a rule using its index (number to the left)."
+			);
+			let selected_match = 'index_request_loop: loop {
+				let reply = try_get_reply(console)?;
+				let Ok(unsigned_int_reply) = reply.trim().parse::<usize>() else {
+					say!(console, "Reply was not an unsigned integer.");
+					say!(console, "Try again.");
+					continue 'index_request_loop;
+				};
+				if unsigned_int_reply >= found_matches.len() {
+					say!(
+						console,
+						"Reply index was not inside the range of the found matches."
+					);
+					say!(console, "Try again.");
+					continue 'index_request_loop;
+				}
+				break 'index_request_loop found_matches.remove(unsigned_int_reply);
+			};
+			Some(selected_match)
+		}
+	}
+}
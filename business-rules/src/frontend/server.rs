@@ -0,0 +1,446 @@
+//! A local HTTP server answering in JSON, so other services can manage purchases and rules and
+//! ask which steps a purchase or order gets.
+//!
+//! Endpoints:
+//! - `GET /purchases`, `POST /purchases`, `GET /purchases/<ID>`, `PUT /purchases/<ID>` and
+//!   `DELETE /purchases/<ID>`, the same under `/rules`. Entries are written like in the data
+//!   files, IDs can be shortened like on the command line.
+//! - `POST /process` with `{"purchase": <PURCHASE>}` or `{"order": [{"purchase": <PURCHASE>,
+//!   "quantity": 2}, ..]}`, where a purchase is a stored title or ID or a whole purchase, and an
+//!   optional `"as_of"` time.
+//!
+//! Saving a rule that validating finds errors in fails unless `?force` is added.
+
+use std::{collections::BTreeSet, io, net::SocketAddr};
+
+use business_rules::{
+	dataset::{ApplicationData, PathFindable, Saved},
+	orders::find_purchase,
+	storage::Change,
+	types::{parse_time, EntryId, Order, Purchase, PurchaseCollection, Rule},
+	validation::Finding
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Response};
+
+use super::{cli::plan_to_json, DatabaseEntry};
+
+/// Status and JSON body of a response, without a body for `204 No Content`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Reply {
+	pub(crate) status: u16,
+	pub(crate) body:   Option<Value>
+}
+impl Reply {
+	fn json(status: u16, body: impl Serialize) -> Self {
+		match serde_json::to_value(body) {
+			Ok(body) => Reply {
+				status,
+				body: Some(body)
+			},
+			Err(error) => Reply::error(500, error.to_string())
+		}
+	}
+	fn error(status: u16, message: impl Into<String>) -> Self {
+		Reply {
+			status,
+			body: Some(json!({ "error": message.into() }))
+		}
+	}
+	fn no_content() -> Self {
+		Reply {
+			status: 204,
+			body:   None
+		}
+	}
+}
+
+pub(crate) struct Server(tiny_http::Server);
+impl Server {
+	/// Binds to an address like `127.0.0.1:8080`, port 0 picks any free port.
+	pub(crate) fn bind(address: &str) -> std::io::Result<Self> {
+		tiny_http::Server::http(address)
+			.map(Server)
+			.map_err(std::io::Error::other)
+	}
+	pub(crate) fn address(&self) -> Option<SocketAddr> { self.0.server_addr().to_ip() }
+
+	/// Answers requests one at a time until the process is stopped.
+	pub(crate) fn run(&self, data: &ApplicationData) {
+		for mut request in self.0.incoming_requests() {
+			let mut body = String::new();
+			let reply = match request.as_reader().read_to_string(&mut body) {
+				Ok(_) => handle(data, request.method().as_str(), request.url(), &body),
+				Err(error) => Reply::error(400, format!("the body could not be read: {}", error))
+			};
+			println!("{} {} {}", request.method(), request.url(), reply.status);
+			let result = match reply.body {
+				Some(body) => {
+					let content_type = Header::from_bytes("Content-Type", "application/json")
+						.expect("the header is valid");
+					request.respond(
+						Response::from_string(body.to_string())
+							.with_status_code(reply.status)
+							.with_header(content_type)
+					)
+				},
+				None => request.respond(Response::empty(reply.status))
+			};
+			if let Err(error) = result {
+				eprintln!("Failed to respond: {}", error);
+			}
+		}
+	}
+}
+
+/// Routes a request, `url` being the path with an optional query.
+pub(crate) fn handle(data: &ApplicationData, method: &str, url: &str, body: &str) -> Reply {
+	let (path, query) = url.split_once('?').unwrap_or((url, ""));
+	let force = query.split('&').any(|parameter| parameter == "force");
+	let segments = path
+		.split('/')
+		.filter(|segment| !segment.is_empty())
+		.collect::<Vec<_>>();
+	match (method, segments.as_slice()) {
+		("GET", ["purchases"]) => list::<Purchase>(data),
+		("POST", ["purchases"]) => add::<Purchase>(data, body, force),
+		("GET", ["purchases", id]) => show::<Purchase>(data, id),
+		("PUT", ["purchases", id]) => replace::<Purchase>(data, id, body, force),
+		("DELETE", ["purchases", id]) => remove::<Purchase>(data, id),
+		("GET", ["rules"]) => list::<Rule>(data),
+		("POST", ["rules"]) => add::<Rule>(data, body, force),
+		("GET", ["rules", id]) => show::<Rule>(data, id),
+		("PUT", ["rules", id]) => replace::<Rule>(data, id, body, force),
+		("DELETE", ["rules", id]) => remove::<Rule>(data, id),
+		("POST", ["process"]) => process(data, body),
+		(_, ["purchases" | "rules"] | ["purchases" | "rules", _] | ["process"]) => Reply::error(
+			405,
+			format!("{} is not allowed on /{}", method, path.trim_matches('/'))
+		),
+		_ => Reply::error(404, format!("there is nothing at {}", path))
+	}
+}
+
+fn list<T: DatabaseEntry>(data: &ApplicationData) -> Reply {
+	match T::load_from_disk(T::get_path(data)) {
+		Ok(all) => Reply::json(200, all),
+		Err(error) => Reply::error(500, error.to_string())
+	}
+}
+
+/// The single entry with the (shortened) ID.
+fn find<'a, T: DatabaseEntry>(id: &str, all: &'a BTreeSet<T>) -> Result<&'a T, Reply> {
+	match T::find_all_by_id(id, all.iter())[..] {
+		[single] => Ok(single),
+		[] => Err(Reply::error(
+			404,
+			format!(
+				"no {} has the ID '{}'",
+				T::type_name_pretty().to_lowercase(),
+				id
+			)
+		)),
+		ref several => Err(Reply::error(
+			409,
+			format!(
+				"{} entries have an ID starting with '{}'",
+				several.len(),
+				id
+			)
+		))
+	}
+}
+
+fn show<T: DatabaseEntry>(data: &ApplicationData, id: &str) -> Reply {
+	let all = match T::load_from_disk(T::get_path(data)) {
+		Ok(all) => all,
+		Err(error) => return Reply::error(500, error.to_string())
+	};
+	match find(id, &all) {
+		Ok(found) => Reply::json(200, found),
+		Err(reply) => reply
+	}
+}
+
+fn parse_entry<T: DeserializeOwned>(body: &str) -> Result<T, Reply> {
+	serde_json::from_str(body).map_err(|error| Reply::error(400, error.to_string()))
+}
+
+/// Errors validation finds in `new` once the change is made, refusing them unless forced.
+fn check<T: DatabaseEntry>(all: &BTreeSet<T>, change: &Change<T>, force: bool) -> Option<Reply> {
+	let mut after = all.clone();
+	change.apply_to(&mut after);
+	let name = change.after()?.name().to_string();
+	let findings = T::validate_set(&after)
+		.into_iter()
+		.filter(|finding| finding.rule.as_ref() == name)
+		.collect::<Vec<_>>();
+	(!force && findings.iter().any(Finding::is_error)).then(|| {
+		let error = "validation found errors, add '?force' to save anyway";
+		Reply::json(422, json!({ "error": error, "findings": findings }))
+	})
+}
+
+fn add<T: DatabaseEntry>(data: &ApplicationData, body: &str, force: bool) -> Reply {
+	let mut new: T = match parse_entry(body) {
+		Ok(new) => new,
+		Err(reply) => return reply
+	};
+	new.set_id(EntryId::generate());
+	let all = match T::load_from_disk(T::get_path(data)) {
+		Ok(all) => all,
+		Err(error) => return Reply::error(500, error.to_string())
+	};
+	if let Some(reply) = check(&all, &Change::Insert(new.clone()), force) {
+		return reply;
+	}
+	match T::insert_entry(data, new.clone()) {
+		Ok(true) => Reply::json(201, new),
+		Ok(false) => Reply::error(
+			409,
+			format!(
+				"this exact {} already exists",
+				T::type_name_pretty().to_lowercase()
+			)
+		),
+		Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+			Reply::error(409, error.to_string())
+		},
+		Err(error) => Reply::error(500, error.to_string())
+	}
+}
+
+/// The new entry keeps the ID of the entry it replaces.
+fn replace<T: DatabaseEntry>(data: &ApplicationData, id: &str, body: &str, force: bool) -> Reply {
+	let mut new: T = match parse_entry(body) {
+		Ok(new) => new,
+		Err(reply) => return reply
+	};
+	let all = match T::load_from_disk(T::get_path(data)) {
+		Ok(all) => all,
+		Err(error) => return Reply::error(500, error.to_string())
+	};
+	let old = match find(id, &all) {
+		Ok(old) => old,
+		Err(reply) => return reply
+	};
+	new.set_id(old.id());
+	let change = Change::Replace {
+		old: old.clone(),
+		new: new.clone()
+	};
+	if let Some(reply) = check(&all, &change, force) {
+		return reply;
+	}
+	match T::replace_entry(data, old, new.clone()) {
+		Ok(true) => Reply::json(200, new),
+		Ok(false) => Reply::error(409, "the entry was changed while replacing it"),
+		Err(error) => Reply::error(500, error.to_string())
+	}
+}
+
+fn remove<T: DatabaseEntry>(data: &ApplicationData, id: &str) -> Reply {
+	let all = match T::load_from_disk(T::get_path(data)) {
+		Ok(all) => all,
+		Err(error) => return Reply::error(500, error.to_string())
+	};
+	let old = match find(id, &all) {
+		Ok(old) => old,
+		Err(reply) => return reply
+	};
+	match T::remove_entry(data, old) {
+		Ok(true) => Reply::no_content(),
+		Ok(false) => Reply::error(409, "the entry was changed while removing it"),
+		Err(error) => Reply::error(500, error.to_string())
+	}
+}
+
+/// A stored purchase by title or ID, or a purchase that isn't stored.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PurchaseReference {
+	Stored(String),
+	Given(Purchase)
+}
+impl PurchaseReference {
+	fn resolve(self, purchases: &BTreeSet<Purchase>) -> Result<Purchase, Reply> {
+		match self {
+			PurchaseReference::Stored(title_or_id) => find_purchase(&title_or_id, purchases)
+				.cloned()
+				.map_err(|message| Reply::error(404, message)),
+			PurchaseReference::Given(purchase) => Ok(purchase)
+		}
+	}
+}
+#[derive(Debug, Deserialize)]
+struct ProcessLine {
+	purchase: PurchaseReference,
+	#[serde(default)]
+	quantity: Option<usize>
+}
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProcessRequest {
+	#[serde(default)]
+	purchase: Option<PurchaseReference>,
+	#[serde(default)]
+	order:    Option<Vec<ProcessLine>>,
+	#[serde(default)]
+	as_of:    Option<String>
+}
+
+/// Steps for a purchase on its own, like 'process --purchase', or the plan of an order.
+fn process(data: &ApplicationData, body: &str) -> Reply {
+	let request: ProcessRequest = match parse_entry(body) {
+		Ok(request) => request,
+		Err(reply) => return reply
+	};
+	let as_of = match request.as_of.as_deref().map(parse_time).transpose() {
+		Ok(as_of) => as_of,
+		Err(message) => return Reply::error(400, message)
+	};
+	let (purchases, rules) = match (
+		Purchase::load_from_disk(Purchase::get_path(data)),
+		Rule::load_from_disk(Rule::get_path(data))
+	) {
+		(Ok(purchases), Ok(rules)) => (purchases, rules),
+		(Err(error), _) | (_, Err(error)) => return Reply::error(500, error.to_string())
+	};
+	match (request.purchase, request.order) {
+		(Some(reference), None) => match reference.resolve(&purchases) {
+			Ok(purchase) => {
+				let steps = purchase.get_processing_steps(&rules, purchase.evaluation_time(as_of));
+				Reply::json(200, json!({ "title": purchase.title, "steps": steps }))
+			},
+			Err(reply) => reply
+		},
+		(None, Some(lines)) => {
+			let mut order = Order {
+				purchases: PurchaseCollection(Default::default())
+			};
+			for line in lines {
+				if line.quantity == Some(0) {
+					return Reply::error(400, "the quantity has to be at least 1");
+				}
+				match line.purchase.resolve(&purchases) {
+					Ok(purchase) => {
+						*order.purchases.0.entry(purchase).or_default() +=
+							line.quantity.unwrap_or(1);
+					},
+					Err(reply) => return reply
+				}
+			}
+			let at = order.evaluation_time(as_of);
+			Reply::json(200, plan_to_json(&order.get_processing_plan(&rules, at)))
+		},
+		_ => Reply::error(400, "give either a purchase or an order")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		io::{Read, Write},
+		net::TcpStream,
+		thread
+	};
+
+	use super::*;
+	use crate::frontend::testing::TempDir;
+
+	/// Sends a request the way another service would, returning the status and JSON body.
+	fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+		let mut stream = TcpStream::connect(address).unwrap();
+		write!(
+			stream,
+			"{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+			method,
+			path,
+			body.len(),
+			body
+		)
+		.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		let (head, body) = response.split_once("\r\n\r\n").unwrap();
+		let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+		(status, serde_json::from_str(body).unwrap_or(Value::Null))
+	}
+
+	#[test]
+	fn serves_entries_and_processing() {
+		let directory = TempDir::new("server");
+		let data = ApplicationData::new(
+			directory.join("all_purchases.json"),
+			directory.join("all_rules.json")
+		);
+		fs::write(&data.purchase_path, "[]").unwrap();
+		fs::write(&data.rule_path, "[]").unwrap();
+		let server = Server::bind("127.0.0.1:0").unwrap();
+		let address = server.address().unwrap();
+		thread::spawn(move || server.run(&data));
+
+		let (status, book) = request(
+			address,
+			"POST",
+			"/purchases",
+			r#"{"title": "1984", "identifiers": ["book", "physical"]}"#
+		);
+		assert_eq!(status, 201);
+		let (status, _) = request(
+			address,
+			"POST",
+			"/rules",
+			r#"{"title": "Royalty slip", "process_action": "packing_slip(royalty)", "trigger": "any(book)"}"#
+		);
+		assert_eq!(status, 201);
+		let (status, body) = request(
+			address,
+			"POST",
+			"/rules",
+			r#"{"title": "Broken", "process_action": "packing_slip", "trigger": "never"}"#
+		);
+		assert_eq!(status, 422);
+		assert_eq!(body["findings"].as_array().unwrap().len(), 1);
+
+		let id = book["id"].as_str().unwrap();
+		let (status, listed) = request(address, "GET", "/purchases", "");
+		assert_eq!((status, listed[0]["title"].as_str()), (200, Some("1984")));
+		let (status, processed) = request(
+			address,
+			"POST",
+			"/process",
+			&format!(r#"{{"purchase": "{}"}}"#, &id[..8])
+		);
+		assert_eq!(status, 200);
+		assert_eq!(
+			processed["steps"][0],
+			json!({ "PackingSlip": { "department": "royalty" } })
+		);
+		let (status, plan) = request(
+			address,
+			"POST",
+			"/process",
+			r#"{"order": [{"purchase": {"title": "Ski Wax", "identifiers": ["physical"]}, "quantity": 2}]}"#
+		);
+		assert_eq!(status, 200);
+		assert_eq!(plan["lines"][0]["quantity"], 2);
+		assert!(plan["lines"][0]["steps"].as_array().unwrap().is_empty());
+
+		let renamed = r#"{"title": "Animal Farm", "identifiers": ["book"]}"#;
+		let (status, modified) = request(address, "PUT", &format!("/purchases/{}", id), renamed);
+		assert_eq!((status, modified["id"].as_str()), (200, Some(id)));
+		assert_eq!(
+			request(address, "DELETE", &format!("/purchases/{}", id), "").0,
+			204
+		);
+		assert_eq!(
+			request(address, "GET", &format!("/purchases/{}", id), "").0,
+			404
+		);
+		assert_eq!(request(address, "PATCH", "/rules", "").0, 405);
+		assert_eq!(request(address, "GET", "/elsewhere", "").0, 404);
+	}
+}
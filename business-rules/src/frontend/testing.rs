@@ -0,0 +1,24 @@
+//! Helpers shared by the tests of the frontend.
+
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+	process
+};
+
+/// A fresh directory, removed with everything in it when dropped.
+#[derive(Debug)]
+pub(crate) struct TempDir(PathBuf);
+impl TempDir {
+	/// Tests run at the same time, so every test needs a name of its own.
+	pub(crate) fn new(name: &str) -> TempDir {
+		let path = env::temp_dir().join(format!("business-rules-{}-{}", name, process::id()));
+		let _ = fs::remove_dir_all(&path);
+		fs::create_dir_all(&path).expect("temporary directory can be created");
+		TempDir(path)
+	}
+	pub(crate) fn join(&self, path: impl AsRef<Path>) -> PathBuf { self.0.join(path) }
+}
+impl Drop for TempDir {
+	fn drop(&mut self) { let _ = fs::remove_dir_all(&self.0); }
+}
@@ -0,0 +1,990 @@
+//! A full-screen terminal UI for browsing and editing purchases and rules.
+//!
+//! The purchases and rules are listed on the left and filtered live with search queries, the
+//! highlighted entry is shown and edited in the middle and the rules firing for the highlighted
+//! purchase are listed on the right. Changes are saved through the `DatabaseEntry` operations.
+
+use std::{collections::BTreeSet, io, mem};
+
+use business_rules::{
+	dataset::ApplicationData,
+	query::Query,
+	storage::Change,
+	transfer::{PurchaseRow, RuleRow, Transferable},
+	types::{
+		CombinationCondition, EntryId, Identified, Named, Purchase, Rule, RuleOutcome, RuleTrigger
+	},
+	validation::Finding
+};
+use ratatui::{
+	crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+	layout::{Constraint, Layout, Rect},
+	style::{Color, Modifier, Style},
+	text::{Line, Span, Text},
+	widgets::{Block, List, ListState, Paragraph, Wrap},
+	Frame
+};
+
+use super::DatabaseEntry;
+
+/// Runs the UI until the user quits, restoring the terminal afterwards.
+pub(crate) fn run(data: &ApplicationData) -> io::Result<()> {
+	let mut tui = Tui::load(data)?;
+	let mut terminal = ratatui::try_init()?;
+	let result = (|| {
+		while !tui.quit {
+			terminal.draw(|frame| tui.draw(frame))?;
+			if let Event::Key(key) = event::read()? {
+				if key.kind == KeyEventKind::Press {
+					tui.handle_key(key);
+				}
+			}
+		}
+		Ok(())
+	})();
+	ratatui::try_restore()?;
+	result
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Pane {
+	Purchases,
+	Rules
+}
+
+enum Mode {
+	Browse,
+	/// Typing the search query of the focused list.
+	Filter,
+	Edit(Form),
+	Tree(TreeEditor),
+	ConfirmDelete
+}
+
+struct Tui<'d> {
+	data:      &'d ApplicationData,
+	purchases: EntryList<Purchase>,
+	rules:     EntryList<Rule>,
+	focus:     Pane,
+	mode:      Mode,
+	status:    String,
+	quit:      bool
+}
+impl<'d> Tui<'d> {
+	fn load(data: &'d ApplicationData) -> io::Result<Self> {
+		Ok(Tui {
+			data,
+			purchases: EntryList::load(data)?,
+			rules: EntryList::load(data)?,
+			focus: Pane::Purchases,
+			mode: Mode::Browse,
+			status: String::new(),
+			quit: false
+		})
+	}
+	fn reload(&mut self) {
+		if let Err(error) = self
+			.purchases
+			.reload(self.data)
+			.and_then(|()| self.rules.reload(self.data))
+		{
+			self.status = format!("Failed to load the data: {}", error);
+		}
+	}
+	fn move_selection(&mut self, offset: isize) {
+		match self.focus {
+			Pane::Purchases => self.purchases.move_by(offset),
+			Pane::Rules => self.rules.move_by(offset)
+		}
+	}
+	fn filter_mut(&mut self) -> &mut String {
+		match self.focus {
+			Pane::Purchases => &mut self.purchases.filter,
+			Pane::Rules => &mut self.rules.filter
+		}
+	}
+	fn refilter(&mut self) {
+		match self.focus {
+			Pane::Purchases => self.purchases.refilter(),
+			Pane::Rules => self.rules.refilter()
+		}
+	}
+
+	fn handle_key(&mut self, key: KeyEvent) {
+		if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+			self.quit = true;
+			return;
+		}
+		self.status.clear();
+		match mem::replace(&mut self.mode, Mode::Browse) {
+			Mode::Browse => self.browse_key(key),
+			Mode::Filter => self.filter_key(key),
+			Mode::Edit(form) => self.edit_key(form, key),
+			Mode::Tree(tree) => self.tree_key(tree, key),
+			Mode::ConfirmDelete => self.confirm_delete_key(key)
+		}
+	}
+	fn browse_key(&mut self, key: KeyEvent) {
+		match key.code {
+			KeyCode::Char('q') => self.quit = true,
+			KeyCode::Tab | KeyCode::BackTab => {
+				self.focus = match self.focus {
+					Pane::Purchases => Pane::Rules,
+					Pane::Rules => Pane::Purchases
+				}
+			},
+			KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+			KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+			KeyCode::Char('/') => self.mode = Mode::Filter,
+			KeyCode::Esc => {
+				self.filter_mut().clear();
+				self.refilter();
+			},
+			KeyCode::Char('r') => {
+				self.reload();
+				self.status = "Reloaded the data.".to_string();
+			},
+			KeyCode::Char('a') => {
+				self.mode = Mode::Edit(match self.focus {
+					Pane::Purchases => Form::new::<Purchase>(Pane::Purchases, None),
+					Pane::Rules => Form::new::<Rule>(Pane::Rules, None)
+				})
+			},
+			KeyCode::Char('e') | KeyCode::Enter => {
+				let form = match self.focus {
+					Pane::Purchases => self
+						.purchases
+						.selected()
+						.map(|purchase| Form::new(Pane::Purchases, Some(purchase))),
+					Pane::Rules => self
+						.rules
+						.selected()
+						.map(|rule| Form::new(Pane::Rules, Some(rule)))
+				};
+				if let Some(form) = form {
+					self.mode = Mode::Edit(form);
+				}
+			},
+			KeyCode::Char('d') => {
+				let any_selected = match self.focus {
+					Pane::Purchases => self.purchases.selected().is_some(),
+					Pane::Rules => self.rules.selected().is_some()
+				};
+				if any_selected {
+					self.mode = Mode::ConfirmDelete;
+				}
+			},
+			KeyCode::Char('t') if self.focus == Pane::Rules => {
+				if let Some(rule) = self.rules.selected() {
+					self.mode = Mode::Tree(TreeEditor::new(rule));
+				}
+			},
+			_ => {}
+		}
+	}
+	fn filter_key(&mut self, key: KeyEvent) {
+		match key.code {
+			KeyCode::Enter => return,
+			KeyCode::Esc => self.filter_mut().clear(),
+			KeyCode::Backspace => {
+				self.filter_mut().pop();
+			},
+			KeyCode::Up => self.move_selection(-1),
+			KeyCode::Down => self.move_selection(1),
+			KeyCode::Char(c) => self.filter_mut().push(c),
+			_ => {}
+		}
+		self.refilter();
+		if key.code != KeyCode::Esc {
+			self.mode = Mode::Filter;
+		}
+	}
+	fn edit_key(&mut self, mut form: Form, key: KeyEvent) {
+		match key.code {
+			KeyCode::Esc => {
+				self.status = "Canceled editing.".to_string();
+				return;
+			},
+			KeyCode::Enter => {
+				let saved = match form.pane {
+					Pane::Purchases => submit(self.data, &self.purchases, &form),
+					Pane::Rules => submit(self.data, &self.rules, &form)
+				};
+				match saved {
+					Ok(message) => {
+						self.status = message;
+						self.reload();
+						return;
+					},
+					Err(message) => self.status = message
+				}
+			},
+			KeyCode::Up | KeyCode::BackTab => form.focused = form.focused.saturating_sub(1),
+			KeyCode::Down | KeyCode::Tab => {
+				form.focused = (form.focused + 1).min(form.values.len() - 1);
+			},
+			KeyCode::Backspace => {
+				form.values[form.focused].pop();
+			},
+			KeyCode::Char(c) => form.values[form.focused].push(c),
+			_ => {}
+		}
+		self.mode = Mode::Edit(form);
+	}
+	fn confirm_delete_key(&mut self, key: KeyEvent) {
+		if key.code != KeyCode::Char('y') {
+			self.status = "Kept the entry.".to_string();
+			return;
+		}
+		let removed = match self.focus {
+			Pane::Purchases => self
+				.purchases
+				.selected()
+				.map(|entry| save(self.data, Change::Remove(entry.clone()))),
+			Pane::Rules => self
+				.rules
+				.selected()
+				.map(|entry| save(self.data, Change::Remove(entry.clone())))
+		};
+		if let Some(removed) = removed {
+			self.status = removed.unwrap_or_else(|message| message);
+			self.reload();
+		}
+	}
+	fn tree_key(&mut self, mut tree: TreeEditor, key: KeyEvent) {
+		if let Some((input, mut text)) = tree.input.take() {
+			match key.code {
+				KeyCode::Esc => {},
+				KeyCode::Enter => match text.parse::<RuleTrigger>() {
+					Ok(typed) => tree.apply(input, typed),
+					Err(error) => {
+						self.status = format!("Invalid trigger, {}", error);
+						tree.input = Some((input, text));
+					}
+				},
+				KeyCode::Backspace => {
+					text.pop();
+					tree.input = Some((input, text));
+				},
+				KeyCode::Char(c) => {
+					text.push(c);
+					tree.input = Some((input, text));
+				},
+				_ => tree.input = Some((input, text))
+			}
+			self.mode = Mode::Tree(tree);
+			return;
+		}
+		match key.code {
+			KeyCode::Esc => {
+				self.status = "Canceled editing the trigger.".to_string();
+				return;
+			},
+			KeyCode::Up | KeyCode::Char('k') => tree.selected = tree.selected.saturating_sub(1),
+			KeyCode::Down | KeyCode::Char('j') => {
+				tree.selected = (tree.selected + 1).min(tree.nodes().len() - 1);
+			},
+			KeyCode::Char('e') | KeyCode::Enter => {
+				let text = tree.selected_node().to_string();
+				tree.input = Some((TreeInput::Replace, text));
+			},
+			KeyCode::Char('&') => tree.input = Some((TreeInput::And, String::new())),
+			KeyCode::Char('|') => tree.input = Some((TreeInput::Or, String::new())),
+			KeyCode::Char('n') => tree.toggle_not(),
+			KeyCode::Char('x') => {
+				if let Err(message) = tree.remove_selected() {
+					self.status = message.to_string();
+				}
+			},
+			KeyCode::Char('s') => {
+				let Some(old) = self.rules.find(tree.rule_id) else {
+					self.status = "The rule was removed in the meantime.".to_string();
+					return;
+				};
+				let new = Rule {
+					trigger: tree.trigger.clone(),
+					..old.clone()
+				};
+				let change = Change::Replace {
+					old: old.clone(),
+					new
+				};
+				match save(self.data, change) {
+					Ok(message) => {
+						self.status = message;
+						self.reload();
+						return;
+					},
+					Err(message) => self.status = message
+				}
+			},
+			_ => {}
+		}
+		self.mode = Mode::Tree(tree);
+	}
+
+	fn draw(&self, frame: &mut Frame) {
+		let [main, status] =
+			Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+		let [lists, detail, side] = Layout::horizontal([
+			Constraint::Percentage(30),
+			Constraint::Percentage(40),
+			Constraint::Percentage(30)
+		])
+		.areas(main);
+		let [purchases, rules] =
+			Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(lists);
+		self.draw_list(frame, purchases, &self.purchases, Pane::Purchases);
+		self.draw_list(frame, rules, &self.rules, Pane::Rules);
+		self.draw_detail(frame, detail);
+		frame.render_widget(
+			Paragraph::new(self.firing_rules())
+				.block(Block::bordered().title("Firing rules"))
+				.wrap(Wrap { trim: false }),
+			side
+		);
+		let status_text = if self.status.is_empty() {
+			match &self.mode {
+				Mode::Browse => {
+					"q quit  tab switch list  / filter  e edit  a add  d delete  t trigger tree  r \
+					 reload"
+				},
+				Mode::Filter => "type a search query  enter keep  esc clear",
+				Mode::Edit(_) => "up/down choose field  enter save  esc cancel",
+				Mode::Tree(tree) if tree.input.is_some() => "enter apply  esc cancel",
+				Mode::Tree(_) => "e replace  & and  | or  n not  x remove  s save  esc cancel",
+				Mode::ConfirmDelete => "delete the highlighted entry? y to delete"
+			}
+		} else {
+			&self.status
+		};
+		frame.render_widget(Paragraph::new(status_text), status);
+	}
+	fn draw_list<T: Named>(&self, frame: &mut Frame, area: Rect, list: &EntryList<T>, pane: Pane) {
+		let focused = self.focus == pane;
+		let mut title = match pane {
+			Pane::Purchases => "Purchases".to_string(),
+			Pane::Rules => "Rules".to_string()
+		};
+		if !list.filter.is_empty() || (focused && matches!(self.mode, Mode::Filter)) {
+			title += &format!(" /{}", list.filter);
+		}
+		if list.filter_error.is_some() {
+			title += " (invalid query)";
+		}
+		let block = Block::bordered().title(title).border_style(if focused {
+			Style::new().fg(Color::Yellow)
+		} else {
+			Style::new()
+		});
+		let names = list
+			.shown
+			.iter()
+			.map(|&index| list.entries[index].name().to_string());
+		let mut state =
+			ListState::default().with_selected((!list.shown.is_empty()).then_some(list.selected));
+		frame.render_stateful_widget(
+			List::new(names)
+				.block(block)
+				.highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+			area,
+			&mut state
+		);
+	}
+	fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+		let (title, text) = match &self.mode {
+			Mode::Edit(form) => (
+				if form.old_id.is_some() {
+					"Modify"
+				} else {
+					"Add"
+				},
+				form.text()
+			),
+			Mode::Tree(tree) => ("Trigger tree", tree.text()),
+			_ => {
+				let selected = match self.focus {
+					Pane::Purchases => self.purchases.selected().map(ToString::to_string),
+					Pane::Rules => self.rules.selected().map(ToString::to_string)
+				};
+				("Details", Text::from(selected.unwrap_or_default()))
+			}
+		};
+		frame.render_widget(
+			Paragraph::new(text)
+				.block(Block::bordered().title(title))
+				.wrap(Wrap { trim: false }),
+			area
+		);
+	}
+	/// The processing steps of the highlighted purchase and why each rule did or didn't fire.
+	fn firing_rules(&self) -> Text<'static> {
+		let Some(purchase) = self.purchases.selected() else {
+			return Text::from("No purchase is highlighted.");
+		};
+		let rules = self.rules.entries.iter().cloned().collect::<BTreeSet<_>>();
+		let at = purchase.evaluation_time(None);
+		let mut lines = vec![Line::from(format!("'{}' gets:", purchase.title))];
+		lines.extend(
+			purchase
+				.get_processing_steps(&rules, at)
+				.iter()
+				.map(|step| Line::from(format!(" - {}", step.description())))
+		);
+		lines.push(Line::default());
+		for explanation in purchase.explain_processing(&rules, at) {
+			let title = explanation.rule.title.to_string();
+			lines.push(match explanation.outcome {
+				RuleOutcome::Fired => Line::from(vec![
+					Span::styled("fired ", Style::new().fg(Color::Green)),
+					Span::raw(title),
+				]),
+				outcome => {
+					let reason = match outcome {
+						RuleOutcome::NotTriggered => "trigger didn't match".to_string(),
+						RuleOutcome::Inactive => "not active".to_string(),
+						RuleOutcome::Excluded { group } => format!("excluded by group '{}'", group),
+						RuleOutcome::Stopped { by } => format!("stopped by '{}'", by),
+						RuleOutcome::Fired => unreachable!()
+					};
+					Line::styled(
+						format!("      {}: {}", title, reason),
+						Style::new().fg(Color::DarkGray)
+					)
+				}
+			});
+		}
+		Text::from(lines)
+	}
+}
+
+/// The entries of one kind, with the ones matching the filter shown in search order.
+struct EntryList<T> {
+	entries:      Vec<T>,
+	filter:       String,
+	filter_error: Option<String>,
+	shown:        Vec<usize>,
+	/// Position of the highlighted entry in `shown`.
+	selected:     usize
+}
+impl<T: DatabaseEntry> EntryList<T> {
+	fn load(data: &ApplicationData) -> io::Result<Self> {
+		let mut list = EntryList {
+			entries:      Vec::new(),
+			filter:       String::new(),
+			filter_error: None,
+			shown:        Vec::new(),
+			selected:     0
+		};
+		list.reload(data)?;
+		Ok(list)
+	}
+	/// Loads the entries again, keeping the highlighted entry highlighted if it still exists.
+	fn reload(&mut self, data: &ApplicationData) -> io::Result<()> {
+		let selected = self.selected().map(Identified::id);
+		self.entries = T::load_from_disk(T::get_path(data))?.into_iter().collect();
+		self.refilter_keeping(selected);
+		Ok(())
+	}
+	fn refilter(&mut self) { self.refilter_keeping(self.selected().map(Identified::id)) }
+	/// Applies the filter, highlighting the entry with the given ID if it is shown.
+	fn refilter_keeping(&mut self, selected: Option<EntryId>) {
+		self.filter_error = None;
+		if self.filter.trim().is_empty() {
+			self.shown = (0..self.entries.len()).collect();
+		} else {
+			match self.filter.parse::<Query>() {
+				Ok(query) => {
+					self.shown = T::search(&query, self.entries.iter())
+						.into_iter()
+						.filter_map(|scored| {
+							self.entries
+								.iter()
+								.position(|entry| std::ptr::eq(entry, scored.entry))
+						})
+						.collect();
+				},
+				// the last valid query stays shown while typing
+				Err(error) => self.filter_error = Some(error.to_string())
+			}
+		}
+		self.selected = selected
+			.and_then(|id| {
+				self.shown
+					.iter()
+					.position(|&index| self.entries[index].id() == id)
+			})
+			.unwrap_or_else(|| self.selected.min(self.shown.len().saturating_sub(1)));
+	}
+	fn selected(&self) -> Option<&T> {
+		self.shown
+			.get(self.selected)
+			.map(|&index| &self.entries[index])
+	}
+	fn find(&self, id: EntryId) -> Option<&T> { self.entries.iter().find(|entry| entry.id() == id) }
+	fn move_by(&mut self, offset: isize) {
+		self.selected = self
+			.selected
+			.saturating_add_signed(offset)
+			.min(self.shown.len().saturating_sub(1));
+	}
+}
+
+/// Entries edited as text fields, one for each column of their transfer row besides the ID.
+trait Editable: DatabaseEntry + Transferable {
+	const FIELDS: &'static [&'static str];
+
+	fn to_fields(&self) -> Vec<String>;
+	/// The entry has the nil ID.
+	fn from_fields(fields: Vec<String>) -> Result<Self, String>;
+}
+impl Editable for Purchase {
+	const FIELDS: &'static [&'static str] = &["title", "identifiers", "attributes", "purchased at"];
+
+	fn to_fields(&self) -> Vec<String> {
+		let row = self.to_row();
+		vec![
+			row.title,
+			row.identifiers,
+			row.attributes,
+			row.purchased_at.unwrap_or_default(),
+		]
+	}
+	fn from_fields(fields: Vec<String>) -> Result<Self, String> {
+		let [title, identifiers, attributes, purchased_at] =
+			<[String; 4]>::try_from(fields).expect("a field for every column");
+		Self::from_row(PurchaseRow {
+			title,
+			identifiers,
+			attributes,
+			purchased_at: Some(purchased_at),
+			id: None
+		})
+	}
+}
+impl Editable for Rule {
+	const FIELDS: &'static [&'static str] = &[
+		"title",
+		"action",
+		"trigger",
+		"scope",
+		"priority",
+		"stop",
+		"group",
+		"valid from",
+		"valid until"
+	];
+
+	fn to_fields(&self) -> Vec<String> {
+		let row = self.to_row();
+		vec![
+			row.title,
+			row.action,
+			row.trigger,
+			row.scope.unwrap_or_default(),
+			row.priority.unwrap_or_default().to_string(),
+			if row.stop.unwrap_or_default() {
+				"yes"
+			} else {
+				"no"
+			}
+			.to_string(),
+			row.group.unwrap_or_default(),
+			row.valid_from.unwrap_or_default(),
+			row.valid_until.unwrap_or_default(),
+		]
+	}
+	fn from_fields(fields: Vec<String>) -> Result<Self, String> {
+		let [title, action, trigger, scope, priority, stop, group, valid_from, valid_until] =
+			<[String; 9]>::try_from(fields).expect("a field for every column");
+		let priority = match priority.trim() {
+			"" => None,
+			priority => Some(
+				priority
+					.parse()
+					.map_err(|_| format!("'{}' is not a whole number", priority))?
+			)
+		};
+		let stop = match stop.trim().to_lowercase().as_str() {
+			"" | "no" | "false" => false,
+			"yes" | "true" => true,
+			other => return Err(format!("'{}' is not yes or no", other))
+		};
+		Self::from_row(RuleRow {
+			title,
+			action,
+			trigger,
+			scope: Some(scope),
+			priority,
+			stop: Some(stop),
+			group: Some(group),
+			valid_from: Some(valid_from),
+			valid_until: Some(valid_until),
+			id: None
+		})
+	}
+}
+
+/// The fields of an entry being added or modified.
+struct Form {
+	pane:    Pane,
+	/// The entry being modified, `None` when adding one.
+	old_id:  Option<EntryId>,
+	labels:  &'static [&'static str],
+	values:  Vec<String>,
+	focused: usize
+}
+impl Form {
+	fn new<T: Editable>(pane: Pane, old: Option<&T>) -> Self {
+		Form {
+			pane,
+			old_id: old.map(Identified::id),
+			labels: T::FIELDS,
+			values: old.map_or_else(|| vec![String::new(); T::FIELDS.len()], T::to_fields),
+			focused: 0
+		}
+	}
+	fn text(&self) -> Text<'static> {
+		let lines =
+			self.labels
+				.iter()
+				.zip(&self.values)
+				.enumerate()
+				.map(|(index, (label, value))| {
+					if index == self.focused {
+						Line::from(vec![
+							Span::styled(format!("{}: ", label), Style::new().fg(Color::Yellow)),
+							Span::raw(format!("{}_", value)),
+						])
+					} else {
+						Line::from(format!("{}: {}", label, value))
+					}
+				});
+		Text::from(lines.collect::<Vec<_>>())
+	}
+}
+/// Saves the entry of the form, returning what happened.
+fn submit<T: Editable>(
+	data: &ApplicationData,
+	list: &EntryList<T>,
+	form: &Form
+) -> Result<String, String> {
+	let mut new = T::from_fields(form.values.clone())?;
+	let change = match form.old_id {
+		Some(id) => {
+			new.set_id(id);
+			let old = list
+				.find(id)
+				.ok_or("The entry was removed in the meantime.")?;
+			Change::Replace {
+				old: old.clone(),
+				new
+			}
+		},
+		None => {
+			new.set_id(EntryId::generate());
+			Change::Insert(new)
+		}
+	};
+	save(data, change)
+}
+/// Validates the entry the change leaves in the data and saves it, returning what happened.
+fn save<T: DatabaseEntry>(data: &ApplicationData, change: Change<T>) -> Result<String, String> {
+	let name = T::type_name_pretty();
+	let mut changed = T::load_from_disk(T::get_path(data)).map_err(|error| error.to_string())?;
+	if !change.apply_to(&mut changed) {
+		return Err(format!(
+			"Nothing was saved, the {} already exists or was changed by someone else.",
+			name.to_lowercase()
+		));
+	}
+	let changed_name = change.after().map(Named::name);
+	if let Some(error) = T::validate_set(&changed)
+		.into_iter()
+		.filter(|finding| Some(finding.rule.as_ref()) == changed_name)
+		.find(Finding::is_error)
+	{
+		return Err(format!("Not saved, {}", error));
+	}
+	let saved = match change {
+		Change::Insert(new) => T::insert_entry(data, new).map(|_| format!("Added the {}.", name)),
+		Change::Replace { old, new } => {
+			T::replace_entry(data, &old, new).map(|_| format!("Saved the {}.", name))
+		},
+		Change::Remove(old) => T::remove_entry(data, &old).map(|_| format!("Deleted the {}.", name))
+	};
+	saved.map_err(|error| format!("Failed to save the {}: {}", name, error))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TreeInput {
+	/// Replaces the highlighted node.
+	Replace,
+	/// Combines the highlighted node with `and`.
+	And,
+	/// Combines the highlighted node with `or`.
+	Or
+}
+
+/// The trigger of a rule being edited one node at a time.
+struct TreeEditor {
+	rule_id:  EntryId,
+	trigger:  RuleTrigger,
+	/// Position of the highlighted node in `nodes`.
+	selected: usize,
+	/// The expression being typed for the highlighted node.
+	input:    Option<(TreeInput, String)>
+}
+impl TreeEditor {
+	fn new(rule: &Rule) -> Self {
+		TreeEditor {
+			rule_id:  rule.id,
+			trigger:  rule.trigger.clone(),
+			selected: 0,
+			input:    None
+		}
+	}
+	/// The path of child indices to every node, parents before their children.
+	fn nodes(&self) -> Vec<Vec<usize>> {
+		fn collect(trigger: &RuleTrigger, path: Vec<usize>, nodes: &mut Vec<Vec<usize>>) {
+			nodes.push(path.clone());
+			for (index, child) in children(trigger).into_iter().enumerate() {
+				let mut child_path = path.clone();
+				child_path.push(index);
+				collect(child, child_path, nodes);
+			}
+		}
+		let mut nodes = Vec::new();
+		collect(&self.trigger, Vec::new(), &mut nodes);
+		nodes
+	}
+	fn selected_path(&self) -> Vec<usize> { self.nodes().swap_remove(self.selected) }
+	fn selected_node(&self) -> &RuleTrigger {
+		self.selected_path()
+			.iter()
+			.fold(&self.trigger, |node, &index| children(node)[index])
+	}
+	fn selected_node_mut(&mut self) -> &mut RuleTrigger {
+		let path = self.selected_path();
+		path.iter().fold(&mut self.trigger, |node, &index| {
+			children_mut(node).swap_remove(index)
+		})
+	}
+	fn apply(&mut self, input: TreeInput, typed: RuleTrigger) {
+		let node = self.selected_node_mut();
+		*node = match input {
+			TreeInput::Replace => typed,
+			TreeInput::And | TreeInput::Or => RuleTrigger::Combination {
+				a:         Box::new(mem::replace(node, RuleTrigger::Never)),
+				b:         Box::new(typed),
+				condition: match input {
+					TreeInput::And => CombinationCondition::Both,
+					_ => CombinationCondition::Either
+				}
+			}
+		};
+	}
+	fn toggle_not(&mut self) {
+		let node = self.selected_node_mut();
+		*node = match mem::replace(node, RuleTrigger::Never) {
+			RuleTrigger::Not { flipped_rule } => *flipped_rule,
+			other => RuleTrigger::Not {
+				flipped_rule: Box::new(other)
+			}
+		};
+	}
+	/// Removes the highlighted node from its list, or its combination which leaves the other part.
+	fn remove_selected(&mut self) -> Result<(), &'static str> {
+		let mut path = self.selected_path();
+		let index = path
+			.pop()
+			.ok_or("The whole trigger can't be removed, replace it instead.")?;
+		let parent = path.iter().fold(&mut self.trigger, |node, &index| {
+			children_mut(node).swap_remove(index)
+		});
+		match parent {
+			RuleTrigger::AllOf(triggers)
+			| RuleTrigger::AnyOf(triggers)
+			| RuleTrigger::NoneOf(triggers)
+			| RuleTrigger::AtLeast(_, triggers) => {
+				triggers.remove(index);
+			},
+			RuleTrigger::Combination { a, b, .. } => {
+				let kept = if index == 0 { b } else { a };
+				*parent = mem::replace(kept.as_mut(), RuleTrigger::Never);
+			},
+			_ => return Err("Only parts of lists and combinations can be removed.")
+		}
+		self.selected = self.selected.min(self.nodes().len() - 1);
+		Ok(())
+	}
+	fn text(&self) -> Text<'static> {
+		let mut lines = self
+			.nodes()
+			.into_iter()
+			.enumerate()
+			.map(|(position, path)| {
+				let node = path
+					.iter()
+					.fold(&self.trigger, |node, &index| children(node)[index]);
+				let line = format!("{}{}", "  ".repeat(path.len()), label(node));
+				if position == self.selected {
+					Line::styled(line, Style::new().add_modifier(Modifier::REVERSED))
+				} else {
+					Line::from(line)
+				}
+			})
+			.collect::<Vec<_>>();
+		if let Some((input, text)) = &self.input {
+			lines.push(Line::default());
+			lines.push(Line::from(format!(
+				"{}: {}_",
+				match input {
+					TreeInput::Replace => "replace with",
+					TreeInput::And => "and",
+					TreeInput::Or => "or"
+				},
+				text
+			)));
+		}
+		Text::from(lines)
+	}
+}
+fn children(trigger: &RuleTrigger) -> Vec<&RuleTrigger> {
+	match trigger {
+		RuleTrigger::Combination { a, b, .. } => vec![a, b],
+		RuleTrigger::Not {
+			flipped_rule: child
+		}
+		| RuleTrigger::OrderContains { other: child } => vec![child],
+		RuleTrigger::AllOf(triggers)
+		| RuleTrigger::AnyOf(triggers)
+		| RuleTrigger::NoneOf(triggers)
+		| RuleTrigger::AtLeast(_, triggers) => triggers.iter().collect(),
+		_ => Vec::new()
+	}
+}
+fn children_mut(trigger: &mut RuleTrigger) -> Vec<&mut RuleTrigger> {
+	match trigger {
+		RuleTrigger::Combination { a, b, .. } => vec![a, b],
+		RuleTrigger::Not {
+			flipped_rule: child
+		}
+		| RuleTrigger::OrderContains { other: child } => vec![child],
+		RuleTrigger::AllOf(triggers)
+		| RuleTrigger::AnyOf(triggers)
+		| RuleTrigger::NoneOf(triggers)
+		| RuleTrigger::AtLeast(_, triggers) => triggers.iter_mut().collect(),
+		_ => Vec::new()
+	}
+}
+/// Nodes with children are labeled by how they combine them, like in `explain`.
+fn label(trigger: &RuleTrigger) -> String {
+	match trigger {
+		RuleTrigger::Combination { condition, .. } => match condition {
+			CombinationCondition::None => "nor",
+			CombinationCondition::ExactlyOne => "xor",
+			CombinationCondition::Either => "or",
+			CombinationCondition::Both => "and"
+		}
+		.to_string(),
+		RuleTrigger::Not { .. } => "not".to_string(),
+		RuleTrigger::AllOf(_) => "all_of".to_string(),
+		RuleTrigger::AnyOf(_) => "any_of".to_string(),
+		RuleTrigger::NoneOf(_) => "none_of".to_string(),
+		RuleTrigger::AtLeast(needed, _) => format!("at_least({})", needed),
+		RuleTrigger::OrderContains { .. } => "order_contains".to_string(),
+		leaf => leaf.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use ratatui::{backend::TestBackend, Terminal};
+
+	use super::*;
+	use crate::frontend::testing::TempDir;
+
+	/// The data lives as long as the directory.
+	fn test_data(name: &str) -> (TempDir, ApplicationData) {
+		let directory = TempDir::new(&format!("tui-{}", name));
+		let data = ApplicationData::new(
+			directory.join("all_purchases.json"),
+			directory.join("all_rules.json")
+		);
+		fs::write(
+			&data.purchase_path,
+			r#"[{"title": "Corsair K95", "identifiers": ["keyboard"]},
+			    {"title": "First Aid", "identifiers": ["book"]}]"#
+		)
+		.unwrap();
+		fs::write(
+			&data.rule_path,
+			r#"[{"title": "royalties", "process_action": "packing_slip(royalty)",
+			     "trigger": "any(book)"}]"#
+		)
+		.unwrap();
+		(directory, data)
+	}
+	fn press(tui: &mut Tui, keys: &str) {
+		for c in keys.chars() {
+			let code = match c {
+				'\n' => KeyCode::Enter,
+				'\t' => KeyCode::Tab,
+				'\x1b' => KeyCode::Esc,
+				'\x08' => KeyCode::Backspace,
+				c => KeyCode::Char(c)
+			};
+			tui.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+		}
+	}
+	fn screen(tui: &Tui) -> String {
+		let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+		terminal.draw(|frame| tui.draw(frame)).unwrap();
+		let buffer = terminal.backend().buffer();
+		buffer
+			.content
+			.chunks(buffer.area.width as usize)
+			.map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>() + "\n")
+			.collect()
+	}
+
+	#[test]
+	fn filters_edits_and_deletes_purchases() {
+		let (_directory, data) = test_data("edit");
+		let mut tui = Tui::load(&data).unwrap();
+		press(&mut tui, "/first\n");
+		assert_eq!(tui.purchases.shown.len(), 1);
+		assert_eq!(
+			tui.purchases.selected().unwrap().title.as_ref(),
+			"First Aid"
+		);
+
+		press(&mut tui, "e Kit\n");
+		assert_eq!(tui.status, "Saved the Purchase.");
+		let saved = fs::read_to_string(&data.purchase_path).unwrap();
+		assert!(saved.contains("First Aid Kit"), "{}", saved);
+
+		press(&mut tui, "\x1bdn");
+		assert_eq!(tui.purchases.entries.len(), 2);
+		press(&mut tui, "dy");
+		assert_eq!(tui.status, "Deleted the Purchase.");
+		assert_eq!(tui.purchases.entries.len(), 1);
+		assert_eq!(tui.purchases.entries[0].title.as_ref(), "Corsair K95");
+	}
+
+	#[test]
+	fn shows_firing_rules_and_edits_triggers() {
+		let (_directory, data) = test_data("tree");
+		let mut tui = Tui::load(&data).unwrap();
+		press(&mut tui, "j");
+		assert!(screen(&tui).contains("fired royalties"));
+
+		press(&mut tui, "\ttns");
+		assert_eq!(tui.status, "Saved the Rule.");
+		assert_eq!(tui.rules.entries[0].trigger.to_string(), "not any(book)");
+		assert!(!screen(&tui).contains("fired royalties"));
+	}
+}
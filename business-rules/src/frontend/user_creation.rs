@@ -0,0 +1,668 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+#[allow(clippy::wildcard_imports)]
+use crate::*;
+
+pub(crate) trait TryUserCreate
+where
+	Self: Sized
+{
+	fn try_prompt_creation(console: &dyn Console) -> Option<Self>;
+}
+impl TryUserCreate for IdentifierCollection {
+	fn try_prompt_creation(console: &dyn Console) -> Option<Self> {
+		say!(
+			console,
+			"Please provide some tags (separated by semicolon)."
+		);
+		let mut all_identifiers = IdentifierCollection::default();
+		// always start by adding
+		let add_reply = try_get_reply(console)?;
+		all_identifiers = add_from_str(console, all_identifiers, add_reply);
+		'review_modify_loop: loop {
+			say!(
+				console,
+				"Identifiers: [{}]",
+				all_identifiers
+					.0
+					.iter()
+					.map(|i| i.0.as_ref())
+					.collect::<Vec<_>>()
+					.join(", ")
+			);
+			if prompt_yes_no_question(console, "Are you satisfied with the identifiers?") {
+				break 'review_modify_loop;
+			}
+			all_identifiers = try_modify_identifiercollection(console, all_identifiers)?;
+		}
+		Some(all_identifiers)
+	}
+}
+impl TryUserCreate for AttributeMap {
+	fn try_prompt_creation(console: &dyn Console) -> Option<Self> {
+		if !prompt_yes_no_question(
+			console,
+			"Do you want to give the purchase attributes, such as a price or region?"
+		) {
+			return Some(AttributeMap::default());
+		}
+		loop {
+			let reply = try_prompt_question(
+				console,
+				"Which attributes (key=value, separated by semicolon)?"
+			)?;
+			match AttributeMap::from_pairs(&reply) {
+				Ok(attributes) => return Some(attributes),
+				Err(message) => {
+					say!(console, "{}", message);
+					say!(console, "Try again.");
+				}
+			}
+		}
+	}
+}
+
+impl TryUserCreate for Purchase {
+	fn try_prompt_creation(console: &dyn Console) -> Option<Self> {
+		Some(Purchase {
+			title:        Arc::from(try_prompt_question(
+				console,
+				"Provide a title to the purchase."
+			)?),
+			identifiers:  IdentifierCollection::try_prompt_creation(console)?,
+			attributes:   AttributeMap::try_prompt_creation(console)?,
+			purchased_at: try_prompt_optional(
+				console,
+				"When was the purchase made, as YYYY-MM-DD or like 2024-05-01T14:30:00Z? (leave \
+				 empty if unknown)",
+				parse_time
+			)?,
+			id:           EntryId::generate()
+		})
+	}
+}
+impl TryUserCreate for Rule {
+	fn try_prompt_creation(console: &dyn Console) -> Option<Self> {
+		let rule = Rule {
+			title:           Arc::from(try_prompt_question(
+				console,
+				"What should the title of this rule be?"
+			)?),
+			process_action:  prompt_process_action(console)?,
+			trigger:         RuleTrigger::try_prompt_creation(console)?,
+			scope:           prompt_rule_scope(console),
+			priority:        0,
+			stop_processing: false,
+			exclusion_group: None,
+			valid_from:      None,
+			valid_until:     None,
+			id:              EntryId::generate()
+		};
+		let rule = if prompt_yes_no_question(
+			console,
+			"Do you want to set a priority, stop or exclusion group for this rule?"
+		) {
+			try_prompt_processing_order(console, rule)?
+		} else {
+			rule
+		};
+		if prompt_yes_no_question(console, "Does this rule only apply between certain dates?") {
+			try_prompt_validity(console, rule)
+		} else {
+			Some(rule)
+		}
+	}
+}
+pub(crate) fn try_prompt_processing_order(console: &dyn Console, mut rule: Rule) -> Option<Rule> {
+	rule.priority = 'priority_parse: loop {
+		let reply = try_prompt_question(
+			console,
+			format!(
+				"What priority should this rule have? Higher priorities are processed first \
+				 (currently {}).",
+				rule.priority
+			)
+		)?;
+		match reply.parse::<i32>() {
+			Ok(priority) => break 'priority_parse priority,
+			Err(_) => {
+				say!(console, "Reply was not an integer.");
+				say!(console, "Try again.");
+			}
+		}
+	};
+	rule.stop_processing = prompt_yes_no_question(
+		console,
+		"Should this rule stop lower priority rules from firing when it fires?"
+	);
+	let group = try_prompt_question(
+		console,
+		"Which exclusion group should this rule be in? Only the first rule to fire in a group \
+			 is applied. (leave empty for none)"
+	)?;
+	rule.exclusion_group = Some(group).filter(|group| !group.is_empty()).map(Arc::from);
+	Some(rule)
+}
+pub(crate) fn try_prompt_validity(console: &dyn Console, mut rule: Rule) -> Option<Rule> {
+	rule.valid_from = try_prompt_optional(
+		console,
+		"What is the first day this rule applies, as YYYY-MM-DD? (leave empty for no limit)",
+		parse_date
+	)?;
+	rule.valid_until = try_prompt_optional(
+		console,
+		"What is the last day this rule applies, as YYYY-MM-DD? (leave empty for no limit)",
+		parse_date
+	)?;
+	Some(rule)
+}
+
+/// Asks until the reply is empty or parses.
+pub(crate) fn try_prompt_optional<T>(
+	console: &dyn Console,
+	question: &str,
+	parse: fn(&str) -> Result<T, String>
+) -> Option<Option<T>> {
+	loop {
+		let reply = try_prompt_question(console, question)?;
+		if reply.trim().is_empty() {
+			return Some(None);
+		}
+		match parse(&reply) {
+			Ok(value) => return Some(Some(value)),
+			Err(message) => {
+				say!(console, "{}", message);
+				say!(console, "Try again.");
+			}
+		}
+	}
+}
+
+pub(crate) fn prompt_process_action(console: &dyn Console) -> Option<ProcessAction> {
+	let reply = try_prompt_question(
+		console,
+		format!(
+			"What should happen when this rule is triggered?\n({})",
+			ProcessAction::SYNTAX_HELP
+		)
+	)?;
+	Some(ProcessAction::from(reply.as_str()))
+}
+pub(crate) fn prompt_rule_scope(console: &dyn Console) -> RuleScope {
+	if prompt_yes_no_question(
+		console,
+		"Should this rule run once for a whole order, instead of for each triggering purchase?"
+	) {
+		RuleScope::Order
+	} else {
+		RuleScope::Line
+	}
+}
+
+pub(crate) trait UserSelected {
+	fn prompt_data_selection(data: &ApplicationData) -> Self;
+}
+impl UserSelected for Order {
+	fn prompt_data_selection(data: &ApplicationData) -> Self {
+		let console = data.console.as_ref();
+		let purchases = {
+			let all_purchases =
+				Purchase::load_from_disk_retrying(console, Purchase::get_path(data)).unwrap(); // TODO: make function visibly fallible
+			let mut purchases = PurchaseCollection(BTreeMap::new());
+			'purchase_add_loop: loop {
+				say!(
+					console,
+					"--- Purchase {} ---",
+					purchases.0.values().sum::<usize>()
+				);
+				let new_purchase = 'purchase_find_loop: loop {
+					if let Some(purchase) = Purchase::try_find_single(console, all_purchases.iter())
+					{
+						break 'purchase_find_loop purchase;
+					}
+					if !prompt_yes_no_question(
+						console,
+						"Failed to find valid purchase. Do you want to try again?"
+					) {
+						break 'purchase_add_loop;
+					}
+				};
+				purchases
+					.0
+					.entry(new_purchase.clone())
+					.and_modify(|count| *count += 1)
+					.or_insert(1);
+				say!(console, "Purchase added to order.");
+				if !prompt_yes_no_question(
+					console,
+					"Do you want to add another purchase to this order?"
+				) {
+					break 'purchase_add_loop;
+				}
+			}
+			purchases
+		};
+		Order { purchases }
+	}
+}
+#[derive(Clone)]
+enum RuleTriggerSurface {
+	Never,
+	Always,
+	Title,
+	TitleContains,
+	TitleGlob,
+	TitleRegex,
+	Identifier,
+	IdentifierPrefix,
+	Attribute,
+	Combination,
+	Not,
+	AllOf,
+	AnyOf,
+	NoneOf,
+	AtLeast,
+	Quantity,
+	OrderSize,
+	OrderContains,
+	Expression
+}
+impl RuleTriggerSurface {
+	fn try_complete(self, console: &dyn Console) -> Option<RuleTrigger> {
+		Some(match self {
+			RuleTriggerSurface::Never => RuleTrigger::Never,
+			RuleTriggerSurface::Always => RuleTrigger::Always,
+			RuleTriggerSurface::Title => RuleTrigger::Title {
+				name: Arc::from(try_prompt_question(
+					console,
+					"What should the title of the purchase be for this rule to trigger?"
+				)?)
+			},
+			RuleTriggerSurface::TitleContains => RuleTrigger::TitleContains {
+				text: Arc::from(try_prompt_question(
+					console,
+					"Which text should the title contain for this rule to trigger? (case is \
+					 ignored)"
+				)?)
+			},
+			RuleTriggerSurface::TitleGlob => RuleTrigger::TitleGlob {
+				pattern: Arc::from(try_prompt_question(
+					console,
+					"Which pattern should the whole title match for this rule to trigger? ('*' \
+					 matches any text, '?' any character, case is ignored)"
+				)?)
+			},
+			RuleTriggerSurface::TitleRegex => 'pattern_parse: loop {
+				let reply = try_prompt_question(
+					console,
+					"Which regular expression should the title match for this rule to trigger?"
+				)?;
+				match reply.parse() {
+					Ok(pattern) => break 'pattern_parse RuleTrigger::TitleRegex { pattern },
+					Err(error) => {
+						say!(console, "{}", error);
+						say!(console, "Try again.");
+					}
+				}
+			},
+			RuleTriggerSurface::Identifier => {
+				let identifiers = IdentifierCollection::try_prompt_creation(console)?;
+				let condition = match identifiers.0.len() {
+					1 => {
+						say!(
+							console,
+							"Condition of single identifier is set to 'Any' by default."
+						);
+						IdentifierCondition::Any
+					},
+					_ => 'condition_parse: loop {
+						say!(
+							console,
+							"Select the condition to trigger this Identifier rule:"
+						);
+						say!(console, " - [None] of the identifiers can be present");
+						say!(console, " - [Any] of the identifiers have to be present");
+						say!(console, " - [All] of the identifiers have to be present");
+						let reply = try_get_reply(console)?;
+						break 'condition_parse match reply.to_lowercase() {
+							s if s.contains("none") => IdentifierCondition::None,
+							s if s.contains("any") => IdentifierCondition::Any,
+							s if s.contains("all") => IdentifierCondition::All,
+							s => {
+								say!(console, "'{}' was not recognized as one of the options.", s);
+								say!(console, "Try again.");
+								continue 'condition_parse;
+							}
+						};
+					}
+				};
+				RuleTrigger::Identifier {
+					identifiers,
+					condition
+				}
+			},
+			RuleTriggerSurface::IdentifierPrefix => RuleTrigger::IdentifierPrefix {
+				prefix: Arc::from(try_prompt_question(
+					console,
+					"Which prefix should an identifier start with for this rule to trigger? (e.g. \
+					 'membership:')"
+				)?)
+			},
+			RuleTriggerSurface::Attribute => {
+				let key = try_prompt_question(console, "Which attribute should be compared?")?;
+				let comparison =
+					try_prompt_comparison_kind(console, &format!("the attribute '{}'", key))?;
+				let value = try_prompt_question(
+					console,
+					format!("What should it be compared to ({} ...)?", comparison)
+				)?;
+				RuleTrigger::Attribute {
+					key: Arc::from(key),
+					comparison,
+					value: AttributeValue::from_text(&value)
+				}
+			},
+			RuleTriggerSurface::Combination => {
+				say!(console, "In order to make a combination of rule triggers,");
+				say!(console, "you must provide two different triggers.");
+				say!(console, "Are you sure you want to proceed?");
+				RuleTrigger::Combination {
+					a:         {
+						say!(console, "--- RULE TRIGGER A ---");
+						Box::new(RuleTrigger::try_prompt_creation(console)?)
+					},
+					b:         {
+						say!(console, "--- RULE TRIGGER B ---");
+						Box::new(RuleTrigger::try_prompt_creation(console)?)
+					},
+					condition: {
+						'condition_parse: loop {
+							say!(console, "Select the combinational trigger of this rule:");
+							say!(console, " - [None] of the triggers need to be active");
+							say!(console, " - [ExactlyOne] of the triggers has to be active");
+							say!(console, " - [Either] one of the triggers has to be active");
+							say!(console, " - [Both] of the triggers have to be cative");
+							let reply = try_get_reply(console)?;
+							break 'condition_parse match reply.to_lowercase() {
+								s if s.contains("none") => CombinationCondition::None,
+								s if s.contains("exactly") => CombinationCondition::ExactlyOne,
+								s if s.contains("either") => CombinationCondition::Either,
+								s if s.contains("both") => CombinationCondition::Both,
+								s => {
+									say!(
+										console,
+										"'{}' was not recognized as one of the options.",
+										s
+									);
+									say!(console, "Try again.");
+									continue 'condition_parse;
+								}
+							};
+						}
+					}
+				}
+			},
+			RuleTriggerSurface::Not => {
+				say!(console, "--- FLIPPED RULE TRIGGER ---");
+				RuleTrigger::Not {
+					flipped_rule: Box::new(RuleTrigger::try_prompt_creation(console)?)
+				}
+			},
+			RuleTriggerSurface::AllOf => RuleTrigger::AllOf(try_prompt_trigger_list(console)?),
+			RuleTriggerSurface::AnyOf => RuleTrigger::AnyOf(try_prompt_trigger_list(console)?),
+			RuleTriggerSurface::NoneOf => RuleTrigger::NoneOf(try_prompt_trigger_list(console)?),
+			RuleTriggerSurface::AtLeast => {
+				let triggers = try_prompt_trigger_list(console)?;
+				let count = 'count_request_loop: loop {
+					let reply = try_prompt_question(
+						console,
+						format!(
+							"How many of the {} triggers have to be active?",
+							triggers.len()
+						)
+					)?;
+					let Ok(count) = reply.parse::<usize>() else {
+						say!(console, "Reply was not an unsigned integer.");
+						say!(console, "Try again.");
+						continue 'count_request_loop;
+					};
+					if count > triggers.len() {
+						say!(console, "Reply was larger than the amount of triggers.");
+						say!(console, "Try again.");
+						continue 'count_request_loop;
+					}
+					break 'count_request_loop count;
+				};
+				RuleTrigger::AtLeast(count, triggers)
+			},
+			RuleTriggerSurface::Quantity => {
+				let (comparison, amount) =
+					try_prompt_comparison(console, "the amount of this purchase in the order")?;
+				RuleTrigger::Quantity { comparison, amount }
+			},
+			RuleTriggerSurface::OrderSize => {
+				let (comparison, amount) =
+					try_prompt_comparison(console, "the total amount of items in the order")?;
+				RuleTrigger::OrderSize { comparison, amount }
+			},
+			RuleTriggerSurface::OrderContains => {
+				say!(console, "--- TRIGGER FOR ANOTHER PURCHASE IN THE ORDER ---");
+				RuleTrigger::OrderContains {
+					other: Box::new(RuleTrigger::try_prompt_creation(console)?)
+				}
+			},
+			RuleTriggerSurface::Expression => 'expression_parse: loop {
+				let expression = try_prompt_question(
+					console,
+					"Type the trigger expression, e.g. 'any(book, physical) and not \
+					 title(\"1984\")':"
+				)?;
+				match expression.parse() {
+					Ok(trigger) => break 'expression_parse trigger,
+					Err(error) => {
+						say!(console, "{}", error.pointed(&expression));
+						say!(console, "Try again.");
+					}
+				}
+			}
+		})
+	}
+}
+fn try_prompt_comparison(console: &dyn Console, compared: &str) -> Option<(Comparison, usize)> {
+	let comparison = try_prompt_comparison_kind(console, compared)?;
+	let amount = 'amount_parse: loop {
+		let reply = try_prompt_question(
+			console,
+			format!("What should it be compared to ({} ...)?", comparison)
+		)?;
+		match reply.parse::<usize>() {
+			Ok(amount) => break 'amount_parse amount,
+			Err(_) => {
+				say!(console, "Reply was not an unsigned integer.");
+				say!(console, "Try again.");
+			}
+		}
+	};
+	Some((comparison, amount))
+}
+fn try_prompt_comparison_kind(console: &dyn Console, compared: &str) -> Option<Comparison> {
+	'comparison_parse: loop {
+		let reply = try_prompt_question(
+			console,
+			format!(
+				"How should {} be compared? ({})",
+				compared,
+				Comparison::ALL.map(Comparison::symbol).join(", ")
+			)
+		)?;
+		match Comparison::from_symbol(reply.trim()) {
+			Some(comparison) => break 'comparison_parse Some(comparison),
+			None => {
+				say!(
+					console,
+					"'{}' was not recognized as one of the options.",
+					reply
+				);
+				say!(console, "Try again.");
+			}
+		}
+	}
+}
+fn try_prompt_trigger_list(console: &dyn Console) -> Option<Vec<RuleTrigger>> {
+	let mut triggers = Vec::new();
+	'trigger_add_loop: loop {
+		say!(console, "--- RULE TRIGGER {} ---", triggers.len() + 1);
+		triggers.push(RuleTrigger::try_prompt_creation(console)?);
+		if !prompt_yes_no_question(console, "Do you want to add another trigger to this list?") {
+			break 'trigger_add_loop;
+		}
+	}
+	Some(triggers)
+}
+impl TryUserCreate for RuleTrigger {
+	fn try_prompt_creation(console: &dyn Console) -> Option<Self> {
+		lazy_static! {
+			static ref SURFACE_DECISION: Decision<RuleTriggerSurface> = Decision {
+				prompt: "Select the type of trigger for this rule:".into(),
+				possible_choices: vec![
+					(("N", "Never trigger").into(), RuleTriggerSurface::Never).into(),
+					(("A", "Always trigger").into(), RuleTriggerSurface::Always).into(),
+					(
+						("T", "Trigger on title match").into(),
+						RuleTriggerSurface::Title
+					)
+						.into(),
+					(
+						("C", "Trigger when the title contains some text").into(),
+						RuleTriggerSurface::TitleContains
+					)
+						.into(),
+					(
+						("G", "Trigger when the title matches a wildcard pattern").into(),
+						RuleTriggerSurface::TitleGlob
+					)
+						.into(),
+					(
+						("R", "Trigger when the title matches a regular expression").into(),
+						RuleTriggerSurface::TitleRegex
+					)
+						.into(),
+					(
+						("I", "Trigger on identifier match").into(),
+						RuleTriggerSurface::Identifier
+					)
+						.into(),
+					(
+						("P", "Trigger when an identifier starts with a prefix").into(),
+						RuleTriggerSurface::IdentifierPrefix
+					)
+						.into(),
+					(
+						("V", "Trigger on the value of an attribute").into(),
+						RuleTriggerSurface::Attribute
+					)
+						.into(),
+					(
+						("2", "Trigger on a combination of two other rules").into(),
+						RuleTriggerSurface::Combination
+					)
+						.into(),
+					(
+						("!", "Trigger when another rule is not triggered").into(),
+						RuleTriggerSurface::Not
+					)
+						.into(),
+					(
+						("&", "Trigger when all of several other rules are triggered").into(),
+						RuleTriggerSurface::AllOf
+					)
+						.into(),
+					(
+						("|", "Trigger when any of several other rules are triggered").into(),
+						RuleTriggerSurface::AnyOf
+					)
+						.into(),
+					(
+						(
+							"~",
+							"Trigger when none of several other rules are triggered"
+						)
+							.into(),
+						RuleTriggerSurface::NoneOf
+					)
+						.into(),
+					(
+						(
+							"#",
+							"Trigger when at least some of several other rules are triggered"
+						)
+							.into(),
+						RuleTriggerSurface::AtLeast
+					)
+						.into(),
+					(
+						("Q", "Trigger on the quantity of the purchase in its order").into(),
+						RuleTriggerSurface::Quantity
+					)
+						.into(),
+					(
+						("S", "Trigger on the total size of the order").into(),
+						RuleTriggerSurface::OrderSize
+					)
+						.into(),
+					(
+						(
+							"O",
+							"Trigger when the order contains another matching purchase"
+						)
+							.into(),
+						RuleTriggerSurface::OrderContains
+					)
+						.into(),
+					(
+						("E", "Type the trigger as an expression").into(),
+						RuleTriggerSurface::Expression
+					)
+						.into()
+				],
+				..Default::default()
+			};
+		}
+		SURFACE_DECISION.run_prompt(console)?.try_complete(console)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use business_rules::console::ScriptedConsole;
+
+	use super::*;
+
+	#[test]
+	fn creates_triggers_from_replies() {
+		let console = ScriptedConsole::new(["I", "video; book", "y", "all"]);
+		let trigger = RuleTrigger::try_prompt_creation(&console).unwrap();
+		assert_eq!(trigger, "all(video, book)".parse().unwrap());
+		assert_eq!(console.remaining(), 0);
+
+		let console = ScriptedConsole::new(["V", "price", ">=", "20"]);
+		let trigger = RuleTrigger::try_prompt_creation(&console).unwrap();
+		assert_eq!(trigger, "price >= 20".parse().unwrap());
+		let console = ScriptedConsole::new(["R", "(", "^ski"]);
+		let trigger = RuleTrigger::try_prompt_creation(&console).unwrap();
+		assert_eq!(trigger, r#"title_regex("^ski")"#.parse().unwrap());
+		assert_eq!(console.remaining(), 0);
+	}
+
+	#[test]
+	fn creates_purchases_from_replies() {
+		let console = ScriptedConsole::new(["1984", "book", "y", "n", "someday", "2024-05-01"]);
+		let purchase = Purchase::try_prompt_creation(&console).unwrap();
+		assert_eq!(
+			purchase.identifiers,
+			IdentifierCollection(["book".into()].into())
+		);
+		assert_eq!(purchase.purchased_at, parse_time("2024-05-01").ok());
+		assert_eq!(console.remaining(), 0);
+	}
+}
@@ -0,0 +1,95 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use super::types::{Purchase, RuleTrigger};
+
+/// What changing a rule's trigger does to the stored purchases, each taken on its own.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TriggerImpact<'a> {
+	pub newly_triggering:   Vec<&'a Purchase>,
+	pub stopped_triggering: Vec<&'a Purchase>,
+	/// Purchases triggering the rule both before and after the change.
+	pub still_triggering:   usize
+}
+impl<'a> TriggerImpact<'a> {
+	pub fn between(
+		old: &RuleTrigger,
+		new: &RuleTrigger,
+		purchases: &'a BTreeSet<Purchase>
+	) -> Self {
+		let mut impact = TriggerImpact {
+			newly_triggering:   Vec::new(),
+			stopped_triggering: Vec::new(),
+			still_triggering:   0
+		};
+		for purchase in purchases {
+			match (old.triggered_by(purchase), new.triggered_by(purchase)) {
+				(false, true) => impact.newly_triggering.push(purchase),
+				(true, false) => impact.stopped_triggering.push(purchase),
+				(true, true) => impact.still_triggering += 1,
+				(false, false) => {}
+			}
+		}
+		impact
+	}
+}
+impl Display for TriggerImpact<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let listed = |purchase: &&Purchase| {
+			let identifiers = purchase
+				.identifiers
+				.0
+				.iter()
+				.map(ToString::to_string)
+				.collect::<Vec<_>>();
+			format!("{} [{}]", purchase.title, identifiers.join(", "))
+		};
+		if self.newly_triggering.is_empty() && self.stopped_triggering.is_empty() {
+			write!(f, "No purchase triggers the rule differently")?;
+		} else {
+			for purchase in &self.newly_triggering {
+				writeln!(f, "+ {}", listed(purchase))?;
+			}
+			for purchase in &self.stopped_triggering {
+				writeln!(f, "- {}", listed(purchase))?;
+			}
+			write!(
+				f,
+				"{} purchase(s) will newly trigger the rule, {} will stop triggering it",
+				self.newly_triggering.len(),
+				self.stopped_triggering.len()
+			)?;
+		}
+		write!(f, ", {} still trigger it.", self.still_triggering)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::purchase;
+
+	#[test]
+	fn lists_changed_purchases() {
+		let purchases = BTreeSet::from([
+			purchase("1984", &["book", "physical"]),
+			purchase("Learning to Ski", &["video"]),
+			purchase("Ski Wax", &["physical"])
+		]);
+		let old = "any(physical)".parse().unwrap();
+		let new = "any(book, video)".parse().unwrap();
+		let impact = TriggerImpact::between(&old, &new, &purchases);
+		assert_eq!(impact.newly_triggering[0].title.as_ref(), "Learning to Ski");
+		assert_eq!(impact.stopped_triggering[0].title.as_ref(), "Ski Wax");
+		assert_eq!(impact.still_triggering, 1);
+		assert_eq!(
+			impact.to_string(),
+			"+ Learning to Ski ['video']\n- Ski Wax ['physical']\n1 purchase(s) will newly trigger \
+			 the rule, 1 will stop triggering it, 1 still trigger it."
+		);
+		let unchanged = TriggerImpact::between(&old, &old, &purchases);
+		assert_eq!(
+			unchanged.to_string(),
+			"No purchase triggers the rule differently, 2 still trigger it."
+		);
+	}
+}
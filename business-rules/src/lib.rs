@@ -0,0 +1,41 @@
+//! Business rules for processing purchases: which actions to take when something is bought.
+//!
+//! Rules pair a trigger with a process action and are evaluated against purchases or whole
+//! orders. None of the library reads from or writes to the terminal, everything that can fail
+//! returns a `Result`.
+//!
+//! ```
+//! use std::collections::BTreeSet;
+//!
+//! use business_rules::types::{Purchase, Rule};
+//!
+//! let rules: BTreeSet<Rule> = serde_json::from_str(
+//!     r#"[{"title": "royalties", "process_action": "packing_slip(royalty)",
+//!          "trigger": "any(book)"}]"#
+//! )
+//! .unwrap();
+//! let purchase: Purchase =
+//!     serde_json::from_str(r#"{"title": "First Aid", "identifiers": ["book"]}"#).unwrap();
+//! let steps = purchase.get_processing_steps(&rules, purchase.evaluation_time(None));
+//! assert_eq!(steps[0].to_string(), "packing_slip(royalty)");
+//! ```
+
+pub mod actions;
+pub mod artifacts;
+pub mod audit;
+pub mod console;
+pub mod dataset;
+pub mod explanation;
+pub mod expression;
+pub mod fixtures;
+pub mod impact;
+pub mod location;
+pub mod orders;
+pub mod query;
+pub mod searching;
+pub mod storage;
+#[cfg(test)]
+mod testing;
+pub mod transfer;
+pub mod types;
+pub mod validation;
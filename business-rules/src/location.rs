@@ -0,0 +1,237 @@
+//! Where the data is kept: a data directory holding the default dataset, with named profiles such
+//! as `staging` or `production` kept next to it.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt::Display,
+	fs::{self, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf}
+};
+
+use serde::Deserialize;
+
+use crate::dataset::ApplicationData;
+
+pub const DATA_DIR_VARIABLE: &str = "BUSINESS_RULES_DATA_DIR";
+pub const PROFILE_VARIABLE: &str = "BUSINESS_RULES_PROFILE";
+const APPLICATION_DIR: &str = "business-rules";
+const PROFILES_DIR: &str = "profiles";
+
+/// The settings of `config.yaml` in the config directory of the user, all of them optional.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+	pub data_dir: Option<PathBuf>,
+	/// The profile used unless another one is chosen.
+	pub profile:  Option<String>,
+	/// Profiles kept somewhere else than in the data directory.
+	#[serde(default)]
+	pub profiles: BTreeMap<String, PathBuf>
+}
+impl Config {
+	pub fn default_path() -> Option<PathBuf> {
+		dirs::config_dir().map(|directory| directory.join(APPLICATION_DIR).join("config.yaml"))
+	}
+	/// A missing or empty file is an empty config.
+	pub fn load(path: &Path) -> Result<Config, LocationError> {
+		match fs::read_to_string(path) {
+			Ok(text) if text.trim().is_empty() => Ok(Config::default()),
+			Ok(text) => serde_yaml::from_str(&text).map_err(|error| LocationError::Config {
+				path:    path.to_path_buf(),
+				message: error.to_string()
+			}),
+			Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+			Err(error) => Err(error.into())
+		}
+	}
+}
+
+/// The dataset in use, either the default one or a named profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataLocation {
+	/// Holds the default dataset and the `profiles` directory.
+	pub data_dir: PathBuf,
+	/// `None` for the default dataset.
+	pub profile:  Option<String>,
+	profiles:     BTreeMap<String, PathBuf>
+}
+impl DataLocation {
+	/// The given data directory and profile win over the ones of the config, the data directory
+	/// of the user is used when none is given at all.
+	pub fn resolve(
+		data_dir: Option<PathBuf>,
+		profile: Option<String>,
+		config: Config
+	) -> Result<DataLocation, LocationError> {
+		let data_dir = data_dir
+			.or(config.data_dir)
+			.or_else(|| dirs::data_dir().map(|directory| directory.join(APPLICATION_DIR)))
+			.ok_or(LocationError::NoDataDir)?;
+		DataLocation {
+			data_dir,
+			profile: None,
+			profiles: config.profiles
+		}
+		.with_profile(profile.or(config.profile))
+	}
+	pub fn with_profile(&self, profile: Option<String>) -> Result<DataLocation, LocationError> {
+		if let Some(name) = &profile {
+			let valid = name
+				.chars()
+				.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+			if name.is_empty() || !valid {
+				return Err(LocationError::InvalidProfile(name.clone()));
+			}
+		}
+		Ok(DataLocation {
+			profile,
+			..self.clone()
+		})
+	}
+
+	/// The directory holding the files of the dataset.
+	pub fn directory(&self) -> PathBuf {
+		match &self.profile {
+			None => self.data_dir.clone(),
+			Some(name) => self
+				.profiles
+				.get(name)
+				.cloned()
+				.unwrap_or_else(|| self.data_dir.join(PROFILES_DIR).join(name))
+		}
+	}
+	/// The profiles of the config and the ones in the data directory, in order.
+	pub fn profiles(&self) -> io::Result<Vec<String>> {
+		let mut names = self.profiles.keys().cloned().collect::<BTreeSet<_>>();
+		match fs::read_dir(self.data_dir.join(PROFILES_DIR)) {
+			Ok(entries) => {
+				for entry in entries {
+					let entry = entry?;
+					if entry.file_type()?.is_dir() {
+						names.extend(entry.file_name().to_str().map(String::from));
+					}
+				}
+			},
+			Err(error) if error.kind() == io::ErrorKind::NotFound => {},
+			Err(error) => return Err(error)
+		}
+		Ok(names.into_iter().collect())
+	}
+	/// Creates an empty dataset first if the directory doesn't hold one yet.
+	pub fn open(&self) -> io::Result<ApplicationData> {
+		let directory = self.directory();
+		fs::create_dir_all(&directory)?;
+		if !directory.join(ApplicationData::DATABASE_FILE).exists() {
+			let json = ApplicationData::json_in(&directory);
+			for path in [json.purchase_path, json.rule_path] {
+				match OpenOptions::new().write(true).create_new(true).open(path) {
+					Ok(mut file) => file.write_all(b"[]")?,
+					Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {},
+					Err(error) => return Err(error)
+				}
+			}
+		}
+		Ok(ApplicationData::from_src_path(directory))
+	}
+}
+impl Display for DataLocation {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.profile {
+			None => write!(f, "the default dataset"),
+			Some(name) => write!(f, "profile '{}'", name)
+		}?;
+		write!(f, " in {}", self.directory().display())
+	}
+}
+
+#[derive(Debug)]
+pub enum LocationError {
+	/// The user has no data directory and none was given.
+	NoDataDir,
+	InvalidProfile(String),
+	Config {
+		path:    PathBuf,
+		message: String
+	},
+	Io(io::Error)
+}
+impl Display for LocationError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			LocationError::NoDataDir => write!(
+				f,
+				"There is no data directory, give one with '--data-dir' or {}.",
+				DATA_DIR_VARIABLE
+			),
+			LocationError::InvalidProfile(name) => write!(
+				f,
+				"'{}' is not a valid profile name, use letters, digits, '-' and '_'.",
+				name
+			),
+			LocationError::Config { path, message } => {
+				write!(f, "Invalid config {}: {}", path.display(), message)
+			},
+			LocationError::Io(error) => write!(f, "IO error: {}", error)
+		}
+	}
+}
+impl From<io::Error> for LocationError {
+	fn from(value: io::Error) -> Self { LocationError::Io(value) }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::TempDir;
+
+	#[test]
+	fn opens_profiles_as_empty_datasets() {
+		let data_dir = TempDir::new("location-profiles");
+		let elsewhere = TempDir::new("location-production");
+		let config: Config = serde_yaml::from_str(&format!(
+			"profile: staging\nprofiles:\n  production: {}",
+			elsewhere.path().display()
+		))
+		.unwrap();
+		let location = DataLocation::resolve(Some(data_dir.path().into()), None, config).unwrap();
+		assert_eq!(location.profile.as_deref(), Some("staging"));
+		assert_eq!(
+			location.directory(),
+			data_dir.join("profiles").join("staging")
+		);
+
+		let data = location.open().unwrap();
+		assert_eq!(fs::read_to_string(&data.purchase_path).unwrap(), "[]");
+		assert_eq!(fs::read_to_string(&data.rule_path).unwrap(), "[]");
+		assert_eq!(location.profiles().unwrap(), ["production", "staging"]);
+
+		let production = location.with_profile(Some("production".into())).unwrap();
+		assert_eq!(production.directory(), elsewhere.path());
+		assert_eq!(
+			location.with_profile(None).unwrap().directory(),
+			data_dir.path()
+		);
+		assert!(matches!(
+			location.with_profile(Some("../up".into())),
+			Err(LocationError::InvalidProfile(_))
+		));
+	}
+
+	#[test]
+	fn loads_missing_and_invalid_configs() {
+		let directory = TempDir::new("location-config");
+		let path = directory.join("config.yaml");
+		assert!(Config::load(&path).unwrap().data_dir.is_none());
+		fs::write(&path, "data_dir: /srv/rules\n").unwrap();
+		assert_eq!(
+			Config::load(&path).unwrap().data_dir,
+			Some(PathBuf::from("/srv/rules"))
+		);
+		fs::write(&path, "data_directory: /srv/rules\n").unwrap();
+		assert!(matches!(
+			Config::load(&path),
+			Err(LocationError::Config { .. })
+		));
+	}
+}
@@ -1,61 +1,126 @@
-use std::{env, sync::Arc};
+use std::{env, process::ExitCode, sync::Arc};
 
-mod library;
+mod frontend;
 
-use lazy_static::lazy_static;
 #[allow(clippy::wildcard_imports)]
-use library::{decisions::*, io::*, printing::*, searching::*, types::*, user_creation::*, *};
+use business_rules::{
+	actions::*, audit, console::Console, dataset::*, location::DataLocation, types::*
+};
+#[allow(clippy::wildcard_imports)]
+use frontend::{cli, decisions::*, io::*, printing::*, searching::*, user_creation::*, *};
+use lazy_static::lazy_static;
+
+fn main() -> ExitCode {
+	let mut args = cli::Arguments::parse(env::args().skip(1)); // skip executable path
+	let (mut location, data) = match cli::open_data(&mut args) {
+		Ok(opened) => opened,
+		Err(error) => return cli::fail(error)
+	};
+	let mut data = data.with_console(Arc::new(Terminal));
+	if !args.is_empty() {
+		return cli::run(args, &data);
+	}
 
-fn main() {
 	'program_loop: loop {
-		type DecisionFn = fn(&ApplicationData);
+		type DecisionFn = fn(&mut DataLocation, &mut ApplicationData);
 		lazy_static! {
 			static ref DECISION: Decision<DecisionFn> = Decision {
 				possible_choices: vec![
 					(
 						("P", "View purchase data").into(),
-						Purchase::entry_action_decision as DecisionFn
+						(|_, data| Purchase::entry_action_decision(data)) as DecisionFn
 					)
 						.into(),
 					(
 						("R", "View rule data").into(),
-						Rule::entry_action_decision as DecisionFn
+						(|_, data| Rule::entry_action_decision(data)) as DecisionFn
+					)
+						.into(),
+					(
+						("Q", "Query database").into(),
+						(|_, data| query_database(data)) as DecisionFn
+					)
+						.into(),
+					(
+						("U", "Undo the last change").into(),
+						(|_, data| undo_change(data)) as DecisionFn
 					)
 						.into(),
-					(("Q", "Query database").into(), query_database as DecisionFn).into()
+					(
+						("Y", "Redo the last undone change").into(),
+						(|_, data| redo_change(data)) as DecisionFn
+					)
+						.into(),
+					(
+						("D", "Switch to another dataset").into(),
+						switch_dataset as DecisionFn
+					)
+						.into()
 				],
 				cancel_answer: Answer::exit_answer(),
 				..Default::default()
 			};
 		}
 
-		// maybe this should be assigned somewhere, through env or something
-		let src_path: String = env::current_dir()
-			.expect("cannot get first env argument?")
-			.to_str()
-			.expect("could not convert path to string?")
-			.to_string();
-		let data = ApplicationData::from_src_path(src_path + "/src/");
-		assert!(
-			data.purchase_path.try_exists().unwrap_or(false),
-			"Purchase path not found?"
-		);
-		assert!(
-			data.rule_path.try_exists().unwrap_or(false),
-			"Rule path not found?"
-		);
+		let console = data.console.clone();
+		say!(console, "----- MAIN MENU -----");
+		say!(console, "Using {}.", location);
 
-		println!("----- MAIN MENU -----");
-
-		if let Some(action) = DECISION.run_prompt() {
-			action(&data);
+		if let Some(action) = DECISION.run_prompt(console.as_ref()) {
+			action(&mut location, &mut data);
 		} else {
 			break 'program_loop;
 		}
 	}
+	ExitCode::SUCCESS
+}
+
+fn switch_dataset(location: &mut DataLocation, data: &mut ApplicationData) {
+	let console = data.console.clone();
+	match location.profiles() {
+		Ok(profiles) if profiles.is_empty() => say!(console, "There are no profiles yet."),
+		Ok(profiles) => say!(console, "Profiles: {}", profiles.join(", ")),
+		Err(error) => say!(console, "Could not list the profiles: {}", error)
+	}
+	let Some(reply) = try_prompt_question(
+		console.as_ref(),
+		"Which profile do you want to use? (leave empty for the default dataset, a new name \
+		 creates an empty one)"
+	) else {
+		return;
+	};
+	let switched = location
+		.with_profile(Some(reply).filter(|name| !name.is_empty()))
+		.and_then(|switched| Ok((switched.open()?, switched)));
+	match switched {
+		Ok((opened, switched)) => {
+			*data = opened.with_console(console.clone());
+			*location = switched;
+			say!(console, "Switched to {}.", location);
+		},
+		Err(error) => say!(console, "{}", error)
+	}
+}
+
+fn undo_change(data: &ApplicationData) {
+	let console = data.console.as_ref();
+	match audit::undo(data) {
+		Ok(event) => say!(console, "Undone:\n{}", event),
+		Err(audit::UndoError::Empty) => say!(console, "There is nothing to undo."),
+		Err(error) => say!(console, "{}", error)
+	}
+}
+fn redo_change(data: &ApplicationData) {
+	let console = data.console.as_ref();
+	match audit::redo(data) {
+		Ok(event) => say!(console, "Redone:\n{}", event),
+		Err(audit::UndoError::Empty) => say!(console, "There is nothing to redo."),
+		Err(error) => say!(console, "{}", error)
+	}
 }
 
 fn query_database(data: &ApplicationData) {
+	let console = data.console.as_ref();
 	lazy_static! {
 		static ref DECISION: Decision<PathDataFn> = Decision {
 			prompt: "What do you want to use the database for?".into(),
@@ -67,11 +132,12 @@ fn query_database(data: &ApplicationData) {
 			..Default::default()
 		};
 	}
-	if let Some(action) = DECISION.run_prompt() {
+	if let Some(action) = DECISION.run_prompt(console) {
 		action(data);
 	}
 }
 fn print_processing_decision(data: &ApplicationData) {
+	let console = data.console.as_ref();
 	lazy_static! {
 		static ref DECISION: Decision<PathDataFn> = Decision {
 			prompt: "How much processing information do you want to print out?".into(),
@@ -91,44 +157,109 @@ fn print_processing_decision(data: &ApplicationData) {
 					print_processing_individual as PathDataFn
 				)
 					.into(),
+				(
+					("F", "Orders from an order file").into(),
+					print_processing_order_file as PathDataFn
+				)
+					.into(),
 			],
 			..Default::default()
 		};
 	}
-	if let Some(action) = DECISION.run_prompt() {
+	if let Some(action) = DECISION.run_prompt(console) {
 		action(data);
 	}
 }
-fn try_modify_purchase_title(mut purchase: Purchase) -> Option<Purchase> {
+fn try_modify_purchase_title(console: &dyn Console, mut purchase: Purchase) -> Option<Purchase> {
 	purchase.title = Arc::from(try_prompt_question(
+		console,
 		"What would you like the new title to be?"
 	)?);
 	Some(purchase)
 }
-fn try_modify_purchase_identifiers(mut purchase: Purchase) -> Option<Purchase> {
-	purchase.identifiers = try_modify_identifiercollection(purchase.identifiers)?;
+fn try_modify_purchase_identifiers(
+	console: &dyn Console,
+	mut purchase: Purchase
+) -> Option<Purchase> {
+	purchase.identifiers = try_modify_identifiercollection(console, purchase.identifiers)?;
 	Some(purchase)
 }
-fn try_modify_rule_title(mut rule: Rule) -> Option<Rule> {
+fn try_modify_purchase_attributes(
+	console: &dyn Console,
+	mut purchase: Purchase
+) -> Option<Purchase> {
+	let reply = try_prompt_question(
+		console,
+		"Which attributes do you want to set (key=value, separated by semicolon)? Leave out the \
+		 value to remove an attribute, as in 'price='."
+	)?;
+	for pair in reply.split(';').filter(|pair| !pair.trim().is_empty()) {
+		match parse_attribute(pair) {
+			Ok((key, AttributeValue::Text(text))) if text.is_empty() => {
+				if purchase.attributes.0.remove(&key).is_none() {
+					say!(
+						console,
+						"'{}' is not an attribute of this entry, skipping removal...",
+						key
+					);
+				}
+			},
+			Ok((key, value)) => {
+				purchase.attributes.0.insert(key, value);
+			},
+			Err(message) => say!(console, "{}, skipping...", message)
+		}
+	}
+	Some(purchase)
+}
+fn try_modify_purchase_time(console: &dyn Console, mut purchase: Purchase) -> Option<Purchase> {
+	purchase.purchased_at = try_prompt_optional(
+		console,
+		"When was the purchase made, as YYYY-MM-DD or like 2024-05-01T14:30:00Z? (leave empty if \
+		 unknown)",
+		parse_time
+	)?;
+	Some(purchase)
+}
+fn try_modify_rule_title(console: &dyn Console, mut rule: Rule) -> Option<Rule> {
 	rule.title = Arc::from(try_prompt_question(
+		console,
 		"What would you like the new title to be?"
 	)?);
 	Some(rule)
 }
-fn try_modify_rule_process_action(mut rule: Rule) -> Option<Rule> {
-	rule.title = Arc::from(try_prompt_question(
-		"What would you like the new process action to be?"
-	)?);
+fn try_modify_rule_process_action(console: &dyn Console, mut rule: Rule) -> Option<Rule> {
+	rule.process_action = ProcessAction::from(
+		try_prompt_question(
+			console,
+			format!(
+				"What would you like the new process action to be?\n({})",
+				ProcessAction::SYNTAX_HELP
+			)
+		)?
+		.as_str()
+	);
+	Some(rule)
+}
+fn try_modify_rule_trigger(console: &dyn Console, mut rule: Rule) -> Option<Rule> {
+	rule.trigger = RuleTrigger::try_prompt_creation(console)?;
 	Some(rule)
 }
-fn try_modify_rule_trigger(mut rule: Rule) -> Option<Rule> {
-	rule.trigger = RuleTrigger::try_prompt_creation()?;
+fn try_modify_rule_scope(console: &dyn Console, mut rule: Rule) -> Option<Rule> {
+	rule.scope = prompt_rule_scope(console);
 	Some(rule)
 }
+fn try_modify_rule_processing_order(console: &dyn Console, rule: Rule) -> Option<Rule> {
+	try_prompt_processing_order(console, rule)
+}
+fn try_modify_rule_validity(console: &dyn Console, rule: Rule) -> Option<Rule> {
+	try_prompt_validity(console, rule)
+}
 fn try_modify_identifiercollection(
+	console: &dyn Console,
 	mut all_identifiers: IdentifierCollection
 ) -> Option<IdentifierCollection> {
-	type FnType = fn(IdentifierCollection, String) -> IdentifierCollection;
+	type FnType = fn(&dyn Console, IdentifierCollection, String) -> IdentifierCollection;
 	lazy_static! {
 		static ref DECISION: Decision<FnType> = Decision {
 			prompt: "What about do you want to change about the identifiers?".into(),
@@ -143,24 +274,27 @@ fn try_modify_identifiercollection(
 			..Default::default()
 		};
 	}
-	if let Some(modifying_fn) = DECISION.run_prompt() {
-		let identifier_reply = try_prompt_question("Which identifiers (separated by semicolon)?")?;
-		all_identifiers = modifying_fn(all_identifiers, identifier_reply);
+	if let Some(modifying_fn) = DECISION.run_prompt(console) {
+		let identifier_reply =
+			try_prompt_question(console, "Which identifiers (separated by semicolon)?")?;
+		all_identifiers = modifying_fn(console, all_identifiers, identifier_reply);
 		Some(all_identifiers)
 	} else {
 		None
 	}
 }
 fn add_from_str(
+	console: &dyn Console,
 	mut all_identifiers: IdentifierCollection,
 	s: impl AsRef<str>
 ) -> IdentifierCollection {
 	let identifiers_to_add = s.as_ref().split(';').map(str::trim).collect::<Vec<_>>();
 	for identifier in identifiers_to_add {
 		if identifier.is_empty() {
-			println!("'' is an empty identifier and is skipped.");
+			say!(console, "'' is an empty identifier and is skipped.");
 		} else if !all_identifiers.0.insert(identifier.into()) {
-			println!(
+			say!(
+				console,
 				"'{}' is already an identifier for this entry, skipping addition...",
 				identifier
 			);
@@ -169,6 +303,7 @@ fn add_from_str(
 	all_identifiers
 }
 fn remove_from_str(
+	console: &dyn Console,
 	mut all_identifiers: IdentifierCollection,
 	s: impl AsRef<str>
 ) -> IdentifierCollection {
@@ -179,7 +314,8 @@ fn remove_from_str(
 		.collect::<Vec<Identifier>>();
 	for identifier in identifiers_for_removal {
 		if !all_identifiers.0.remove(&identifier) {
-			println!(
+			say!(
+				console,
 				"'{}' is not an identifier for this entry, skipping removal...",
 				identifier.0
 			);
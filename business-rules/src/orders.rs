@@ -0,0 +1,282 @@
+//! Order files, holding many orders with one line per purchase in an order.
+//!
+//! Every line has the columns `order` (a reference shared by the lines of the same order),
+//! `purchase` (the title or ID of the purchase) and `quantity` (1 when left out). Files ending in
+//! `.csv` are CSV with a header, anything else has one JSON object per line, for example
+//! `{"order": "A-1", "purchase": "Learning to Ski", "quantity": 2}`.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs::{self, OpenOptions},
+	io::{self, Write},
+	path::Path
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+	searching::Searchable,
+	transfer::RowReport,
+	types::{Order, Purchase, PurchaseCollection}
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OrderFileFormat {
+	Csv,
+	JsonLines
+}
+impl OrderFileFormat {
+	pub fn of_path(path: &Path) -> Self {
+		match path.extension().and_then(|extension| extension.to_str()) {
+			Some(extension) if extension.eq_ignore_ascii_case("csv") => OrderFileFormat::Csv,
+			_ => OrderFileFormat::JsonLines
+		}
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OrderFileLine {
+	pub order:    String,
+	/// Title or ID of the purchase.
+	pub purchase: String,
+	#[serde(default)]
+	pub quantity: Option<usize>
+}
+
+/// An order read from an order file, processed only when none of its lines had errors.
+#[derive(Debug)]
+pub struct BatchOrder {
+	pub reference: String,
+	pub order:     Order,
+	pub errors:    Vec<RowReport>
+}
+
+/// Every order in an order file, in the order they first appear in.
+#[derive(Debug, Default)]
+pub struct OrderBatch {
+	pub orders: Vec<BatchOrder>,
+	/// Lines that couldn't be read, so they don't belong to any order.
+	pub errors: Vec<RowReport>
+}
+impl OrderBatch {
+	pub fn read(
+		contents: &str,
+		format: OrderFileFormat,
+		purchases: &BTreeSet<Purchase>
+	) -> io::Result<Self> {
+		let mut batch = OrderBatch::default();
+		for (row, line) in read_lines(contents, format)? {
+			let line = match line {
+				Ok(line) => line,
+				Err(message) => {
+					batch.errors.push(RowReport { row, message });
+					continue;
+				}
+			};
+			let index = match batch
+				.orders
+				.iter()
+				.position(|order| order.reference == line.order)
+			{
+				Some(index) => index,
+				None => {
+					batch.orders.push(BatchOrder {
+						reference: line.order.clone(),
+						order:     Order {
+							purchases: PurchaseCollection(BTreeMap::new())
+						},
+						errors:    Vec::new()
+					});
+					batch.orders.len() - 1
+				}
+			};
+			let order = &mut batch.orders[index];
+			match (find_purchase(&line.purchase, purchases), line.quantity) {
+				(_, Some(0)) => order.errors.push(RowReport {
+					row,
+					message: "the quantity has to be at least 1".to_string()
+				}),
+				(Ok(purchase), quantity) => {
+					*order.order.purchases.0.entry(purchase.clone()).or_default() +=
+						quantity.unwrap_or(1);
+				},
+				(Err(message), _) => order.errors.push(RowReport { row, message })
+			}
+		}
+		Ok(batch)
+	}
+}
+fn read_lines(
+	contents: &str,
+	format: OrderFileFormat
+) -> io::Result<Vec<(usize, Result<OrderFileLine, String>)>> {
+	match format {
+		OrderFileFormat::Csv => {
+			let mut reader = csv::ReaderBuilder::new()
+				.trim(csv::Trim::All)
+				.from_reader(contents.as_bytes());
+			let headers = reader.headers().map_err(io::Error::other)?.clone();
+			Ok(reader
+				.records()
+				.map(|record| {
+					let row = match &record {
+						Ok(record) => record.position(),
+						Err(error) => error.position()
+					}
+					.map_or(0, |position| position.line() as usize);
+					let line = record
+						.map_err(|error| error.to_string())
+						.and_then(|record| {
+							record
+								.deserialize(Some(&headers))
+								.map_err(|error| error.to_string())
+						});
+					(row, line)
+				})
+				.collect())
+		},
+		OrderFileFormat::JsonLines => Ok(contents
+			.lines()
+			.enumerate()
+			.filter(|(_, line)| !line.trim().is_empty())
+			.map(|(index, line)| {
+				(
+					index + 1,
+					serde_json::from_str(line).map_err(|error| error.to_string())
+				)
+			})
+			.collect())
+	}
+}
+/// By title or by ID, which has to be unambiguous. Titles such as "Cafe" can also be the start
+/// of an ID, which is a conflict when they are the title of another purchase.
+pub fn find_purchase<'a>(
+	title_or_id: &str,
+	purchases: &'a BTreeSet<Purchase>
+) -> Result<&'a Purchase, String> {
+	let by_title = Purchase::find_all_by_title(title_or_id, purchases.iter());
+	let by_id = Purchase::find_all_by_id(title_or_id, purchases.iter());
+	match (&by_title[..], &by_id[..]) {
+		([single], []) | ([], [single]) => Ok(single),
+		([single], [other]) if single == other => Ok(single),
+		([], []) => Err(format!("no purchase has the title or ID '{}'", title_or_id)),
+		([], several) => Err(format!(
+			"{} purchases have an ID starting with '{}', give more of the ID",
+			several.len(),
+			title_or_id
+		)),
+		([_], _) => Err(format!(
+			"'{}' is the title of one purchase and the start of the ID of another, use the whole \
+			 ID instead",
+			title_or_id
+		)),
+		(several, _) => Err(format!(
+			"{} purchases have the title '{}', use the ID instead",
+			several.len(),
+			title_or_id
+		))
+	}
+}
+
+/// Adds the order to the end of an order file, referring to purchases by ID.
+pub fn append_order(path: &Path, reference: &str, order: &Order) -> io::Result<()> {
+	let format = OrderFileFormat::of_path(path);
+	let is_new = fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+	let mut contents = String::new();
+	for (purchase, quantity) in &order.purchases.0 {
+		let line = OrderFileLine {
+			order:    reference.to_string(),
+			purchase: purchase.id.to_string(),
+			quantity: Some(*quantity)
+		};
+		contents += &match format {
+			OrderFileFormat::Csv => {
+				let mut writer = csv::WriterBuilder::new()
+					.has_headers(is_new && contents.is_empty())
+					.from_writer(Vec::new());
+				writer.serialize(&line).map_err(io::Error::other)?;
+				String::from_utf8(writer.into_inner().map_err(io::Error::other)?)
+					.map_err(io::Error::other)?
+			},
+			OrderFileFormat::JsonLines => serde_json::to_string(&line)? + "\n"
+		};
+	}
+	let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+	file.write_all(contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{self, purchase, TempDir};
+
+	#[test]
+	fn reads_orders_per_reference() {
+		let (book, video) = (purchase("1984", &[]), purchase("Learning to Ski", &[]));
+		let purchases = BTreeSet::from([
+			book.clone(),
+			video.clone(),
+			purchase("Ski Wax", &[]),
+			purchase("Ski Wax", &["physical"])
+		]);
+		let contents = format!(
+			"{{\"order\": \"A\", \"purchase\": \"1984\", \"quantity\": 2}}\n{{\"order\": \"B\", \
+			 \"purchase\": \"{}\"}}\n\n{{\"order\": \"A\", \"purchase\": \"learning to ski\"}}\n{{\"order\": \
+			 \"B\", \"purchase\": \"Ski Wax\"}}\nnot json\n",
+			video.id
+		);
+		let batch = OrderBatch::read(&contents, OrderFileFormat::JsonLines, &purchases).unwrap();
+		assert_eq!(
+			batch
+				.errors
+				.iter()
+				.map(|error| error.row)
+				.collect::<Vec<_>>(),
+			[6]
+		);
+		let [first, second] = &batch.orders[..] else {
+			panic!("expected two orders");
+		};
+		assert_eq!(first.reference, "A");
+		assert!(first.errors.is_empty());
+		assert_eq!(
+			first.order.purchases.0,
+			BTreeMap::from([(book, 2), (video.clone(), 1)])
+		);
+		assert_eq!(second.order.purchases.0, BTreeMap::from([(video, 1)]));
+		assert_eq!(second.errors[0].row, 5);
+
+		let directory = TempDir::new("orders");
+		let path = directory.join("orders.csv");
+		append_order(&path, "A", &first.order).unwrap();
+		append_order(&path, "C", &first.order).unwrap();
+		let written = fs::read_to_string(&path).unwrap();
+		let reread = OrderBatch::read(&written, OrderFileFormat::Csv, &purchases).unwrap();
+		assert_eq!(written.lines().count(), 5);
+		assert_eq!(reread.orders.len(), 2);
+		assert_eq!(reread.orders[1].order.purchases.0, first.order.purchases.0);
+	}
+
+	#[test]
+	fn finds_titles_that_look_like_ids() {
+		let purchase = |title: &str, id: &str| Purchase {
+			id: id.parse().unwrap(),
+			..testing::purchase(title, &[])
+		};
+		let cafe = purchase("Cafe", "0c0f0000-0000-4000-8000-000000000000");
+		let face = purchase("Face", "face0000-0000-4000-8000-000000000000");
+		let other = purchase("Learning to Ski", "cafe0000-0000-4000-8000-000000000000");
+		let purchases = BTreeSet::from([cafe.clone(), face.clone(), other.clone()]);
+		assert_eq!(
+			find_purchase("1984", &purchases).unwrap_err(),
+			"no purchase has the title or ID '1984'"
+		);
+		assert_eq!(find_purchase("Face", &purchases), Ok(&face));
+		assert_eq!(find_purchase("0c0f", &purchases), Ok(&cafe));
+		assert_eq!(find_purchase("cafe0", &purchases), Ok(&other));
+		assert!(find_purchase("Cafe", &purchases)
+			.unwrap_err()
+			.contains("use the whole ID"));
+		assert_eq!(find_purchase(&other.id.to_string(), &purchases), Ok(&other));
+	}
+}
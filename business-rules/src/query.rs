@@ -0,0 +1,308 @@
+//! Search queries over purchases and rules, for example: `title:ski ident:video -ident:digital`
+//!
+//! Terms are separated by spaces and every term has to match. Plain text is matched against the
+//! title, `field:text` against one of the fields `title`, `ident`, `action` or `id`, and a
+//! leading `-` leaves out whatever the term matches. Quotes keep spaces inside a term, as in
+//! `title:"first aid"`. Text is matched fuzzily, so typos and words in another order still
+//! match, with closer matches scoring higher.
+
+use std::{fmt::Display, str::FromStr};
+
+use super::{expression::ParseError, types::EntryId};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum QueryField {
+	Title,
+	/// Identifiers of a purchase, or the identifiers a rule's trigger checks.
+	Identifier,
+	/// Description of a rule's action.
+	Action,
+	/// Any unique start of the entry ID, see `EntryId::matches`.
+	Id
+}
+impl QueryField {
+	fn from_name(name: &str) -> Option<Self> {
+		match name.to_lowercase().as_str() {
+			"title" => Some(QueryField::Title),
+			"ident" | "identifier" => Some(QueryField::Identifier),
+			"action" => Some(QueryField::Action),
+			"id" => Some(QueryField::Id),
+			_ => None
+		}
+	}
+}
+impl Display for QueryField {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			QueryField::Title => write!(f, "title"),
+			QueryField::Identifier => write!(f, "ident"),
+			QueryField::Action => write!(f, "action"),
+			QueryField::Id => write!(f, "id")
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QueryTerm {
+	pub field:   QueryField,
+	pub text:    String,
+	/// Entries matching the term are left out.
+	pub negated: bool
+}
+impl QueryTerm {
+	/// The best score of the text against any of the values.
+	fn score(&self, values: &[String]) -> Option<f64> {
+		values
+			.iter()
+			.filter_map(|value| match self.field {
+				QueryField::Id => {
+					let search = self.text.to_lowercase();
+					(search.len() >= EntryId::MIN_PREFIX && value.starts_with(&search))
+						.then_some(1.0)
+				},
+				_ => fuzzy_score(&self.text, value)
+			})
+			.reduce(f64::max)
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Query(pub Vec<QueryTerm>);
+impl Query {
+	/// Scores an entry from 0 to 1 by how well it matches, `None` if it doesn't.
+	/// `values` gives the entry's values for a field.
+	pub fn score(&self, values: impl Fn(QueryField) -> Vec<String>) -> Option<f64> {
+		let mut total = 0.0;
+		let mut counted = 0;
+		for term in &self.0 {
+			let score = term.score(&values(term.field));
+			match (term.negated, score) {
+				(true, Some(_)) | (false, None) => return None,
+				(true, None) => {},
+				(false, Some(score)) => {
+					total += score;
+					counted += 1;
+				}
+			}
+		}
+		// only leaving out entries matches everything else equally well
+		Some(if counted == 0 {
+			1.0
+		} else {
+			total / counted as f64
+		})
+	}
+}
+impl FromStr for Query {
+	type Err = ParseError;
+
+	fn from_str(source: &str) -> Result<Self, Self::Err> {
+		let mut terms = Vec::new();
+		let mut chars = source.char_indices().peekable();
+		loop {
+			while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+			let Some(&(start, _)) = chars.peek() else {
+				break;
+			};
+			let column = source[..start].chars().count() + 1;
+			let negated = chars.next_if(|(_, c)| *c == '-').is_some();
+			let mut name = None;
+			let mut text = String::new();
+			let mut quoted = false;
+			while let Some((_, c)) = chars.next_if(|(_, c)| quoted || !c.is_whitespace()) {
+				match c {
+					'"' => quoted = !quoted,
+					':' if !quoted && name.is_none() => name = Some(std::mem::take(&mut text)),
+					c => text.push(c)
+				}
+			}
+			if quoted {
+				return Err(ParseError {
+					column,
+					message: "unclosed quote".to_string()
+				});
+			}
+			let field = match name {
+				None => QueryField::Title,
+				Some(name) => QueryField::from_name(&name).ok_or_else(|| ParseError {
+					column,
+					message: format!(
+						"unknown field '{}', expected 'title', 'ident', 'action' or 'id'",
+						name
+					)
+				})?
+			};
+			let text = text.trim().to_string();
+			if text.is_empty() {
+				return Err(ParseError {
+					column,
+					message: "expected text to search for".to_string()
+				});
+			}
+			terms.push(QueryTerm {
+				field,
+				text,
+				negated
+			});
+		}
+		if terms.is_empty() {
+			return Err(ParseError {
+				column:  1,
+				message: "expected something to search for".to_string()
+			});
+		}
+		Ok(Query(terms))
+	}
+}
+impl Display for Query {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let terms = self
+			.0
+			.iter()
+			.map(|term| {
+				let text = if term.text.contains(char::is_whitespace) {
+					format!("\"{}\"", term.text)
+				} else {
+					term.text.clone()
+				};
+				format!(
+					"{}{}:{}",
+					if term.negated { "-" } else { "" },
+					term.field,
+					text
+				)
+			})
+			.collect::<Vec<_>>();
+		write!(f, "{}", terms.join(" "))
+	}
+}
+
+/// Words of a search that are less similar than this to every word of the text don't match.
+const MIN_WORD_SIMILARITY: f64 = 0.6;
+
+/// Scores how well `search` matches `text` from 0 to 1, ignoring case.
+/// Text containing the search scores at least 0.8, more the more of the text it covers. Other
+/// text matches when every word of the search is similar to one of its words, scoring up to
+/// 0.75.
+pub fn fuzzy_score(search: &str, text: &str) -> Option<f64> {
+	let search = search.trim().to_lowercase();
+	let text = text.trim().to_lowercase();
+	if search.is_empty() {
+		return None;
+	}
+	if text.contains(&search) {
+		let coverage = search.chars().count() as f64 / text.chars().count() as f64;
+		return Some(0.8 + 0.2 * coverage);
+	}
+	let text_words = words(&text);
+	let search_words = words(&search);
+	if search_words.is_empty() {
+		return None;
+	}
+	let mut total = 0.0;
+	for search_word in &search_words {
+		let best = text_words
+			.iter()
+			.map(|text_word| word_similarity(search_word, text_word))
+			.fold(0.0, f64::max);
+		if best < MIN_WORD_SIMILARITY {
+			return None;
+		}
+		total += best;
+	}
+	Some(0.75 * total / search_words.len() as f64)
+}
+fn words(text: &str) -> Vec<&str> {
+	text.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.collect()
+}
+/// 1 for equal words, 0.9 when the text word starts with the searched one, otherwise based on
+/// the edit distance between them.
+fn word_similarity(search: &str, text: &str) -> f64 {
+	if search == text {
+		1.0
+	} else if text.starts_with(search) {
+		0.9
+	} else {
+		let longest = search.chars().count().max(text.chars().count());
+		1.0 - edit_distance(search, text) as f64 / longest as f64
+	}
+}
+/// Levenshtein distance: the number of single character insertions, deletions and
+/// substitutions needed to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut previous = (0..=b.len()).collect::<Vec<_>>();
+	for (i, a_char) in a.chars().enumerate() {
+		let mut current = vec![i + 1];
+		for (j, b_char) in b.iter().enumerate() {
+			let substitution = previous[j] + usize::from(a_char != *b_char);
+			current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+		}
+		previous = current;
+	}
+	previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{searching::Searchable, testing::purchase, types::Purchase};
+
+	#[test]
+	fn parses_queries() {
+		let query: Query = "ski -ident:digital title:\"first aid\"".parse().unwrap();
+		assert_eq!(
+			query.0,
+			[
+				QueryTerm {
+					field:   QueryField::Title,
+					text:    "ski".into(),
+					negated: false
+				},
+				QueryTerm {
+					field:   QueryField::Identifier,
+					text:    "digital".into(),
+					negated: true
+				},
+				QueryTerm {
+					field:   QueryField::Title,
+					text:    "first aid".into(),
+					negated: false
+				}
+			]
+		);
+		assert_eq!(query.to_string().parse::<Query>().unwrap(), query);
+		assert_eq!("  ".parse::<Query>().unwrap_err().column, 1);
+		assert_eq!("ski colour:red".parse::<Query>().unwrap_err().column, 5);
+		assert_eq!("title:\"ski".parse::<Query>().unwrap_err().column, 1);
+	}
+
+	#[test]
+	fn scores_fuzzy_matches() {
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+		assert_eq!(fuzzy_score("learning to ski", "Learning to Ski"), Some(1.0));
+		let substring = fuzzy_score("ski", "Learning to Ski").unwrap();
+		let typo = fuzzy_score("lerning skii", "Learning to Ski").unwrap();
+		let reordered = fuzzy_score("ski learning", "Learning to Ski").unwrap();
+		assert!(substring > reordered && reordered > typo && typo > 0.0);
+		assert_eq!(fuzzy_score("gym", "Learning to Ski"), None);
+	}
+
+	#[test]
+	fn searches_purchases() {
+		let purchases = [
+			purchase("Learning to Ski", &["video", "physical"]),
+			purchase("Learning to Ski", &["video", "digital"]),
+			purchase("Skiing for Beginners", &["video"]),
+			purchase("Ski Wax", &["physical"])
+		];
+		let query = "ski ident:vidoe -ident:digital".parse().unwrap();
+		let found = Purchase::search(&query, purchases.iter())
+			.into_iter()
+			.map(|scored| scored.entry)
+			.collect::<Vec<_>>();
+		assert_eq!(found, [&purchases[0], &purchases[2]]);
+	}
+}
@@ -0,0 +1,161 @@
+use std::fmt::{Debug, Display};
+
+use serde::Serialize;
+
+use crate::{
+	query::{Query, QueryField},
+	types::{Identified, Named, Purchase, Rule}
+};
+
+/// An entry found by a query, with how well it matched from 0 to 1.
+#[derive(Debug, Serialize)]
+pub struct Scored<'a, T> {
+	pub score: f64,
+	pub entry: &'a T
+}
+impl<T: Named> Display for Scored<'_, T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:.2}\t{}", self.score, self.entry.name())
+	}
+}
+
+/// How entries matched a name, from best to worst.
+pub enum SearchMatches<T, I> {
+	MultipleExact(I),
+	SingleExact(T),
+	None,
+	SinglePartial(T),
+	MultiplePartial(I)
+}
+
+pub trait NameSearchable<'a, T>
+where
+	T: 'a
+{
+	fn filter_by_name<I>(value: &str, iter: I) -> SearchMatches<&'a T, Vec<&'a T>>
+	where
+		I: Iterator<Item = &'a T>;
+}
+impl<'a, T> NameSearchable<'a, T> for T
+where
+	T: 'a + Named
+{
+	fn filter_by_name<I>(name: &str, iter: I) -> SearchMatches<&'a T, Vec<&'a T>>
+	where
+		I: Iterator<Item = &'a T>
+	{
+		let data: Vec<&'a T> = iter.collect();
+		let mut exact_matches: Vec<&'a T> = data
+			.iter()
+			.copied()
+			.filter(|item| item.name() == name)
+			.collect();
+		match exact_matches.len() {
+			0 => {
+				drop(exact_matches);
+				let name_lowercase = name.to_lowercase();
+				let mut lower_matches: Vec<&'a T> = data
+					.iter()
+					.copied()
+					.filter(|item| item.name().to_lowercase() == name_lowercase)
+					.collect();
+				match lower_matches.len() {
+					0 => {
+						drop(lower_matches);
+						let mut partial_lower_matches: Vec<&'a T> = data
+							.into_iter()
+							.filter(|item| item.name().to_lowercase().contains(&name_lowercase))
+							.collect();
+						match partial_lower_matches.len() {
+							0 => SearchMatches::None,
+							1 => SearchMatches::SinglePartial(partial_lower_matches.remove(0)),
+							_ => SearchMatches::MultiplePartial(partial_lower_matches)
+						}
+					},
+					1 => SearchMatches::SingleExact(lower_matches.remove(0)),
+					_ => SearchMatches::MultipleExact(lower_matches)
+				}
+			},
+			1 => SearchMatches::SingleExact(exact_matches.remove(0)),
+			_ => SearchMatches::MultipleExact(exact_matches)
+		}
+	}
+}
+
+pub trait Searchable<'a>
+where
+	Self: 'a + Sized + Named + Identified + Debug + Display
+{
+	/// Every entry whose title matches exactly (falling back to case-insensitive), partial
+	/// matches are never returned.
+	fn find_all_by_title<I>(title: &str, data: I) -> Vec<&'a Self>
+	where
+		I: Iterator<Item = &'a Self>
+	{
+		match Self::filter_by_name(title, data) {
+			SearchMatches::MultipleExact(multiple) => multiple,
+			SearchMatches::SingleExact(single) => vec![single],
+			SearchMatches::None
+			| SearchMatches::SinglePartial(_)
+			| SearchMatches::MultiplePartial(_) => Vec::new()
+		}
+	}
+	/// Values of a field searched by queries.
+	fn query_values(&self, field: QueryField) -> Vec<String>;
+	/// Every entry matching the query, best matches first and equal scores by title.
+	fn search<I>(query: &Query, data: I) -> Vec<Scored<'a, Self>>
+	where
+		I: Iterator<Item = &'a Self>
+	{
+		let mut ranked = data
+			.filter_map(|entry| {
+				let score = query.score(|field| entry.query_values(field))?;
+				Some(Scored { score, entry })
+			})
+			.collect::<Vec<_>>();
+		ranked.sort_by(|a, b| {
+			b.score
+				.total_cmp(&a.score)
+				.then_with(|| a.entry.name().cmp(b.entry.name()))
+		});
+		ranked
+	}
+	/// Every entry whose ID is `id` or starts with it, see `EntryId::matches`.
+	fn find_all_by_id<I>(id: &str, data: I) -> Vec<&'a Self>
+	where
+		I: Iterator<Item = &'a Self>
+	{
+		data.filter(|entry| entry.id().matches(id)).collect()
+	}
+}
+
+impl<'a> Searchable<'a> for Purchase {
+	fn query_values(&self, field: QueryField) -> Vec<String> {
+		match field {
+			QueryField::Title => vec![self.title.to_string()],
+			QueryField::Identifier => self
+				.identifiers
+				.0
+				.iter()
+				.map(|identifier| identifier.0.to_string())
+				.collect(),
+			QueryField::Action => Vec::new(),
+			QueryField::Id => vec![self.id.to_string()]
+		}
+	}
+}
+impl<'a> Searchable<'a> for Rule {
+	fn query_values(&self, field: QueryField) -> Vec<String> {
+		match field {
+			QueryField::Title => vec![self.title.to_string()],
+			QueryField::Identifier => self
+				.trigger
+				.identifiers()
+				.into_iter()
+				.map(|identifier| identifier.0.to_string())
+				.collect(),
+			QueryField::Action => vec![self.process_action.description()],
+			QueryField::Id => vec![self.id.to_string()]
+		}
+	}
+}
@@ -0,0 +1,694 @@
+use std::{
+	any::type_name,
+	collections::BTreeSet,
+	ffi::OsString,
+	fs::{self, read_to_string, File, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	time::Duration
+};
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{from_str, to_string, to_string_pretty};
+
+use super::{
+	actions::stable_hash,
+	types::{EntryId, Identified, Identifier, Named, Purchase, Rule}
+};
+
+/// Data files with one of these extensions are SQLite databases, anything else is JSON.
+pub const DATABASE_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+/// Anything that can be kept in a storage backend.
+pub trait Stored: Named + Identified + Ord + Clone + Serialize + DeserializeOwned {
+	/// Table name in the SQLite backend.
+	const TABLE: &'static str;
+	/// The type's name without its module path, as in "Purchase".
+	fn type_name_pretty() -> &'static str {
+		let raw_name = type_name::<Self>();
+		if let Some(simplified_name) = raw_name.split("::").last() {
+			simplified_name
+		} else {
+			raw_name
+		}
+	}
+	/// Identifiers the SQLite backend indexes the entry by.
+	fn indexed_identifiers(&self) -> BTreeSet<&Identifier>;
+	/// Equal apart from the ID.
+	fn same_contents(&self, other: &Self) -> bool {
+		let mut renumbered = self.clone();
+		renumbered.set_id(other.id());
+		renumbered == *other
+	}
+}
+impl Stored for Purchase {
+	const TABLE: &'static str = "purchases";
+
+	fn indexed_identifiers(&self) -> BTreeSet<&Identifier> { self.identifiers.0.iter().collect() }
+}
+impl Stored for Rule {
+	const TABLE: &'static str = "rules";
+
+	fn indexed_identifiers(&self) -> BTreeSet<&Identifier> { self.trigger.identifiers() }
+}
+
+/// A change to a single entry, which backends apply without touching any other entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<T> {
+	Insert(T),
+	Replace { old: T, new: T },
+	Remove(T)
+}
+impl<T: Stored> Change<T> {
+	/// The entry as it was before the change, `None` when it was inserted.
+	pub fn before(&self) -> Option<&T> {
+		match self {
+			Change::Insert(_) => None,
+			Change::Replace { old, .. } | Change::Remove(old) => Some(old)
+		}
+	}
+	/// The entry as it is after the change, `None` when it was removed.
+	pub fn after(&self) -> Option<&T> {
+		match self {
+			Change::Insert(new) | Change::Replace { new, .. } => Some(new),
+			Change::Remove(_) => None
+		}
+	}
+	/// Whether the change applies but would store its entry under the ID of another entry.
+	pub fn takes_id(&self, set: &BTreeSet<T>) -> bool {
+		match self {
+			Change::Insert(new) => {
+				!set.iter().any(|entry| entry.same_contents(new))
+					&& set.iter().any(|entry| entry.id() == new.id())
+			},
+			Change::Replace { old, new } => {
+				set.contains(old)
+					&& set
+						.iter()
+						.any(|entry| entry != old && entry.id() == new.id())
+			},
+			Change::Remove(_) => false
+		}
+	}
+	/// Returns `false` when the change doesn't apply: the inserted entry is already there, even
+	/// under another ID, or the replaced or removed entry is gone.
+	pub fn apply_to(&self, set: &mut BTreeSet<T>) -> bool {
+		match self {
+			Change::Insert(new) => {
+				!set.iter().any(|entry| entry.same_contents(new)) && set.insert(new.clone())
+			},
+			Change::Replace { old, new } => {
+				if !set.remove(old) {
+					return false;
+				}
+				set.insert(new.clone());
+				true
+			},
+			Change::Remove(old) => set.remove(old)
+		}
+	}
+}
+
+/// Held while data is being changed, released when dropped.
+pub struct StorageLock {
+	_file: Option<File>
+}
+
+/// Gives an entry saved before IDs existed the ID derived from its contents.
+fn with_id<T: Stored>(mut entry: T) -> T {
+	if entry.id().is_missing() {
+		entry.set_id(EntryId::derived_from(&serialize(&entry)));
+	}
+	entry
+}
+fn with_ids<T: Stored>(set: BTreeSet<T>) -> BTreeSet<T> { set.into_iter().map(with_id).collect() }
+/// The error for storing `entry` when another entry already has its ID.
+fn id_taken<T: Stored>(entry: &T) -> io::Error {
+	io::Error::new(
+		io::ErrorKind::AlreadyExists,
+		format!(
+			"another {} already has the ID {}",
+			T::type_name_pretty(),
+			entry.id()
+		)
+	)
+}
+
+pub trait Storage<T: Stored> {
+	/// Makes other instances wait until the returned lock is dropped before changing the data.
+	/// Loading and saving don't lock by themselves.
+	fn lock(&self) -> io::Result<StorageLock> { Ok(StorageLock { _file: None }) }
+	/// Differs whenever the stored data has changed.
+	fn version(&self) -> io::Result<u64>;
+	/// Entries without an ID are given the one derived from their contents, see `EntryId`.
+	fn load(&self) -> io::Result<BTreeSet<T>>;
+	/// Replaces everything that was stored with `set`.
+	fn save(&self, set: &BTreeSet<T>) -> io::Result<()>;
+	/// See `Change::apply_to` for what the result means. Fails with `AlreadyExists` when the
+	/// change would store its entry under the ID of another entry.
+	fn apply(&self, change: &Change<T>) -> io::Result<bool> {
+		let mut set = self.load()?;
+		if change.takes_id(&set) {
+			return Err(id_taken(
+				change.after().expect("only added entries take IDs")
+			));
+		}
+		if !change.apply_to(&mut set) {
+			return Ok(false);
+		}
+		self.save(&set)?;
+		Ok(true)
+	}
+}
+/// Picks the backend from the file extension of `path`.
+pub fn storage_for<'a, T: Stored>(path: &'a Path) -> Box<dyn Storage<T> + 'a> {
+	let is_database = path
+		.extension()
+		.and_then(|extension| extension.to_str())
+		.is_some_and(|extension| DATABASE_EXTENSIONS.contains(&extension));
+	if is_database {
+		Box::new(SqliteStorage { path })
+	} else {
+		Box::new(JsonStorage { path })
+	}
+}
+
+/// The whole set in a single JSON file, replaced on every change. The previous versions of the
+/// file are kept in a `backups` directory next to it.
+pub struct JsonStorage<'a> {
+	pub path: &'a Path
+}
+impl JsonStorage<'_> {
+	const KEPT_BACKUPS: usize = 10;
+
+	/// `path` with `prefix` and `suffix` added around its file name.
+	fn sibling(&self, prefix: &str, suffix: &str) -> PathBuf {
+		let mut name = OsString::from(prefix);
+		name.push(self.path.file_name().unwrap_or_default());
+		name.push(suffix);
+		self.path.with_file_name(name)
+	}
+	fn back_up(&self) -> io::Result<()> {
+		if fs::metadata(self.path).map_or(true, |metadata| metadata.len() == 0) {
+			return Ok(());
+		}
+		let directory = self.path.with_file_name("backups");
+		fs::create_dir_all(&directory)?;
+		let stem = self
+			.path
+			.file_stem()
+			.unwrap_or_default()
+			.to_string_lossy()
+			.to_string();
+		let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.9fZ");
+		fs::copy(
+			self.path,
+			directory.join(format!("{}.{}.json", stem, timestamp))
+		)?;
+		// timestamps sort chronologically, so the oldest backups come first
+		let mut backups = fs::read_dir(&directory)?
+			.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+			.filter(|backup| {
+				backup
+					.file_name()
+					.and_then(|name| name.to_str())
+					.is_some_and(|name| name.starts_with(&format!("{}.", stem)))
+			})
+			.collect::<Vec<_>>();
+		backups.sort();
+		let excess = backups.len().saturating_sub(Self::KEPT_BACKUPS);
+		for backup in &backups[..excess] {
+			fs::remove_file(backup)?;
+		}
+		Ok(())
+	}
+}
+impl<T: Stored> Storage<T> for JsonStorage<'_> {
+	fn lock(&self) -> io::Result<StorageLock> {
+		let file = OpenOptions::new()
+			.create(true)
+			.truncate(false)
+			.write(true)
+			.open(self.sibling("", ".lock"))?;
+		file.lock()?;
+		Ok(StorageLock { _file: Some(file) })
+	}
+	fn version(&self) -> io::Result<u64> {
+		match read_to_string(self.path) {
+			Ok(contents) => Ok(stable_hash(&[&contents])),
+			Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(0),
+			Err(error) => Err(error)
+		}
+	}
+	fn load(&self) -> io::Result<BTreeSet<T>> {
+		let data_string: String = read_to_string(self.path)?;
+		if data_string.is_empty() {
+			Ok(BTreeSet::new())
+		} else {
+			Ok(with_ids(from_str(data_string.as_str())?))
+		}
+	}
+	fn save(&self, set: &BTreeSet<T>) -> io::Result<()> {
+		let data_string = to_string_pretty(set).expect("should always be able to parse");
+		self.back_up()?;
+		// a crash while writing leaves the old file intact, the rename replaces it in one step
+		let temporary_path = self.sibling(".", ".tmp");
+		let mut temporary = File::create(&temporary_path)?;
+		temporary.write_all(data_string.as_bytes())?;
+		temporary.sync_all()?;
+		fs::rename(&temporary_path, self.path)
+	}
+}
+
+/// One row per entry, keyed by its entry ID, with every change made in its own transaction.
+pub struct SqliteStorage<'a> {
+	pub path: &'a Path
+}
+impl SqliteStorage<'_> {
+	fn connect<T: Stored>(&self) -> rusqlite::Result<Connection> {
+		let connection = Connection::open(self.path)?;
+		connection.busy_timeout(Duration::from_secs(5))?;
+		connection.execute_batch(&format!(
+			"PRAGMA foreign_keys = ON;
+			CREATE TABLE IF NOT EXISTS {table} (
+				id INTEGER PRIMARY KEY,
+				title TEXT NOT NULL,
+				data TEXT NOT NULL,
+				entry_id TEXT UNIQUE
+			);
+			CREATE INDEX IF NOT EXISTS {table}_title ON {table} (title);
+			CREATE TABLE IF NOT EXISTS {table}_identifiers (
+				entry_id INTEGER NOT NULL REFERENCES {table} (id) ON DELETE CASCADE,
+				identifier TEXT NOT NULL,
+				PRIMARY KEY (entry_id, identifier)
+			);
+			CREATE INDEX IF NOT EXISTS {table}_identifiers_identifier
+				ON {table}_identifiers (identifier);",
+			table = T::TABLE
+		))?;
+		Self::add_entry_ids::<T>(&connection)?;
+		Ok(connection)
+	}
+	/// Databases made before rows were keyed by entry ID get the column, filled with the IDs
+	/// loading gives their entries.
+	fn add_entry_ids<T: Stored>(connection: &Connection) -> rusqlite::Result<()> {
+		let has_column = connection
+			.prepare(&format!(
+				"SELECT 1 FROM pragma_table_info('{}') WHERE name = 'entry_id'",
+				T::TABLE
+			))?
+			.exists([])?;
+		if !has_column {
+			connection.execute_batch(&format!(
+				"ALTER TABLE {table} ADD COLUMN entry_id TEXT;
+				CREATE UNIQUE INDEX {table}_entry_id ON {table} (entry_id);",
+				table = T::TABLE
+			))?;
+		}
+		let mut statement = connection.prepare(&format!(
+			"SELECT id, data FROM {} WHERE entry_id IS NULL",
+			T::TABLE
+		))?;
+		let missing = statement
+			.query_map([], |row| {
+				Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+			})?
+			.collect::<rusqlite::Result<Vec<_>>>()?;
+		for (id, data) in missing {
+			let Ok(entry) = from_str::<T>(&data) else {
+				continue;
+			};
+			connection.execute(
+				&format!("UPDATE {} SET entry_id = ?1 WHERE id = ?2", T::TABLE),
+				params![with_id(entry).id().to_string(), id]
+			)?;
+		}
+		Ok(())
+	}
+	/// Returns `false` if the entry was already stored, even under another ID, and fails with
+	/// `AlreadyExists` if another entry has its ID.
+	fn insert<T: Stored>(connection: &Connection, entry: &T) -> io::Result<bool> {
+		let mut statement = connection
+			.prepare(&format!("SELECT data FROM {} WHERE title = ?1", T::TABLE))
+			.map_err(to_io)?;
+		let same_title = statement
+			.query_map(params![entry.name()], |row| row.get::<_, String>(0))
+			.map_err(to_io)?;
+		for data in same_title {
+			if from_str::<T>(&data.map_err(to_io)?).is_ok_and(|stored| stored.same_contents(entry))
+			{
+				return Ok(false);
+			}
+		}
+		let inserted = connection
+			.execute(
+				&format!(
+					"INSERT OR IGNORE INTO {} (title, data, entry_id) VALUES (?1, ?2, ?3)",
+					T::TABLE
+				),
+				params![entry.name(), serialize(entry), entry.id().to_string()]
+			)
+			.map_err(to_io)?;
+		if inserted == 0 {
+			return Err(id_taken(entry));
+		}
+		let id = connection.last_insert_rowid();
+		for identifier in entry.indexed_identifiers() {
+			connection
+				.execute(
+					&format!(
+						"INSERT INTO {}_identifiers (entry_id, identifier) VALUES (?1, ?2)",
+						T::TABLE
+					),
+					params![id, identifier.0.as_ref()]
+				)
+				.map_err(to_io)?;
+		}
+		Ok(true)
+	}
+	/// Returns `false` if no entry with its ID is stored or the stored one differs from it.
+	fn remove<T: Stored>(connection: &Connection, entry: &T) -> rusqlite::Result<bool> {
+		let entry_id = entry.id().to_string();
+		let stored = connection
+			.query_row(
+				&format!("SELECT data FROM {} WHERE entry_id = ?1", T::TABLE),
+				params![entry_id],
+				|row| row.get::<_, String>(0)
+			)
+			.optional()?;
+		let unchanged = stored
+			.and_then(|data| from_str::<T>(&data).ok())
+			.is_some_and(|stored| with_id(stored) == *entry);
+		if !unchanged {
+			return Ok(false);
+		}
+		let removed = connection.execute(
+			&format!("DELETE FROM {} WHERE entry_id = ?1", T::TABLE),
+			params![entry_id]
+		)?;
+		Ok(removed > 0)
+	}
+}
+impl<T: Stored> Storage<T> for SqliteStorage<'_> {
+	/// Row ids are reused once the newest row is deleted, so the stored entries are hashed too.
+	fn version(&self) -> io::Result<u64> {
+		let connection = self.connect::<T>().map_err(to_io)?;
+		let rows: String = connection
+			.query_row(
+				&format!(
+					"SELECT coalesce(group_concat(id || ' ' || data, char(10)), '') \
+					 FROM (SELECT id, data FROM {} ORDER BY id)",
+					T::TABLE
+				),
+				[],
+				|row| row.get(0)
+			)
+			.map_err(to_io)?;
+		Ok(stable_hash(&[&rows]))
+	}
+	fn load(&self) -> io::Result<BTreeSet<T>> {
+		let connection = self.connect::<T>().map_err(to_io)?;
+		let mut statement = connection
+			.prepare(&format!("SELECT data FROM {}", T::TABLE))
+			.map_err(to_io)?;
+		let rows = statement
+			.query_map([], |row| row.get::<_, String>(0))
+			.map_err(to_io)?;
+		let mut set = BTreeSet::new();
+		for row in rows {
+			set.insert(from_str(&row.map_err(to_io)?)?);
+		}
+		Ok(with_ids(set))
+	}
+	fn save(&self, set: &BTreeSet<T>) -> io::Result<()> {
+		let mut connection = self.connect::<T>().map_err(to_io)?;
+		let transaction = connection
+			.transaction_with_behavior(TransactionBehavior::Immediate)
+			.map_err(to_io)?;
+		transaction
+			.execute(&format!("DELETE FROM {}", T::TABLE), [])
+			.map_err(to_io)?;
+		for entry in set {
+			Self::insert(&transaction, entry)?;
+		}
+		transaction.commit().map_err(to_io)
+	}
+	fn apply(&self, change: &Change<T>) -> io::Result<bool> {
+		let mut connection = self.connect::<T>().map_err(to_io)?;
+		// immediate, so concurrent writers wait for each other instead of failing halfway
+		let transaction = connection
+			.transaction_with_behavior(TransactionBehavior::Immediate)
+			.map_err(to_io)?;
+		// a failed insert drops the transaction, which keeps a replaced entry
+		let applied = match change {
+			Change::Insert(new) => Self::insert(&transaction, new)?,
+			Change::Replace { old, new } => {
+				let removed = Self::remove(&transaction, old).map_err(to_io)?;
+				if removed {
+					Self::insert(&transaction, new)?;
+				}
+				removed
+			},
+			Change::Remove(old) => Self::remove(&transaction, old).map_err(to_io)?
+		};
+		if applied {
+			transaction.commit().map_err(to_io)?;
+		}
+		Ok(applied)
+	}
+}
+/// Whether the database at `path` already holds any entries of `T`.
+pub fn has_entries<T: Stored>(path: &Path) -> io::Result<bool> {
+	let storage = SqliteStorage { path };
+	let connection = storage.connect::<T>().map_err(to_io)?;
+	let found = connection
+		.query_row(&format!("SELECT 1 FROM {} LIMIT 1", T::TABLE), [], |_| {
+			Ok(())
+		})
+		.optional()
+		.map_err(to_io)?;
+	Ok(found.is_some())
+}
+fn serialize(entry: &impl Serialize) -> String {
+	to_string(entry).expect("should always be able to parse")
+}
+fn to_io(error: rusqlite::Error) -> io::Error { io::Error::other(error) }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		dataset::Saved,
+		testing::{purchase, TempDir},
+		types::EntryId
+	};
+
+	#[test]
+	fn backends_apply_changes_alike() {
+		let directory = TempDir::new("storage");
+		let json_path = directory.join("all_purchases.json");
+		let database_path = directory.join("business_rules.db");
+		fs::write(&json_path, "").unwrap();
+		let book = purchase("1984", &["book", "physical"]);
+		let video = purchase("Learning to Ski", &["video"]);
+		for storage in [
+			storage_for::<Purchase>(&json_path),
+			storage_for(&database_path)
+		] {
+			storage.save(&BTreeSet::from([book.clone()])).unwrap();
+			assert!(storage.apply(&Change::Insert(video.clone())).unwrap());
+			assert!(!storage.apply(&Change::Insert(video.clone())).unwrap());
+			let copy = Purchase {
+				id: EntryId::generate(),
+				..video.clone()
+			};
+			assert!(!storage.apply(&Change::Insert(copy)).unwrap());
+			let renamed = Purchase {
+				title: "Nineteen Eighty-Four".into(),
+				..book.clone()
+			};
+			let replace = Change::Replace {
+				old: book.clone(),
+				new: renamed.clone()
+			};
+			assert!(storage.apply(&replace).unwrap());
+			assert!(!storage.apply(&replace).unwrap());
+			assert!(!storage.apply(&Change::Remove(book.clone())).unwrap());
+			assert_eq!(
+				storage.load().unwrap(),
+				BTreeSet::from([renamed, video.clone()])
+			);
+		}
+		let connection = SqliteStorage {
+			path: &database_path
+		}
+		.connect::<Purchase>()
+		.unwrap();
+		let indexed_title: String = connection
+			.query_row(
+				"SELECT title FROM purchases JOIN purchases_identifiers \
+				 ON id = purchases_identifiers.entry_id WHERE identifier = 'book'",
+				[],
+				|row| row.get(0)
+			)
+			.unwrap();
+		assert_eq!(indexed_title, "Nineteen Eighty-Four");
+	}
+
+	#[test]
+	fn refuses_ids_of_other_entries() {
+		let directory = TempDir::new("id-clashes");
+		let json_path = directory.join("all_purchases.json");
+		let database_path = directory.join("business_rules.db");
+		let book = purchase("1984", &["book"]);
+		let video = purchase("Learning to Ski", &["video"]);
+		let clash = Purchase {
+			id: book.id,
+			..purchase("Ski Wax", &[])
+		};
+		for storage in [
+			storage_for::<Purchase>(&json_path),
+			storage_for(&database_path)
+		] {
+			let stored = BTreeSet::from([book.clone(), video.clone()]);
+			storage.save(&stored).unwrap();
+			let taken = storage.apply(&Change::Insert(clash.clone())).unwrap_err();
+			assert_eq!(taken.kind(), io::ErrorKind::AlreadyExists);
+			let replace = Change::Replace {
+				old: video.clone(),
+				new: clash.clone()
+			};
+			let taken = storage.apply(&replace).unwrap_err();
+			assert_eq!(taken.kind(), io::ErrorKind::AlreadyExists);
+			assert_eq!(storage.load().unwrap(), stored);
+			// the same contents under another ID are still a duplicate
+			let copy = Purchase {
+				id: video.id,
+				..book.clone()
+			};
+			assert!(!storage.apply(&Change::Insert(copy)).unwrap());
+		}
+	}
+
+	#[test]
+	fn versions_change_with_the_data() {
+		let directory = TempDir::new("versions");
+		let json_path = directory.join("all_purchases.json");
+		let database_path = directory.join("business_rules.db");
+		let book = purchase("1984", &["book"]);
+		for storage in [
+			storage_for::<Purchase>(&json_path),
+			storage_for(&database_path)
+		] {
+			storage.save(&BTreeSet::from([book.clone()])).unwrap();
+			let version = storage.version().unwrap();
+			assert_eq!(storage.version().unwrap(), version);
+			// the newest entry is replaced, which reuses its row in SQLite
+			let replace = Change::Replace {
+				old: book.clone(),
+				new: Purchase {
+					title: "Nineteen Eighty-Four".into(),
+					..book.clone()
+				}
+			};
+			assert!(storage.apply(&replace).unwrap());
+			assert_ne!(storage.version().unwrap(), version);
+		}
+	}
+
+	#[test]
+	fn old_databases_are_keyed_by_entry_id() {
+		let directory = TempDir::new("entry-ids");
+		let path = directory.join("business_rules.db");
+		Connection::open(&path)
+			.unwrap()
+			.execute_batch(
+				r#"CREATE TABLE purchases (
+					id INTEGER PRIMARY KEY,
+					title TEXT NOT NULL,
+					data TEXT NOT NULL UNIQUE
+				);
+				INSERT INTO purchases (title, data)
+					VALUES ('1984', '{"identifiers": ["book"], "title": "1984"}');"#
+			)
+			.unwrap();
+		let storage = storage_for::<Purchase>(&path);
+		let book = storage.load().unwrap().pop_first().unwrap();
+		assert!(!book.id.is_missing());
+		let renamed = Purchase {
+			title: "Nineteen Eighty-Four".into(),
+			..book.clone()
+		};
+		assert!(!storage.apply(&Change::Remove(renamed.clone())).unwrap());
+		let replace = Change::Replace {
+			old: book,
+			new: renamed.clone()
+		};
+		assert!(storage.apply(&replace).unwrap());
+		assert_eq!(storage.load().unwrap(), BTreeSet::from([renamed.clone()]));
+		assert!(storage.apply(&Change::Remove(renamed)).unwrap());
+		assert!(storage.load().unwrap().is_empty());
+	}
+
+	#[test]
+	fn json_saves_keep_backups() {
+		let directory = TempDir::new("backups");
+		let path = directory.join("all_purchases.json");
+		let storage = JsonStorage { path: &path };
+		let mut versions = BTreeSet::new();
+		for index in 0..JsonStorage::KEPT_BACKUPS + 3 {
+			let _lock = Storage::<Purchase>::lock(&storage).unwrap();
+			let set = BTreeSet::from([purchase(&index.to_string(), &[])]);
+			storage.save(&set).unwrap();
+			assert_eq!(storage.load().unwrap(), set);
+			versions.insert(Storage::<Purchase>::version(&storage).unwrap());
+		}
+		assert_eq!(versions.len(), JsonStorage::KEPT_BACKUPS + 3);
+		let backups = fs::read_dir(directory.join("backups")).unwrap().count();
+		assert_eq!(backups, JsonStorage::KEPT_BACKUPS);
+		assert!(!directory.join(".all_purchases.json.tmp").exists());
+	}
+
+	#[test]
+	fn loading_gives_missing_ids() {
+		let directory = TempDir::new("ids");
+		let path = directory.join("all_purchases.json");
+		fs::write(
+			&path,
+			r#"[{"title": "1984", "identifiers": ["book"]}, {"title": "1984", "identifiers": []}]"#
+		)
+		.unwrap();
+		let before = fs::read_to_string(&path).unwrap();
+		let loaded = Purchase::load_from_disk(&path).unwrap();
+		let ids = loaded.iter().map(|entry| entry.id).collect::<BTreeSet<_>>();
+		assert_eq!(ids.len(), 2);
+		assert!(ids.iter().all(|id| !id.is_missing()));
+		assert_eq!(Purchase::load_from_disk(&path).unwrap(), loaded);
+		assert_eq!(fs::read_to_string(&path).unwrap(), before);
+		assert!(!directory.join("backups").exists());
+
+		let book = loaded
+			.iter()
+			.find(|entry| !entry.identifiers.0.is_empty())
+			.unwrap();
+		let renamed = Purchase {
+			title: "Nineteen Eighty-Four".into(),
+			..book.clone()
+		};
+		let replace = Change::Replace {
+			old: book.clone(),
+			new: renamed
+		};
+		assert!(Purchase::apply_to_disk(&path, &replace).unwrap());
+		let saved = Purchase::load_from_disk(&path).unwrap();
+		assert_eq!(
+			saved.iter().map(|entry| entry.id).collect::<BTreeSet<_>>(),
+			ids
+		);
+		assert!(!fs::read_to_string(&path)
+			.unwrap()
+			.contains(&EntryId::default().to_string()));
+	}
+}
@@ -0,0 +1,57 @@
+//! Helpers shared by the tests of the library.
+
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+	process
+};
+
+use crate::{
+	actions::ProcessAction,
+	types::{AttributeMap, EntryId, Identifier, IdentifierCollection, Purchase, Rule, RuleScope}
+};
+
+/// A fresh directory, removed with everything in it when dropped.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+impl TempDir {
+	/// Tests run at the same time, so every test needs a name of its own.
+	pub fn new(name: &str) -> TempDir {
+		let path = env::temp_dir().join(format!("business-rules-{}-{}", name, process::id()));
+		let _ = fs::remove_dir_all(&path);
+		fs::create_dir_all(&path).expect("temporary directory can be created");
+		TempDir(path)
+	}
+	pub fn path(&self) -> &Path { &self.0 }
+	pub fn join(&self, path: impl AsRef<Path>) -> PathBuf { self.0.join(path) }
+}
+impl Drop for TempDir {
+	fn drop(&mut self) { let _ = fs::remove_dir_all(&self.0); }
+}
+
+/// A purchase without attributes or time of purchase, with a new ID.
+pub fn purchase(title: &str, identifiers: &[&str]) -> Purchase {
+	Purchase {
+		title:        title.into(),
+		identifiers:  IdentifierCollection(identifiers.iter().map(Identifier::from).collect()),
+		attributes:   AttributeMap::default(),
+		purchased_at: None,
+		id:           EntryId::generate()
+	}
+}
+
+/// A rule run for each purchase, with priority 0, no validity window and a new ID.
+pub fn rule(title: &str, action: &str, trigger: &str) -> Rule {
+	Rule {
+		title:           title.into(),
+		process_action:  ProcessAction::from(action),
+		trigger:         trigger.parse().expect("trigger is valid"),
+		scope:           RuleScope::Line,
+		priority:        0,
+		stop_processing: false,
+		exclusion_group: None,
+		valid_from:      None,
+		valid_until:     None,
+		id:              EntryId::generate()
+	}
+}
@@ -0,0 +1,463 @@
+//! Importing and exporting purchases and rules as CSV or YAML, one row per entry.
+//!
+//! Purchases have the columns `title`, `identifiers` (separated by `;`), `attributes` (`key=value`
+//! pairs separated by `;`), `purchased_at` and `id`, rules have `title`, `action`, `trigger` (as an
+//! expression), `scope`, `priority`, `stop`, `group`, `valid_from`, `valid_until` and `id`. Only
+//! `title` and, for rules, `action` and `trigger` are required.
+
+use std::{collections::BTreeSet, fmt::Display, io, path::Path, sync::Arc};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+	actions::ProcessAction,
+	dataset::Saved,
+	expression::ParseError,
+	storage::Change,
+	types::{
+		format_time, parse_date, parse_time, AttributeMap, EntryId, Identifier,
+		IdentifierCollection, Purchase, Rule, RuleScope
+	}
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+	Csv,
+	Yaml
+}
+impl Format {
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name.to_lowercase().as_str() {
+			"csv" => Some(Format::Csv),
+			"yaml" | "yml" => Some(Format::Yaml),
+			_ => None
+		}
+	}
+	/// Picks the format from the file extension.
+	pub fn of_path(path: &Path) -> Option<Self> { Self::from_name(path.extension()?.to_str()?) }
+}
+
+/// Entries that can be written as rows of a spreadsheet.
+pub trait Transferable: Saved + Display {
+	type Row: Serialize + DeserializeOwned;
+
+	fn to_row(&self) -> Self::Row;
+	/// The entry has the nil ID unless the row gave one.
+	fn from_row(row: Self::Row) -> Result<Self, String>;
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PurchaseRow {
+	pub title:        String,
+	#[serde(default)]
+	pub identifiers:  String,
+	#[serde(default)]
+	pub attributes:   String,
+	#[serde(default)]
+	pub purchased_at: Option<String>,
+	#[serde(default)]
+	pub id:           Option<String>
+}
+impl Transferable for Purchase {
+	type Row = PurchaseRow;
+
+	fn to_row(&self) -> Self::Row {
+		PurchaseRow {
+			title:        self.title.to_string(),
+			identifiers:  self
+				.identifiers
+				.0
+				.iter()
+				.map(|identifier| identifier.0.as_ref())
+				.collect::<Vec<_>>()
+				.join(";"),
+			attributes:   self.attributes.to_pairs(),
+			purchased_at: self.purchased_at.as_ref().map(format_time),
+			id:           Some(self.id.to_string())
+		}
+	}
+	fn from_row(row: Self::Row) -> Result<Self, String> {
+		Ok(Purchase {
+			title:        required_title(&row.title)?,
+			identifiers:  IdentifierCollection(
+				row.identifiers
+					.split(';')
+					.map(str::trim)
+					.filter(|identifier| !identifier.is_empty())
+					.map(Identifier::from)
+					.collect()
+			),
+			attributes:   AttributeMap::from_pairs(&row.attributes)?,
+			purchased_at: optional(row.purchased_at.as_deref(), parse_time)?,
+			id:           parse_id(row.id.as_deref())?
+		})
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RuleRow {
+	pub title:       String,
+	pub action:      String,
+	pub trigger:     String,
+	/// `line` or `order`, `line` when empty.
+	#[serde(default)]
+	pub scope:       Option<String>,
+	#[serde(default)]
+	pub priority:    Option<i32>,
+	#[serde(default)]
+	pub stop:        Option<bool>,
+	#[serde(default)]
+	pub group:       Option<String>,
+	/// First and last day the rule applies, as `YYYY-MM-DD`.
+	#[serde(default)]
+	pub valid_from:  Option<String>,
+	#[serde(default)]
+	pub valid_until: Option<String>,
+	#[serde(default)]
+	pub id:          Option<String>
+}
+impl Transferable for Rule {
+	type Row = RuleRow;
+
+	fn to_row(&self) -> Self::Row {
+		RuleRow {
+			title:       self.title.to_string(),
+			action:      self.process_action.to_string(),
+			trigger:     self.trigger.to_string(),
+			scope:       Some(
+				match self.scope {
+					RuleScope::Line => "line",
+					RuleScope::Order => "order"
+				}
+				.to_string()
+			),
+			priority:    Some(self.priority),
+			stop:        Some(self.stop_processing),
+			group:       self.exclusion_group.as_ref().map(ToString::to_string),
+			valid_from:  self.valid_from.map(|day| day.to_string()),
+			valid_until: self.valid_until.map(|day| day.to_string()),
+			id:          Some(self.id.to_string())
+		}
+	}
+	fn from_row(row: Self::Row) -> Result<Self, String> {
+		if row.action.trim().is_empty() {
+			return Err("the action is empty".to_string());
+		}
+		let scope = match row.scope.as_deref().map(str::trim) {
+			None | Some("" | "line") => RuleScope::Line,
+			Some("order") => RuleScope::Order,
+			Some(other) => {
+				return Err(format!(
+					"invalid scope '{}', expected 'line' or 'order'",
+					other
+				))
+			},
+		};
+		Ok(Rule {
+			title: required_title(&row.title)?,
+			process_action: ProcessAction::from(row.action.as_str()),
+			trigger: row.trigger.parse().map_err(|error: ParseError| {
+				format!("invalid trigger:\n{}", error.pointed(&row.trigger))
+			})?,
+			scope,
+			priority: row.priority.unwrap_or_default(),
+			stop_processing: row.stop.unwrap_or_default(),
+			exclusion_group: row
+				.group
+				.filter(|group| !group.trim().is_empty())
+				.map(|group| Arc::from(group.trim())),
+			valid_from: optional(row.valid_from.as_deref(), parse_date)?,
+			valid_until: optional(row.valid_until.as_deref(), parse_date)?,
+			id: parse_id(row.id.as_deref())?
+		})
+	}
+}
+fn required_title(title: &str) -> Result<Arc<str>, String> {
+	if title.trim().is_empty() {
+		Err("the title is empty".to_string())
+	} else {
+		Ok(title.trim().into())
+	}
+}
+/// An empty cell is the same as a missing one.
+fn optional<T>(
+	text: Option<&str>,
+	parse: impl Fn(&str) -> Result<T, String>
+) -> Result<Option<T>, String> {
+	match text.map(str::trim) {
+		None | Some("") => Ok(None),
+		Some(text) => parse(text).map(Some)
+	}
+}
+fn parse_id(id: Option<&str>) -> Result<EntryId, String> {
+	match id.map(str::trim) {
+		None | Some("") => Ok(EntryId::default()),
+		Some(id) => id.parse().map_err(|_| format!("invalid id '{}'", id))
+	}
+}
+
+/// Something wrong with a single row, which is left out of the import.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RowReport {
+	/// The line of a CSV file, or the position in the list of a YAML file, counting from 1.
+	pub row:     usize,
+	pub message: String
+}
+impl Display for RowReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "row {}: {}", self.row, self.message)
+	}
+}
+
+/// Reads every row on its own, so one bad row doesn't stop the others from being read.
+pub fn read_rows<T: Transferable>(
+	contents: &str,
+	format: Format
+) -> io::Result<Vec<(usize, Result<T, String>)>> {
+	let convert = |row: Result<T::Row, String>| row.and_then(T::from_row);
+	match format {
+		Format::Csv => {
+			let mut reader = csv::ReaderBuilder::new()
+				.trim(csv::Trim::All)
+				.from_reader(contents.as_bytes());
+			let headers = reader.headers().map_err(io::Error::other)?.clone();
+			let mut rows = Vec::new();
+			for record in reader.records() {
+				let (row, parsed) = match record {
+					Ok(record) => (
+						record
+							.position()
+							.map_or(0, |position| position.line() as usize),
+						record
+							.deserialize::<T::Row>(Some(&headers))
+							.map_err(|error| error.to_string())
+					),
+					Err(error) => (
+						error
+							.position()
+							.map_or(0, |position| position.line() as usize),
+						Err(error.to_string())
+					)
+				};
+				rows.push((row, convert(parsed)));
+			}
+			Ok(rows)
+		},
+		Format::Yaml => {
+			let values: Vec<serde_yaml::Value> = if contents.trim().is_empty() {
+				Vec::new()
+			} else {
+				serde_yaml::from_str(contents)
+					.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+			};
+			Ok(values
+				.into_iter()
+				.enumerate()
+				.map(|(index, value)| {
+					let parsed = serde_yaml::from_value(value).map_err(|error| error.to_string());
+					(index + 1, convert(parsed))
+				})
+				.collect())
+		}
+	}
+}
+
+pub fn write_rows<'a, T: Transferable + 'a>(
+	entries: impl Iterator<Item = &'a T>,
+	format: Format
+) -> io::Result<String> {
+	let rows = entries.map(T::to_row).collect::<Vec<_>>();
+	match format {
+		Format::Csv => {
+			let mut writer = csv::Writer::from_writer(Vec::new());
+			for row in &rows {
+				writer.serialize(row).map_err(io::Error::other)?;
+			}
+			let bytes = writer.into_inner().map_err(io::Error::other)?;
+			String::from_utf8(bytes).map_err(io::Error::other)
+		},
+		Format::Yaml => serde_yaml::to_string(&rows).map_err(io::Error::other)
+	}
+}
+
+/// What importing rows would change, compared to the current entries.
+#[derive(Debug)]
+pub struct ImportPlan<T> {
+	/// With the row each change comes from.
+	pub changes:    Vec<(usize, Change<T>)>,
+	/// Rows that are already in the data as they are.
+	pub unchanged:  usize,
+	pub duplicates: Vec<RowReport>,
+	pub errors:     Vec<RowReport>
+}
+impl<T: Transferable> ImportPlan<T> {
+	/// Rows with the ID of an existing entry replace it, other rows are added unless an entry
+	/// with the same contents exists. Rows repeating an earlier row are reported as duplicates.
+	pub fn new(existing: &BTreeSet<T>, rows: Vec<(usize, Result<T, String>)>) -> Self {
+		let mut plan = ImportPlan {
+			changes:    Vec::new(),
+			unchanged:  0,
+			duplicates: Vec::new(),
+			errors:     Vec::new()
+		};
+		let mut imported: Vec<(usize, T)> = Vec::new();
+		for (row, entry) in rows {
+			let mut entry = match entry {
+				Ok(entry) => entry,
+				Err(message) => {
+					plan.errors.push(RowReport { row, message });
+					continue;
+				}
+			};
+			let repeated = imported.iter().find(|(_, earlier)| {
+				earlier.same_contents(&entry)
+					|| (!entry.id().is_missing() && earlier.id() == entry.id())
+			});
+			if let Some((earlier_row, _)) = repeated {
+				plan.duplicates.push(RowReport {
+					row,
+					message: format!("repeats row {}", earlier_row)
+				});
+				continue;
+			}
+			imported.push((row, entry.clone()));
+			let same_id = existing
+				.iter()
+				.find(|current| !entry.id().is_missing() && current.id() == entry.id());
+			match same_id {
+				Some(current) if *current == entry => plan.unchanged += 1,
+				Some(current) => plan.changes.push((
+					row,
+					Change::Replace {
+						old: current.clone(),
+						new: entry
+					}
+				)),
+				None if existing.iter().any(|current| current.same_contents(&entry)) => {
+					plan.unchanged += 1;
+				},
+				None => {
+					if entry.id().is_missing() {
+						entry.set_id(EntryId::generate());
+					}
+					plan.changes.push((row, Change::Insert(entry)));
+				}
+			}
+		}
+		plan
+	}
+}
+impl<T: Transferable> Display for ImportPlan<T> {
+	/// Lists the changes as a diff, followed by the rows that are left out.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (row, change) in &self.changes {
+			match change {
+				Change::Insert(new) => {
+					writeln!(f, "+ row {}: add '{}'", row, new.name())?;
+					for line in new.to_string().lines() {
+						writeln!(f, "    + {}", line)?;
+					}
+				},
+				Change::Replace { old, new } => {
+					writeln!(f, "~ row {}: modify '{}'", row, old.name())?;
+					let (old, new) = (old.to_string(), new.to_string());
+					let (old_lines, new_lines) = (
+						old.lines().collect::<BTreeSet<_>>(),
+						new.lines().collect::<BTreeSet<_>>()
+					);
+					for line in old.lines().filter(|line| !new_lines.contains(line)) {
+						writeln!(f, "    - {}", line)?;
+					}
+					for line in new.lines().filter(|line| !old_lines.contains(line)) {
+						writeln!(f, "    + {}", line)?;
+					}
+				},
+				Change::Remove(old) => writeln!(f, "- row {}: remove '{}'", row, old.name())?
+			}
+		}
+		for duplicate in &self.duplicates {
+			writeln!(f, "duplicate {}", duplicate)?;
+		}
+		for error in &self.errors {
+			writeln!(f, "invalid {}", error)?;
+		}
+		let added = self
+			.changes
+			.iter()
+			.filter(|(_, change)| matches!(change, Change::Insert(_)))
+			.count();
+		write!(
+			f,
+			"{} to add, {} to modify, {} unchanged, {} duplicate and {} invalid rows.",
+			added,
+			self.changes.len() - added,
+			self.unchanged,
+			self.duplicates.len(),
+			self.errors.len()
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::{purchase, rule};
+
+	#[test]
+	fn rows_round_trip() {
+		let rules = BTreeSet::from([Rule {
+			scope: RuleScope::Order,
+			priority: 2,
+			stop_processing: true,
+			exclusion_group: Some("slips".into()),
+			valid_from: parse_date("2024-11-01").ok(),
+			valid_until: parse_date("2024-12-31").ok(),
+			..rule(
+				"Royalty slip",
+				"packing_slip(royalty)",
+				"any(book) and not all(digital)"
+			)
+		}]);
+		for format in [Format::Csv, Format::Yaml] {
+			let written = write_rows(rules.iter(), format).unwrap();
+			let read = read_rows::<Rule>(&written, format)
+				.unwrap()
+				.into_iter()
+				.map(|(_, rule)| rule.unwrap())
+				.collect::<BTreeSet<_>>();
+			assert_eq!(read, rules);
+		}
+	}
+
+	#[test]
+	fn plans_imports_per_row() {
+		let existing_book = purchase("1984", &["book"]);
+		let existing = BTreeSet::from([existing_book.clone()]);
+		let csv = format!(
+			"title,identifiers,id\n1984,book;physical,{}\nSki Wax,physical,\n1984,book,\n,video,\nSki \
+			 Wax,physical,\nBoots,,not-an-id\n",
+			existing_book.id
+		);
+		let rows = read_rows::<Purchase>(&csv, Format::Csv).unwrap();
+		let plan = ImportPlan::new(&existing, rows);
+		assert_eq!(plan.unchanged, 1);
+		assert!(matches!(
+			&plan.changes[..],
+			[(2, Change::Replace { .. }), (3, Change::Insert(added))] if added.title.as_ref() == "Ski Wax"
+		));
+		assert_eq!(
+			plan.duplicates,
+			[RowReport {
+				row:     6,
+				message: "repeats row 3".into()
+			}]
+		);
+		assert_eq!(
+			plan.errors
+				.iter()
+				.map(|error| error.row)
+				.collect::<Vec<_>>(),
+			[5, 7]
+		);
+	}
+}
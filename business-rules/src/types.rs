@@ -0,0 +1,1279 @@
+use std::{
+	cmp::{Ordering, Reverse},
+	collections::{BTreeMap, BTreeSet},
+	fmt::Display,
+	str::FromStr,
+	sync::Arc
+};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+use super::actions::{deserialize_action, ProcessAction};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Identifier(pub Arc<str>);
+impl<T: AsRef<str>> From<T> for Identifier {
+	fn from(value: T) -> Self { Identifier(Arc::from(value.as_ref())) }
+}
+impl Display for Identifier {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "'{}'", self.0) }
+}
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Default)]
+pub struct IdentifierCollection(pub BTreeSet<Identifier>);
+impl From<&[Identifier]> for IdentifierCollection {
+	fn from(value: &[Identifier]) -> Self { IdentifierCollection(value.iter().cloned().collect()) }
+}
+
+/// Value of a purchase attribute, stored in JSON as a boolean, number or string.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum AttributeValue {
+	Flag(bool),
+	Number(f64),
+	Text(Arc<str>)
+}
+impl AttributeValue {
+	/// `true` and `false` are flags, finite numbers are numbers and anything else is text.
+	pub fn from_text(text: &str) -> Self {
+		let text = text.trim();
+		match text {
+			"true" => AttributeValue::Flag(true),
+			"false" => AttributeValue::Flag(false),
+			_ => match text.parse::<f64>() {
+				Ok(number) if number.is_finite() => AttributeValue::Number(number),
+				_ => AttributeValue::Text(text.into())
+			}
+		}
+	}
+	/// Values of different kinds never compare, text compares ignoring case.
+	pub fn compare(&self, comparison: Comparison, other: &Self) -> bool {
+		match (self, other) {
+			(AttributeValue::Flag(left), AttributeValue::Flag(right)) => {
+				comparison.compare(left, right)
+			},
+			(AttributeValue::Number(left), AttributeValue::Number(right)) => {
+				comparison.compare(left, right)
+			},
+			(AttributeValue::Text(left), AttributeValue::Text(right)) => {
+				comparison.compare(&left.to_lowercase(), &right.to_lowercase())
+			},
+			_ => false
+		}
+	}
+	fn kind_order(&self) -> u8 {
+		match self {
+			AttributeValue::Flag(_) => 0,
+			AttributeValue::Number(_) => 1,
+			AttributeValue::Text(_) => 2
+		}
+	}
+}
+impl PartialEq for AttributeValue {
+	fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+impl Eq for AttributeValue {}
+impl PartialOrd for AttributeValue {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for AttributeValue {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self, other) {
+			(AttributeValue::Flag(left), AttributeValue::Flag(right)) => left.cmp(right),
+			(AttributeValue::Number(left), AttributeValue::Number(right)) => left.total_cmp(right),
+			(AttributeValue::Text(left), AttributeValue::Text(right)) => left.cmp(right),
+			_ => self.kind_order().cmp(&other.kind_order())
+		}
+	}
+}
+impl Display for AttributeValue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			AttributeValue::Flag(flag) => write!(f, "{}", flag),
+			AttributeValue::Number(number) => write!(f, "{}", number),
+			AttributeValue::Text(text) => write!(f, "{}", text)
+		}
+	}
+}
+/// Named values of a purchase, such as `price` or `region`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Default)]
+pub struct AttributeMap(pub BTreeMap<Arc<str>, AttributeValue>);
+impl AttributeMap {
+	pub fn is_empty(&self) -> bool { self.0.is_empty() }
+	/// Reads `key=value` pairs separated by `;`, see `AttributeValue::from_text` for the values.
+	pub fn from_pairs(pairs: &str) -> Result<Self, String> {
+		let mut attributes = AttributeMap::default();
+		for pair in pairs.split(';').filter(|pair| !pair.trim().is_empty()) {
+			let (key, value) = parse_attribute(pair)?;
+			attributes.0.insert(key, value);
+		}
+		Ok(attributes)
+	}
+	/// The attributes as `key=value` pairs separated by `;`.
+	pub fn to_pairs(&self) -> String {
+		self.0
+			.iter()
+			.map(|(key, value)| format!("{}={}", key, value))
+			.collect::<Vec<_>>()
+			.join(";")
+	}
+}
+/// Reads a single `key=value` pair.
+pub fn parse_attribute(pair: &str) -> Result<(Arc<str>, AttributeValue), String> {
+	match pair.split_once('=') {
+		Some((key, value)) if !key.trim().is_empty() => {
+			Ok((key.trim().into(), AttributeValue::from_text(value)))
+		},
+		_ => Err(format!("'{}' is not written as key=value", pair.trim()))
+	}
+}
+impl Display for AttributeMap {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let attributes = self
+			.0
+			.iter()
+			.map(|(key, value)| match value {
+				AttributeValue::Text(text) => format!("{}: '{}'", key, text),
+				value => format!("{}: {}", key, value)
+			})
+			.collect::<Vec<_>>();
+		write!(f, "{}", attributes.join(", "))
+	}
+}
+
+/// A regular expression, compared and stored as its source text.
+#[derive(Debug, Clone)]
+pub struct Pattern(pub Regex);
+impl Pattern {
+	pub fn as_str(&self) -> &str { self.0.as_str() }
+}
+impl FromStr for Pattern {
+	type Err = regex::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> { Regex::new(s).map(Pattern) }
+}
+impl PartialEq for Pattern {
+	fn eq(&self, other: &Self) -> bool { self.as_str() == other.as_str() }
+}
+impl Eq for Pattern {}
+impl PartialOrd for Pattern {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for Pattern {
+	fn cmp(&self, other: &Self) -> Ordering { self.as_str().cmp(other.as_str()) }
+}
+impl Serialize for Pattern {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self.as_str())
+	}
+}
+impl<'de> Deserialize<'de> for Pattern {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(serde::de::Error::custom)
+	}
+}
+
+/// Whether `text` matches `pattern` as a whole, ignoring case, where `*` stands for any text and
+/// `?` for any single character.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+	let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+	let text = text.to_lowercase().chars().collect::<Vec<_>>();
+	let (mut p, mut t) = (0, 0);
+	// where the last `*` was and the text position it currently stands in for
+	let mut backtrack = None;
+	while t < text.len() {
+		match pattern.get(p) {
+			Some('*') => {
+				backtrack = Some((p, t));
+				p += 1;
+			},
+			Some(&c) if c == '?' || c == text[t] => {
+				p += 1;
+				t += 1;
+			},
+			_ => match backtrack {
+				Some((star, matched)) => {
+					backtrack = Some((star, matched + 1));
+					p = star + 1;
+					t = matched + 1;
+				},
+				None => return false
+			}
+		}
+	}
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Generated once for every purchase and rule, so an entry can be told apart from others with
+/// the same contents and followed across renames. Entries saved before IDs existed have the nil
+/// ID in their file and are given one derived from their contents when they are loaded.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+#[serde(transparent)]
+pub struct EntryId(pub Uuid);
+impl EntryId {
+	/// Shortest start of an ID that is searched for.
+	pub const MIN_PREFIX: usize = 4;
+
+	pub fn generate() -> Self { EntryId(Uuid::new_v4()) }
+	/// The same for the same contents, so loading an entry without an ID gives it the same one
+	/// every time until it is changed.
+	pub fn derived_from(contents: &str) -> Self {
+		const NAMESPACE: Uuid = Uuid::from_u128(0x6b1f_2c3e_7a4d_4e58_9c1b_0d2e_3f4a_5b6c);
+		EntryId(Uuid::new_v5(&NAMESPACE, contents.as_bytes()))
+	}
+	pub fn is_missing(&self) -> bool { self.0.is_nil() }
+	/// Whether `search` is the ID or at least `MIN_PREFIX` characters from its start.
+	pub fn matches(&self, search: &str) -> bool {
+		let search = search.trim().to_lowercase();
+		!self.is_missing()
+			&& search.len() >= Self::MIN_PREFIX
+			&& self.to_string().starts_with(&search)
+	}
+}
+impl Display for EntryId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.0.hyphenated())
+	}
+}
+impl FromStr for EntryId {
+	type Err = uuid::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> { Uuid::parse_str(s.trim()).map(EntryId) }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Purchase {
+	pub title:        Arc<str>,
+	pub identifiers:  IdentifierCollection,
+	#[serde(default, skip_serializing_if = "AttributeMap::is_empty")]
+	pub attributes:   AttributeMap,
+	/// When the purchase was made, rules are evaluated as of this time unless told otherwise.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub purchased_at: Option<DateTime<Utc>>,
+	#[serde(default)]
+	pub id:           EntryId
+}
+impl Purchase {
+	/// Processing steps for this purchase on its own, as if it was a single item order, with the
+	/// rules that are active at the given time.
+	pub fn get_processing_steps(
+		&self,
+		rules: &BTreeSet<Rule>,
+		at: DateTime<Utc>
+	) -> Vec<ProcessAction> {
+		let order = PurchaseCollection::single(self.clone());
+		let line = OrderLine {
+			purchase: self,
+			quantity: 1,
+			order:    &order
+		};
+		unique_actions(fire_rules(rules.iter(), at, |rule| {
+			rule.trigger.triggered_on(&line)
+		}))
+	}
+
+	/// The time to evaluate rules at: the given time, else when the purchase was made, else now.
+	pub fn evaluation_time(&self, as_of: Option<DateTime<Utc>>) -> DateTime<Utc> {
+		as_of.or(self.purchased_at).unwrap_or_else(Utc::now)
+	}
+
+	pub fn has_identifier(&self, identifier: &Identifier) -> bool {
+		self.identifiers.0.contains(identifier)
+	}
+}
+
+impl Display for Purchase {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"title: '{}',\nidentifiers: [{}]",
+			self.title.as_ref(),
+			self.identifiers
+				.0
+				.iter()
+				.map(|identifier| format!("{}", identifier))
+				.collect::<Vec<_>>()
+				.join(", ")
+		)?;
+		if !self.attributes.is_empty() {
+			write!(f, "\nattributes: {{{}}}", self.attributes)?;
+		}
+		if let Some(purchased_at) = &self.purchased_at {
+			write!(f, "\npurchased at: {}", format_time(purchased_at))?;
+		}
+		if !self.id.is_missing() {
+			write!(f, "\nid: {}", self.id)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub struct PurchaseCollection(pub BTreeMap<Purchase, usize>);
+impl PurchaseCollection {
+	pub fn single(purchase: Purchase) -> Self {
+		PurchaseCollection(BTreeMap::from([(purchase, 1)]))
+	}
+
+	/// Total amount of items, counting every unit of every purchase.
+	pub fn size(&self) -> usize { self.0.values().sum() }
+
+	pub fn lines(&self) -> impl Iterator<Item = OrderLine<'_>> {
+		self.0.iter().map(|(purchase, quantity)| OrderLine {
+			purchase,
+			quantity: *quantity,
+			order: self
+		})
+	}
+}
+#[derive(Debug)]
+pub struct Order {
+	pub purchases: PurchaseCollection
+}
+/// What happened to a rule while deciding which rules fire.
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOutcome {
+	Fired,
+	NotTriggered,
+	/// The rule is not valid at the time the purchase is evaluated.
+	Inactive,
+	/// An earlier rule of the same exclusion group fired.
+	Excluded {
+		group: Arc<str>
+	},
+	/// A fired rule stopped processing before this rule was considered.
+	Stopped {
+		by: Arc<str>
+	}
+}
+/// Every rule in the order it is considered, with what happened to it:
+///  1. rules are considered from highest to lowest priority, equal priorities by title,
+///  2. rules outside of their validity window at the given time are skipped,
+///  3. only the first triggered rule of each exclusion group fires,
+///  4. a fired rule that stops processing prevents every later rule from firing.
+pub fn rule_outcomes<'a>(
+	rules: impl Iterator<Item = &'a Rule>,
+	at: DateTime<Utc>,
+	mut is_triggered: impl FnMut(&Rule) -> bool
+) -> Vec<(&'a Rule, RuleOutcome)> {
+	let mut ordered = rules.collect::<Vec<_>>();
+	ordered.sort_by_key(|rule| (Reverse(rule.priority), rule.title.clone()));
+	let mut outcomes = Vec::new();
+	let mut used_groups = BTreeSet::new();
+	let mut stopped_by: Option<Arc<str>> = None;
+	for rule in ordered {
+		let outcome = if let Some(by) = &stopped_by {
+			RuleOutcome::Stopped { by: by.clone() }
+		} else if !rule.is_active_at(at) {
+			RuleOutcome::Inactive
+		} else if !is_triggered(rule) {
+			RuleOutcome::NotTriggered
+		} else {
+			match &rule.exclusion_group {
+				Some(group) if !used_groups.insert(group.clone()) => RuleOutcome::Excluded {
+					group: group.clone()
+				},
+				_ => {
+					if rule.stop_processing {
+						stopped_by = Some(rule.title.clone());
+					}
+					RuleOutcome::Fired
+				}
+			}
+		};
+		outcomes.push((rule, outcome));
+	}
+	outcomes
+}
+/// The rules that fire, in the order their steps should be carried out.
+pub fn fire_rules<'a>(
+	rules: impl Iterator<Item = &'a Rule>,
+	at: DateTime<Utc>,
+	is_triggered: impl FnMut(&Rule) -> bool
+) -> impl Iterator<Item = &'a Rule> {
+	rule_outcomes(rules, at, is_triggered)
+		.into_iter()
+		.filter(|(_, outcome)| *outcome == RuleOutcome::Fired)
+		.map(|(rule, _)| rule)
+}
+/// The actions of the fired rules, keeping only the first of any duplicate actions.
+fn unique_actions<'a>(fired: impl Iterator<Item = &'a Rule>) -> Vec<ProcessAction> {
+	let mut actions: Vec<ProcessAction> = Vec::new();
+	for rule in fired {
+		if !actions.contains(&rule.process_action) {
+			actions.push(rule.process_action.clone());
+		}
+	}
+	actions
+}
+impl Order {
+	/// Line-scoped rules are listed per purchase, order-scoped rules are listed once for the
+	/// whole order if any of its purchases trigger them. Line and order rules are ordered and
+	/// stopped separately. Only rules active at the given time fire.
+	pub fn get_processing_plan(&self, rules: &BTreeSet<Rule>, at: DateTime<Utc>) -> ProcessingPlan {
+		let lines = self
+			.purchases
+			.lines()
+			.map(|line| LineProcessing {
+				purchase: line.purchase.clone(),
+				quantity: line.quantity,
+				steps:    unique_actions(fire_rules(
+					rules.iter().filter(|rule| rule.scope == RuleScope::Line),
+					at,
+					|rule| rule.trigger.triggered_on(&line)
+				))
+			})
+			.collect();
+		let order_steps = unique_actions(fire_rules(
+			rules.iter().filter(|rule| rule.scope == RuleScope::Order),
+			at,
+			|rule| {
+				self.purchases
+					.lines()
+					.any(|line| rule.trigger.triggered_on(&line))
+			}
+		));
+		ProcessingPlan { lines, order_steps }
+	}
+
+	/// The time to evaluate rules at: the given time, else when the last purchase was made, else
+	/// now.
+	pub fn evaluation_time(&self, as_of: Option<DateTime<Utc>>) -> DateTime<Utc> {
+		as_of
+			.or_else(|| {
+				self.purchases
+					.0
+					.keys()
+					.filter_map(|purchase| purchase.purchased_at)
+					.max()
+			})
+			.unwrap_or_else(Utc::now)
+	}
+}
+/// A purchase as it appears in an order, which is what triggers are evaluated against.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderLine<'a> {
+	pub purchase: &'a Purchase,
+	pub quantity: usize,
+	pub order:    &'a PurchaseCollection
+}
+
+#[derive(Debug)]
+pub struct LineProcessing {
+	pub purchase: Purchase,
+	pub quantity: usize,
+	pub steps:    Vec<ProcessAction>
+}
+#[derive(Debug)]
+pub struct ProcessingPlan {
+	pub lines:       Vec<LineProcessing>,
+	pub order_steps: Vec<ProcessAction>
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum IdentifierCondition {
+	None,
+	Any,
+	All
+}
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum CombinationCondition {
+	None,
+	ExactlyOne,
+	Either,
+	Both
+}
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Comparison {
+	Less,
+	LessOrEqual,
+	Equal,
+	NotEqual,
+	GreaterOrEqual,
+	Greater
+}
+impl Comparison {
+	pub const ALL: [Comparison; 6] = [
+		Comparison::Less,
+		Comparison::LessOrEqual,
+		Comparison::Equal,
+		Comparison::NotEqual,
+		Comparison::GreaterOrEqual,
+		Comparison::Greater
+	];
+
+	pub fn compare<T: PartialOrd>(self, left: &T, right: &T) -> bool {
+		match self {
+			Comparison::Less => left < right,
+			Comparison::LessOrEqual => left <= right,
+			Comparison::Equal => left == right,
+			Comparison::NotEqual => left != right,
+			Comparison::GreaterOrEqual => left >= right,
+			Comparison::Greater => left > right
+		}
+	}
+
+	pub const fn symbol(self) -> &'static str {
+		match self {
+			Comparison::Less => "<",
+			Comparison::LessOrEqual => "<=",
+			Comparison::Equal => "=",
+			Comparison::NotEqual => "!=",
+			Comparison::GreaterOrEqual => ">=",
+			Comparison::Greater => ">"
+		}
+	}
+
+	pub fn from_symbol(symbol: &str) -> Option<Self> {
+		match symbol {
+			"==" => Some(Comparison::Equal),
+			symbol => Comparison::ALL
+				.into_iter()
+				.find(|comparison| comparison.symbol() == symbol)
+		}
+	}
+}
+impl Display for Comparison {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.symbol())
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum RuleTrigger {
+	Never,
+	Always,
+	Title {
+		name: Arc<str>
+	},
+	Identifier {
+		identifiers: IdentifierCollection,
+		condition:   IdentifierCondition
+	},
+	Combination {
+		a:         Box<RuleTrigger>,
+		b:         Box<RuleTrigger>,
+		condition: CombinationCondition
+	},
+	Not {
+		flipped_rule: Box<RuleTrigger>
+	},
+	AllOf(Vec<RuleTrigger>),
+	AnyOf(Vec<RuleTrigger>),
+	NoneOf(Vec<RuleTrigger>),
+	AtLeast(usize, Vec<RuleTrigger>),
+	/// Compares against how many of the purchase are in the order.
+	Quantity {
+		comparison: Comparison,
+		amount:     usize
+	},
+	/// Compares against the total amount of items in the order.
+	OrderSize {
+		comparison: Comparison,
+		amount:     usize
+	},
+	/// Triggers when another purchase in the same order triggers `other`.
+	OrderContains {
+		other: Box<RuleTrigger>
+	},
+	/// The title contains the text, ignoring case.
+	TitleContains {
+		text: Arc<str>
+	},
+	/// The whole title matches a pattern with `*` and `?` wildcards, ignoring case.
+	TitleGlob {
+		pattern: Arc<str>
+	},
+	/// The title matches a regular expression anywhere, unless it is anchored.
+	TitleRegex {
+		pattern: Pattern
+	},
+	/// Any identifier starts with the prefix, such as a namespace like `membership:`.
+	IdentifierPrefix {
+		prefix: Arc<str>
+	},
+	/// Compares an attribute of the purchase, which fails when the purchase doesn't have it.
+	Attribute {
+		key:        Arc<str>,
+		comparison: Comparison,
+		value:      AttributeValue
+	}
+}
+
+impl RuleTrigger {
+	/// For a purchase on its own, as a single item order.
+	pub fn triggered_by(&self, purchase: &Purchase) -> bool {
+		let order = PurchaseCollection::single(purchase.clone());
+		self.triggered_on(&OrderLine {
+			purchase,
+			quantity: 1,
+			order: &order
+		})
+	}
+
+	/// Every identifier the trigger or any of its parts mentions.
+	pub fn identifiers(&self) -> BTreeSet<&Identifier> {
+		let mut found = BTreeSet::new();
+		let mut pending = vec![self];
+		while let Some(trigger) = pending.pop() {
+			match trigger {
+				RuleTrigger::Identifier { identifiers, .. } => found.extend(&identifiers.0),
+				RuleTrigger::Combination { a, b, .. } => pending.extend([a.as_ref(), b.as_ref()]),
+				RuleTrigger::Not {
+					flipped_rule: other
+				}
+				| RuleTrigger::OrderContains { other } => {
+					pending.push(other);
+				},
+				RuleTrigger::AllOf(triggers)
+				| RuleTrigger::AnyOf(triggers)
+				| RuleTrigger::NoneOf(triggers)
+				| RuleTrigger::AtLeast(_, triggers) => pending.extend(triggers),
+				_ => {}
+			}
+		}
+		found
+	}
+
+	pub fn triggered_on(&self, line: &OrderLine) -> bool {
+		let purchase = line.purchase;
+		match self {
+			RuleTrigger::Never => false,
+			RuleTrigger::Always => true,
+			RuleTrigger::Title { name } => {
+				purchase.title.to_lowercase().trim() == name.to_lowercase().trim()
+			},
+			RuleTrigger::Identifier {
+				identifiers,
+				condition
+			} => match condition {
+				IdentifierCondition::Any => identifiers
+					.0
+					.iter()
+					.any(|i| purchase.identifiers.0.contains(i)),
+				IdentifierCondition::All => identifiers
+					.0
+					.iter()
+					.all(|i| purchase.identifiers.0.contains(i)),
+				IdentifierCondition::None => !identifiers
+					.0
+					.iter()
+					.any(|i| purchase.identifiers.0.contains(i))
+			},
+			RuleTrigger::Combination { a, b, condition } => match condition {
+				CombinationCondition::None => !{ a.triggered_on(line) || b.triggered_on(line) },
+				CombinationCondition::ExactlyOne => a.triggered_on(line) != b.triggered_on(line),
+				CombinationCondition::Either => a.triggered_on(line) || b.triggered_on(line),
+				CombinationCondition::Both => a.triggered_on(line) && b.triggered_on(line)
+			},
+			RuleTrigger::Not { flipped_rule } => !flipped_rule.triggered_on(line),
+			RuleTrigger::AllOf(triggers) => triggers.iter().all(|t| t.triggered_on(line)),
+			RuleTrigger::AnyOf(triggers) => triggers.iter().any(|t| t.triggered_on(line)),
+			RuleTrigger::NoneOf(triggers) => !triggers.iter().any(|t| t.triggered_on(line)),
+			RuleTrigger::AtLeast(count, triggers) => {
+				triggers.iter().filter(|t| t.triggered_on(line)).count() >= *count
+			},
+			RuleTrigger::Quantity { comparison, amount } => {
+				comparison.compare(&line.quantity, amount)
+			},
+			RuleTrigger::OrderSize { comparison, amount } => {
+				comparison.compare(&line.order.size(), amount)
+			},
+			RuleTrigger::OrderContains { other } => line
+				.order
+				.lines()
+				.filter(|other_line| other_line.purchase != purchase)
+				.any(|other_line| other.triggered_on(&other_line)),
+			RuleTrigger::TitleContains { text } => {
+				purchase.title.to_lowercase().contains(&text.to_lowercase())
+			},
+			RuleTrigger::TitleGlob { pattern } => glob_matches(pattern, &purchase.title),
+			RuleTrigger::TitleRegex { pattern } => pattern.0.is_match(&purchase.title),
+			RuleTrigger::IdentifierPrefix { prefix } => purchase
+				.identifiers
+				.0
+				.iter()
+				.any(|identifier| identifier.0.starts_with(prefix.as_ref())),
+			RuleTrigger::Attribute {
+				key,
+				comparison,
+				value
+			} => purchase
+				.attributes
+				.0
+				.get(key)
+				.is_some_and(|actual| actual.compare(*comparison, value))
+		}
+	}
+}
+/// Rule files may store a trigger either structurally or as a one-line expression.
+fn deserialize_trigger<'de, D>(deserializer: D) -> Result<RuleTrigger, D::Error>
+where
+	D: Deserializer<'de>
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum StoredTrigger {
+		Structured(RuleTrigger),
+		Expression(String)
+	}
+	match StoredTrigger::deserialize(deserializer)? {
+		StoredTrigger::Structured(trigger) => Ok(trigger),
+		StoredTrigger::Expression(expression) => expression
+			.parse()
+			.map_err(|error| serde::de::Error::custom(format!("in '{}': {}", expression, error)))
+	}
+}
+/// Whether a rule runs for each purchase it is triggered by, or once for a whole order.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub enum RuleScope {
+	#[default]
+	Line,
+	Order
+}
+impl RuleScope {
+	fn is_line(&self) -> bool { *self == RuleScope::Line }
+}
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Rule {
+	pub title:           Arc<str>,
+	#[serde(deserialize_with = "deserialize_action")]
+	pub process_action:  ProcessAction,
+	#[serde(deserialize_with = "deserialize_trigger")]
+	pub trigger:         RuleTrigger,
+	#[serde(default, skip_serializing_if = "RuleScope::is_line")]
+	pub scope:           RuleScope,
+	/// Higher priorities are processed first.
+	#[serde(default, skip_serializing_if = "is_zero")]
+	pub priority:        i32,
+	/// Whether firing this rule prevents lower priority rules from firing.
+	#[serde(default, skip_serializing_if = "is_false")]
+	pub stop_processing: bool,
+	/// Only the first rule to fire within the same group is applied.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub exclusion_group: Option<Arc<str>>,
+	/// First day the rule applies, it applies from the start if missing.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub valid_from:      Option<NaiveDate>,
+	/// Last day the rule applies, it keeps applying if missing.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub valid_until:     Option<NaiveDate>,
+	#[serde(default)]
+	pub id:              EntryId
+}
+fn is_zero(value: &i32) -> bool { *value == 0 }
+fn is_false(value: &bool) -> bool { !*value }
+impl Rule {
+	/// Whether the day of the given time is within the rule's validity window.
+	pub fn is_active_at(&self, at: DateTime<Utc>) -> bool {
+		let day = at.date_naive();
+		self.valid_from.is_none_or(|from| from <= day)
+			&& self.valid_until.is_none_or(|until| day <= until)
+	}
+
+	/// The validity window in words, if the rule has one.
+	pub fn validity(&self) -> Option<String> {
+		match (self.valid_from, self.valid_until) {
+			(None, None) => None,
+			(Some(from), None) => Some(format!("valid from {}", from)),
+			(None, Some(until)) => Some(format!("valid until {}", until)),
+			(Some(from), Some(until)) => Some(format!("valid from {} until {}", from, until))
+		}
+	}
+}
+
+/// Reads a day written as `YYYY-MM-DD`.
+pub fn parse_date(text: &str) -> Result<NaiveDate, String> {
+	NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+		.map_err(|_| format!("'{}' is not a date written as YYYY-MM-DD", text.trim()))
+}
+/// Reads a time in RFC 3339, like `2024-05-01T14:30:00Z`, or a day, taken as its start in UTC.
+pub fn parse_time(text: &str) -> Result<DateTime<Utc>, String> {
+	let text = text.trim();
+	DateTime::parse_from_rfc3339(text)
+		.map(|time| time.with_timezone(&Utc))
+		.or_else(|_| parse_date(text).map(|day| day.and_time(NaiveTime::MIN).and_utc()))
+		.map_err(|_| {
+			format!(
+				"'{}' is not a time written as YYYY-MM-DD or like 2024-05-01T14:30:00Z",
+				text
+			)
+		})
+}
+pub fn format_time(time: &DateTime<Utc>) -> String {
+	time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+impl Display for Rule {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"title: '{}',\nprocess_action: {}\ntrigger: {}",
+			self.title.as_ref(),
+			self.process_action,
+			self.trigger
+		)?;
+		if self.scope == RuleScope::Order {
+			write!(f, "\nscope: once per order")?;
+		}
+		if self.priority != 0 {
+			write!(f, "\npriority: {}", self.priority)?;
+		}
+		if self.stop_processing {
+			write!(f, "\nstops processing of later rules")?;
+		}
+		if let Some(group) = &self.exclusion_group {
+			write!(f, "\nexclusion group: '{}'", group)?;
+		}
+		if let Some(validity) = self.validity() {
+			write!(f, "\n{}", validity)?;
+		}
+		if !self.id.is_missing() {
+			write!(f, "\nid: {}", self.id)?;
+		}
+		Ok(())
+	}
+}
+
+pub trait Named {
+	fn name(&self) -> &str;
+}
+impl Named for Purchase {
+	fn name(&self) -> &str { self.title.as_ref() }
+}
+impl Named for Rule {
+	fn name(&self) -> &str { self.title.as_ref() }
+}
+impl Named for Identifier {
+	fn name(&self) -> &str { &self.0 }
+}
+pub trait Identified {
+	fn id(&self) -> EntryId;
+	fn set_id(&mut self, id: EntryId);
+}
+impl Identified for Purchase {
+	fn id(&self) -> EntryId { self.id }
+	fn set_id(&mut self, id: EntryId) { self.id = id; }
+}
+impl Identified for Rule {
+	fn id(&self) -> EntryId { self.id }
+	fn set_id(&mut self, id: EntryId) { self.id = id; }
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeSet;
+
+	use lazy_static::lazy_static;
+
+	use super::*;
+	use crate::testing::{purchase, rule};
+
+	const GENERATE_SLIP: &str = "generate a packing slip for shipping";
+	const DUPLICATE_SLIP: &str = "create a duplicate packing slip for the royalty department";
+	const ACTIVATE_MEMBERSHIP: &str = "activate that membership";
+	const APPLY_UPGRADE: &str = "apply the upgrade";
+	const EMAIL_OWNER: &str = "e-mail the owner and inform them of the activation/upgrade";
+	const FIRST_AID_VIDEO: &str =
+		"add a free “First Aid” video to the packing slip (the result of a court decision in 1997)";
+	const GENERATE_COMMISION: &str = "generate a commission payment to the agent";
+
+	lazy_static! {
+		static ref RULES_SAMPLE: BTreeSet<Rule> = BTreeSet::from([
+			Rule {
+				title:           "physical products generate slips".into(),
+				process_action:  GENERATE_SLIP.into(),
+				trigger:         RuleTrigger::Identifier {
+					identifiers: IdentifierCollection(["physical".into()].into()),
+					condition:   IdentifierCondition::Any
+				},
+				scope:           RuleScope::Line,
+				priority:        0,
+				stop_processing: false,
+				exclusion_group: None,
+				valid_from:      None,
+				valid_until:     None,
+				id:              EntryId::default()
+			},
+			Rule {
+				title:           "royalty gets their duplicate slip".into(),
+				process_action:  DUPLICATE_SLIP.into(),
+				trigger:         RuleTrigger::Identifier {
+					identifiers: IdentifierCollection(["book".into()].into()),
+					condition:   IdentifierCondition::Any
+				},
+				scope:           RuleScope::Line,
+				priority:        0,
+				stop_processing: false,
+				exclusion_group: None,
+				valid_from:      None,
+				valid_until:     None,
+				id:              EntryId::default()
+			},
+			Rule {
+				title:           "memberships get activated".into(),
+				process_action:  ACTIVATE_MEMBERSHIP.into(),
+				trigger:         RuleTrigger::Identifier {
+					identifiers: IdentifierCollection(["membership".into()].into()),
+					condition:   IdentifierCondition::Any
+				},
+				scope:           RuleScope::Line,
+				priority:        0,
+				stop_processing: false,
+				exclusion_group: None,
+				valid_from:      None,
+				valid_until:     None,
+				id:              EntryId::default()
+			},
+			Rule {
+				title:           "membership upgrade get applied".into(),
+				process_action:  APPLY_UPGRADE.into(),
+				trigger:         RuleTrigger::Identifier {
+					identifiers: IdentifierCollection(["membership upgrade".into()].into()),
+					condition:   IdentifierCondition::Any
+				},
+				scope:           RuleScope::Line,
+				priority:        0,
+				stop_processing: false,
+				exclusion_group: None,
+				valid_from:      None,
+				valid_until:     None,
+				id:              EntryId::default()
+			},
+			Rule {
+				title:           "owner is informed of memberships and upgrades".into(),
+				process_action:  EMAIL_OWNER.into(),
+				trigger:         RuleTrigger::Identifier {
+					identifiers: IdentifierCollection(
+						["membership".into(), "upgrade".into()].into()
+					),
+					condition:   IdentifierCondition::Any
+				},
+				scope:           RuleScope::Line,
+				priority:        0,
+				stop_processing: false,
+				exclusion_group: None,
+				valid_from:      None,
+				valid_until:     None,
+				id:              EntryId::default()
+			},
+			Rule {
+				title:           "Learning to Ski first aid video".into(),
+				process_action:  FIRST_AID_VIDEO.into(),
+				trigger:         RuleTrigger::Title {
+					name: "Learning to Ski".into()
+				},
+				scope:           RuleScope::Line,
+				priority:        0,
+				stop_processing: false,
+				exclusion_group: None,
+				valid_from:      None,
+				valid_until:     None,
+				id:              EntryId::default()
+			},
+			Rule {
+				title:           "physical products or books generate commission payment".into(),
+				process_action:  GENERATE_COMMISION.into(),
+				trigger:         RuleTrigger::Identifier {
+					identifiers: IdentifierCollection(["physical".into(), "book".into()].into()),
+					condition:   IdentifierCondition::Any
+				},
+				scope:           RuleScope::Line,
+				priority:        0,
+				stop_processing: false,
+				exclusion_group: None,
+				valid_from:      None,
+				valid_until:     None,
+				id:              EntryId::default()
+			}
+		]);
+	}
+
+	#[test]
+	fn book_print() {
+		let expectation = [
+			GENERATE_SLIP.into(),
+			DUPLICATE_SLIP.into(),
+			GENERATE_COMMISION.into()
+		];
+		let purchase = Purchase {
+			title:        "1984".into(),
+			identifiers:  IdentifierCollection(["physical".into(), "book".into()].into()),
+			attributes:   AttributeMap::default(),
+			purchased_at: None,
+			id:           EntryId::default()
+		};
+		let steps = purchase.get_processing_steps(&RULES_SAMPLE, Utc::now());
+		assert!(expectation.iter().all(|item| steps.contains(item)));
+	}
+	#[test]
+	fn ski_mp4_print() {
+		let expectation = [FIRST_AID_VIDEO.into()];
+		let purchase = Purchase {
+			title:        "Learning to Ski".into(),
+			identifiers:  IdentifierCollection(["video".into()].into()),
+			attributes:   AttributeMap::default(),
+			purchased_at: None,
+			id:           EntryId::default()
+		};
+		let steps = purchase.get_processing_steps(&RULES_SAMPLE, Utc::now());
+		assert!(expectation.iter().all(|item| steps.contains(item)));
+	}
+	#[test]
+	fn gym_membership_print() {
+		let expectation = [ACTIVATE_MEMBERSHIP.into(), EMAIL_OWNER.into()];
+		let purchase = Purchase {
+			title:        "Fitness World 3 month discount trial".into(),
+			identifiers:  IdentifierCollection(["membership".into()].into()),
+			attributes:   AttributeMap::default(),
+			purchased_at: None,
+			id:           EntryId::default()
+		};
+		let steps = purchase.get_processing_steps(&RULES_SAMPLE, Utc::now());
+		assert!(expectation.iter().all(|item| steps.contains(item)));
+	}
+	#[test]
+	fn stored_trigger_expression() {
+		let rule: Rule = serde_json::from_str(
+			r#"{
+				"title": "books or physical products",
+				"process_action": "generate a packing slip for shipping",
+				"trigger": "any(book) or any(physical)"
+			}"#
+		)
+		.unwrap();
+		assert_eq!(rule.trigger.to_string(), "any(book) or any(physical)");
+		let invalid = serde_json::from_str::<Rule>(
+			r#"{ "title": "", "process_action": "", "trigger": "any(book) or" }"#
+		);
+		assert!(invalid.is_err());
+	}
+	#[test]
+	fn n_ary_triggers() {
+		let purchase = purchase("1984", &["physical", "book"]);
+		let any_of = |identifier: &str| RuleTrigger::Identifier {
+			identifiers: IdentifierCollection([identifier.into()].into()),
+			condition:   IdentifierCondition::Any
+		};
+		let triggers = vec![any_of("book"), any_of("physical"), any_of("video")];
+		assert!(!RuleTrigger::AllOf(triggers.clone()).triggered_by(&purchase));
+		assert!(RuleTrigger::AnyOf(triggers.clone()).triggered_by(&purchase));
+		assert!(!RuleTrigger::NoneOf(triggers.clone()).triggered_by(&purchase));
+		assert!(RuleTrigger::AtLeast(2, triggers.clone()).triggered_by(&purchase));
+		assert!(!RuleTrigger::AtLeast(3, triggers).triggered_by(&purchase));
+		assert!(RuleTrigger::AllOf(Vec::new()).triggered_by(&purchase));
+		assert!(!RuleTrigger::AnyOf(Vec::new()).triggered_by(&purchase));
+
+		let stored: RuleTrigger =
+			serde_json::from_str(r#"{ "AtLeast": [1, ["Always", "Never"]] }"#).unwrap();
+		assert_eq!(
+			stored,
+			RuleTrigger::AtLeast(1, vec![RuleTrigger::Always, RuleTrigger::Never])
+		);
+	}
+	#[test]
+	fn order_processing_plan() {
+		let book = purchase("1984", &["physical", "book"]);
+		let video = purchase("Learning to Ski", &["video"]);
+		let rules = BTreeSet::from([
+			rule(
+				"bulk book discount",
+				"apply bulk discount",
+				"any(book) and quantity >= 3"
+			),
+			rule(
+				"videos bought with books get a bookmark",
+				"add a bookmark",
+				"any(video) and order_contains(any(book))"
+			),
+			Rule {
+				scope: RuleScope::Order,
+				..rule("large orders ship free", "ship for free", "order_size > 3")
+			}
+		]);
+		let order = Order {
+			purchases: PurchaseCollection(BTreeMap::from([(book.clone(), 3), (video.clone(), 1)]))
+		};
+		let plan = order.get_processing_plan(&rules, Utc::now());
+		let steps_for = |purchase: &Purchase| {
+			plan.lines
+				.iter()
+				.find(|line| line.purchase == *purchase)
+				.map(|line| line.steps.clone())
+				.unwrap()
+		};
+		assert_eq!(
+			steps_for(&book),
+			vec![ProcessAction::from("apply bulk discount")]
+		);
+		assert_eq!(
+			steps_for(&video),
+			vec![ProcessAction::from("add a bookmark")]
+		);
+		assert_eq!(plan.order_steps, vec![ProcessAction::from("ship for free")]);
+
+		let small_order = Order {
+			purchases: PurchaseCollection::single(video.clone())
+		};
+		let plan = small_order.get_processing_plan(&rules, Utc::now());
+		assert!(plan.lines[0].steps.is_empty());
+		assert!(plan.order_steps.is_empty());
+	}
+	#[test]
+	fn rule_processing_order() {
+		let rule = |title: &str, priority: i32| Rule {
+			priority,
+			..rule(title, title, "always")
+		};
+		let purchase = purchase("1984", &[]);
+		let mut rules = BTreeSet::from([
+			rule("commission", 1),
+			rule("slip", 3),
+			rule("duplicate slip", 2),
+			rule("another commission", 1)
+		]);
+		assert_eq!(
+			purchase.get_processing_steps(&rules, Utc::now()),
+			["slip", "duplicate slip", "another commission", "commission"].map(ProcessAction::from)
+		);
+
+		let mut grouped = rule("free video", 2);
+		grouped.exclusion_group = Some("freebie".into());
+		let mut also_grouped = rule("free sticker", 2);
+		also_grouped.exclusion_group = Some("freebie".into());
+		let mut stop = rule("cancelled", 2);
+		stop.stop_processing = true;
+		rules.extend([grouped, also_grouped, stop]);
+		assert_eq!(
+			purchase.get_processing_steps(&rules, Utc::now()),
+			["slip", "cancelled"].map(ProcessAction::from)
+		);
+		rules.retain(|rule| !rule.stop_processing);
+		assert_eq!(
+			purchase.get_processing_steps(&rules, Utc::now()),
+			[
+				"slip",
+				"duplicate slip",
+				"free sticker",
+				"another commission",
+				"commission"
+			]
+			.map(ProcessAction::from)
+		);
+	}
+	#[test]
+	fn duplicate_actions_are_listed_once() {
+		let rules = BTreeSet::from([
+			rule("books get slips", "packing_slip", "any(book)"),
+			rule(
+				"physical products get slips",
+				"packing_slip",
+				"any(physical)"
+			)
+		]);
+		let purchase = purchase("1984", &["physical", "book"]);
+		assert_eq!(
+			purchase.get_processing_steps(&rules, Utc::now()),
+			vec![ProcessAction::PackingSlip { department: None }]
+		);
+	}
+
+	#[test]
+	fn title_patterns_and_attributes() {
+		assert!(glob_matches("learn*ski?", "Learning to Skis"));
+		assert!(glob_matches("*", ""));
+		assert!(!glob_matches("learn*ski", "Learning to Skis"));
+		let purchase: Purchase = serde_json::from_str(
+			r#"{
+				"title": "Fitness World Gold",
+				"identifiers": ["membership:gold"],
+				"attributes": {"price": 120, "region": "EU", "gift": false}
+			}"#
+		)
+		.unwrap();
+		assert_eq!(
+			purchase.attributes.0.get("price"),
+			Some(&AttributeValue::Number(120.0))
+		);
+		let written = serde_json::to_string(&purchase).unwrap();
+		assert_eq!(
+			serde_json::from_str::<Purchase>(&written).unwrap(),
+			purchase
+		);
+		for (trigger, expected) in [
+			("title_contains(\"WORLD\")", true),
+			("title_glob(\"fitness*gold\")", true),
+			("title_regex(\"^Fitness\")", true),
+			("title_regex(\"^fitness\")", false),
+			("ident_prefix(membership:)", true),
+			("ident_prefix(upgrade:)", false),
+			("price > 100 and price <= 120", true),
+			("region = eu and region != US", true),
+			("gift = false", true),
+			("price = \"120\"", false),
+			("weight < 5", false)
+		] {
+			let parsed: RuleTrigger = trigger.parse().unwrap();
+			assert_eq!(parsed.triggered_by(&purchase), expected, "{}", trigger);
+		}
+	}
+	#[test]
+	fn rules_apply_within_their_validity_window() {
+		let mut promotion: Rule = serde_json::from_str(
+			r#"{
+				"title": "Winter promotion",
+				"process_action": "attach(Ski Wax)",
+				"trigger": "always",
+				"valid_from": "2024-12-01",
+				"valid_until": "2024-12-31"
+			}"#
+		)
+		.unwrap();
+		let mut purchase: Purchase =
+			serde_json::from_str(r#"{"title": "Learning to Ski", "identifiers": ["video"]}"#)
+				.unwrap();
+		let rules = BTreeSet::from([promotion.clone()]);
+		for (time, fires) in [
+			("2024-11-30T23:59:59Z", false),
+			("2024-12-01", true),
+			("2024-12-31T23:59:59+00:00", true),
+			("2025-01-01", false)
+		] {
+			let at = parse_time(time).unwrap();
+			assert_eq!(
+				!purchase.get_processing_steps(&rules, at).is_empty(),
+				fires,
+				"{}",
+				time
+			);
+		}
+		assert!(parse_time("31-12-2024").is_err());
+
+		purchase.purchased_at = parse_time("2024-12-24T10:00:00Z").ok();
+		assert_eq!(
+			purchase.evaluation_time(None),
+			purchase.purchased_at.unwrap()
+		);
+		let as_of = parse_time("2025-02-01").unwrap();
+		assert_eq!(purchase.evaluation_time(Some(as_of)), as_of);
+		let order = Order {
+			purchases: PurchaseCollection::single(purchase)
+		};
+		let plan = order.get_processing_plan(&rules, order.evaluation_time(None));
+		assert_eq!(
+			plan.lines[0].steps,
+			[ProcessAction::from("attach(Ski Wax)")]
+		);
+
+		promotion.valid_from = None;
+		assert_eq!(
+			promotion.validity().as_deref(),
+			Some("valid until 2024-12-31")
+		);
+	}
+}
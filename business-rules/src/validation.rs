@@ -0,0 +1,403 @@
+use std::{collections::BTreeSet, fmt::Display, ptr, sync::Arc};
+
+use serde::Serialize;
+
+use super::types::{
+	CombinationCondition, Identifier, IdentifierCondition, Purchase, Rule, RuleTrigger
+};
+
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	Warning,
+	Error
+}
+impl Display for Severity {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Severity::Warning => write!(f, "warning"),
+			Severity::Error => write!(f, "error")
+		}
+	}
+}
+
+/// A problem with a single rule.
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct Finding {
+	pub severity: Severity,
+	pub rule:     Arc<str>,
+	pub message:  String
+}
+impl Finding {
+	fn new(severity: Severity, rule: &Rule, message: String) -> Self {
+		Finding {
+			severity,
+			rule: rule.title.clone(),
+			message
+		}
+	}
+	pub fn is_error(&self) -> bool { self.severity == Severity::Error }
+}
+impl Display for Finding {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{} in rule '{}': {}",
+			self.severity, self.rule, self.message
+		)
+	}
+}
+
+/// Checks the rules for triggers and validity windows that can't do what they look like they do.
+/// Identifiers are only checked against purchases when `purchases` are given.
+pub fn validate_rules(
+	rules: &BTreeSet<Rule>,
+	purchases: Option<&BTreeSet<Purchase>>
+) -> Vec<Finding> {
+	let mut findings = Vec::new();
+	let mut seen: Vec<&Rule> = Vec::new();
+	for rule in rules {
+		if let (Some(from), Some(until)) = (rule.valid_from, rule.valid_until) {
+			if from > until {
+				findings.push(Finding::new(
+					Severity::Error,
+					rule,
+					format!(
+						"the validity window ends on {} before it starts on {}",
+						until, from
+					)
+				));
+			}
+		}
+		let empty = empty_identifier_triggers(&rule.trigger);
+		for trigger in &empty {
+			findings.push(Finding::new(
+				Severity::Error,
+				rule,
+				format!("'{}' doesn't list any identifiers", trigger)
+			));
+		}
+		// an empty identifier list is already constant, no need to report it twice
+		if empty.is_empty() {
+			for (trigger, value) in constant_parts(&rule.trigger) {
+				findings.push(if ptr::eq(trigger, &rule.trigger) {
+					if value {
+						Finding::new(
+							Severity::Warning,
+							rule,
+							format!("the trigger '{}' always fires, use 'always'", trigger)
+						)
+					} else {
+						Finding::new(
+							Severity::Error,
+							rule,
+							format!("the trigger '{}' can never fire", trigger)
+						)
+					}
+				} else {
+					Finding::new(
+						Severity::Warning,
+						rule,
+						format!("'{}' is always {}", trigger, value)
+					)
+				});
+			}
+		}
+		if let Some(purchases) = purchases {
+			for identifier in rule.trigger.identifiers() {
+				if !purchases
+					.iter()
+					.any(|purchase| purchase.identifiers.0.contains(identifier))
+				{
+					findings.push(Finding::new(
+						Severity::Warning,
+						rule,
+						format!("no purchase has the identifier {}", identifier)
+					));
+				}
+			}
+		}
+		if let Some(same) = seen.iter().find(|other| {
+			other.trigger == rule.trigger && other.process_action == rule.process_action
+		}) {
+			findings.push(Finding::new(
+				Severity::Warning,
+				rule,
+				format!("has the same trigger and action as '{}'", same.title)
+			));
+		}
+		seen.push(rule);
+	}
+	findings
+}
+
+fn children(trigger: &RuleTrigger) -> Vec<&RuleTrigger> {
+	match trigger {
+		RuleTrigger::Combination { a, b, .. } => vec![a, b],
+		RuleTrigger::Not { flipped_rule } => vec![flipped_rule],
+		RuleTrigger::AllOf(triggers)
+		| RuleTrigger::AnyOf(triggers)
+		| RuleTrigger::NoneOf(triggers)
+		| RuleTrigger::AtLeast(_, triggers) => triggers.iter().collect(),
+		RuleTrigger::OrderContains { other } => vec![other],
+		_ => Vec::new()
+	}
+}
+fn empty_identifier_triggers(trigger: &RuleTrigger) -> Vec<&RuleTrigger> {
+	match trigger {
+		RuleTrigger::Identifier { identifiers, .. } if identifiers.0.is_empty() => vec![trigger],
+		_ => children(trigger)
+			.into_iter()
+			.flat_map(empty_identifier_triggers)
+			.collect()
+	}
+}
+/// The outermost parts of a trigger that give the same result for every purchase and order.
+/// A plain `always` is taken to be intentional.
+fn constant_parts(trigger: &RuleTrigger) -> Vec<(&RuleTrigger, bool)> {
+	if trigger == &RuleTrigger::Always {
+		return Vec::new();
+	}
+	match Situations::of(trigger).and_then(|situations| situations.constant_result(trigger)) {
+		Some(value) => vec![(trigger, value)],
+		None => children(trigger)
+			.into_iter()
+			.flat_map(constant_parts)
+			.collect()
+	}
+}
+
+/// Every distinct situation a trigger can tell apart. Each `order_contains`, title pattern,
+/// identifier prefix and attribute comparison is treated as independent of everything else,
+/// which can only hide constant triggers, never invent them.
+struct Situations<'a> {
+	titles:      Vec<String>,
+	identifiers: Vec<&'a Identifier>,
+	quantities:  BTreeSet<usize>,
+	sizes:       BTreeSet<usize>,
+	independent: Vec<&'a RuleTrigger>
+}
+struct Situation<'s, 'a> {
+	title:       Option<&'s str>,
+	identifiers: BTreeSet<&'a Identifier>,
+	quantity:    usize,
+	size:        usize,
+	independent: u32
+}
+impl<'a> Situations<'a> {
+	/// Gives up on triggers with too many situations to go through.
+	const LIMIT: usize = 1 << 16;
+
+	fn of(trigger: &'a RuleTrigger) -> Option<Self> {
+		let mut situations = Situations {
+			titles:      Vec::new(),
+			identifiers: Vec::new(),
+			quantities:  BTreeSet::from([1]),
+			sizes:       BTreeSet::from([1]),
+			independent: Vec::new()
+		};
+		situations.collect(trigger);
+		let sizes = situations.quantities.clone();
+		situations.sizes.extend(sizes);
+		let count = (situations.titles.len() + 1)
+			.checked_mul(1usize.checked_shl(situations.identifiers.len() as u32)?)?
+			.checked_mul(situations.quantities.len() * situations.sizes.len())?
+			.checked_mul(1usize.checked_shl(situations.independent.len() as u32)?)?;
+		(count <= Self::LIMIT).then_some(situations)
+	}
+	fn collect(&mut self, trigger: &'a RuleTrigger) {
+		let around = |amount: usize| [amount.saturating_sub(1), amount, amount + 1];
+		match trigger {
+			RuleTrigger::Title { name } => {
+				let title = name.to_lowercase().trim().to_string();
+				if !self.titles.contains(&title) {
+					self.titles.push(title);
+				}
+			},
+			RuleTrigger::Identifier { identifiers, .. } => {
+				for identifier in &identifiers.0 {
+					if !self.identifiers.contains(&identifier) {
+						self.identifiers.push(identifier);
+					}
+				}
+			},
+			RuleTrigger::Quantity { amount, .. } => {
+				self.quantities
+					.extend(around(*amount).into_iter().filter(|&q| q > 0));
+			},
+			RuleTrigger::OrderSize { amount, .. } => {
+				self.sizes
+					.extend(around(*amount).into_iter().filter(|&s| s > 0));
+			},
+			// analysed on its own by `constant_parts`
+			RuleTrigger::OrderContains { .. } => self.independent.push(trigger),
+			RuleTrigger::TitleContains { .. }
+			| RuleTrigger::TitleGlob { .. }
+			| RuleTrigger::TitleRegex { .. }
+			| RuleTrigger::IdentifierPrefix { .. }
+			| RuleTrigger::Attribute { .. } => self.independent.push(trigger),
+			_ => {}
+		}
+		if !matches!(trigger, RuleTrigger::OrderContains { .. }) {
+			for child in children(trigger) {
+				self.collect(child);
+			}
+		}
+	}
+	fn constant_result(&self, trigger: &RuleTrigger) -> Option<bool> {
+		let mut results = BTreeSet::new();
+		let titles = self
+			.titles
+			.iter()
+			.map(|title| Some(title.as_str()))
+			.chain([None]);
+		for title in titles {
+			for identifier_mask in 0..1u32 << self.identifiers.len() {
+				let identifiers = (self.identifiers.iter().enumerate())
+					.filter(|(index, _)| identifier_mask & (1 << index) != 0)
+					.map(|(_, identifier)| *identifier)
+					.collect::<BTreeSet<_>>();
+				for &quantity in &self.quantities {
+					for &size in self.sizes.range(quantity..) {
+						for independent in 0..1u32 << self.independent.len() {
+							let situation = Situation {
+								title,
+								identifiers: identifiers.clone(),
+								quantity,
+								size,
+								independent
+							};
+							results.insert(self.evaluate(trigger, &situation));
+							if results.len() > 1 {
+								return None;
+							}
+						}
+					}
+				}
+			}
+		}
+		results.pop_first()
+	}
+	fn evaluate(&self, trigger: &RuleTrigger, situation: &Situation) -> bool {
+		let evaluate = |trigger: &RuleTrigger| self.evaluate(trigger, situation);
+		match trigger {
+			RuleTrigger::Never => false,
+			RuleTrigger::Always => true,
+			RuleTrigger::Title { name } => situation.title == Some(name.to_lowercase().trim()),
+			RuleTrigger::Identifier {
+				identifiers,
+				condition
+			} => {
+				let mut present = identifiers
+					.0
+					.iter()
+					.map(|identifier| situation.identifiers.contains(identifier));
+				match condition {
+					IdentifierCondition::Any => present.any(|is| is),
+					IdentifierCondition::All => present.all(|is| is),
+					IdentifierCondition::None => !present.any(|is| is)
+				}
+			},
+			RuleTrigger::Combination { a, b, condition } => match condition {
+				CombinationCondition::None => !(evaluate(a) || evaluate(b)),
+				CombinationCondition::ExactlyOne => evaluate(a) != evaluate(b),
+				CombinationCondition::Either => evaluate(a) || evaluate(b),
+				CombinationCondition::Both => evaluate(a) && evaluate(b)
+			},
+			RuleTrigger::Not { flipped_rule } => !evaluate(flipped_rule),
+			RuleTrigger::AllOf(triggers) => triggers.iter().all(evaluate),
+			RuleTrigger::AnyOf(triggers) => triggers.iter().any(evaluate),
+			RuleTrigger::NoneOf(triggers) => !triggers.iter().any(evaluate),
+			RuleTrigger::AtLeast(count, triggers) => {
+				triggers.iter().filter(|t| evaluate(t)).count() >= *count
+			},
+			RuleTrigger::Quantity { comparison, amount } => {
+				comparison.compare(&situation.quantity, amount)
+			},
+			RuleTrigger::OrderSize { comparison, amount } => {
+				comparison.compare(&situation.size, amount)
+			},
+			RuleTrigger::OrderContains { .. }
+			| RuleTrigger::TitleContains { .. }
+			| RuleTrigger::TitleGlob { .. }
+			| RuleTrigger::TitleRegex { .. }
+			| RuleTrigger::IdentifierPrefix { .. }
+			| RuleTrigger::Attribute { .. } => self
+				.independent
+				.iter()
+				.position(|independent| ptr::eq(*independent, trigger))
+				.is_some_and(|index| situation.independent & (1 << index) != 0)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		testing::{self, purchase},
+		types::IdentifierCollection
+	};
+
+	fn rule(title: &str, trigger: &str) -> Rule { testing::rule(title, "packing_slip", trigger) }
+	fn messages(findings: &[Finding]) -> Vec<String> {
+		findings.iter().map(ToString::to_string).collect()
+	}
+
+	#[test]
+	fn finds_constant_triggers() {
+		let mut backwards = rule("backwards", "any(book)");
+		backwards.valid_from = "2024-12-31".parse().ok();
+		backwards.valid_until = "2024-01-01".parse().ok();
+		let rules = BTreeSet::from([
+			backwards,
+			rule("contradiction", "any(book) and not any(book)"),
+			rule("tautology", "quantity >= 1"),
+			rule(
+				"redundant part",
+				"any(video) and (all(book) or not all(book))"
+			),
+			rule("fine", "any(book) and quantity >= 2 or order_size > 3"),
+			rule("never", "never"),
+			rule("always", "always")
+		]);
+		assert_eq!(
+			messages(&validate_rules(&rules, None)),
+			[
+				"error in rule 'backwards': the validity window ends on 2024-01-01 before it starts \
+				 on 2024-12-31",
+				"error in rule 'contradiction': the trigger 'any(book) and not any(book)' can \
+				 never fire",
+				"error in rule 'never': the trigger 'never' can never fire",
+				"warning in rule 'redundant part': 'all(book) or not all(book)' is always true",
+				"warning in rule 'tautology': the trigger 'quantity >= 1' always fires, use \
+				 'always'"
+			]
+		);
+	}
+
+	#[test]
+	fn finds_unused_identifiers_and_duplicates() {
+		let mut empty = rule("empty", "always");
+		empty.trigger = RuleTrigger::Identifier {
+			identifiers: IdentifierCollection(BTreeSet::new()),
+			condition:   IdentifierCondition::Any
+		};
+		let rules = BTreeSet::from([
+			rule("a", "any(book, poster)"),
+			rule("b", "any(book, poster)"),
+			empty
+		]);
+		let purchases = BTreeSet::from([purchase("1984", &["book"])]);
+		let findings = validate_rules(&rules, Some(&purchases));
+		assert_eq!(
+			messages(&findings),
+			[
+				"warning in rule 'a': no purchase has the identifier 'poster'",
+				"warning in rule 'b': no purchase has the identifier 'poster'",
+				"warning in rule 'b': has the same trigger and action as 'a'",
+				"error in rule 'empty': 'any()' doesn't list any identifiers"
+			]
+		);
+	}
+}